@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 use std::io::{Write};
 
-use exe_common::config::net::Config;
+use exe_common::config::net::{self, Config};
+use exe_common::network::native;
 
 use utils::term::Term;
 
@@ -74,7 +75,8 @@ pub fn remote_fetch( mut term: Term
                    , peers: Vec<String>
                    )
 {
-    let blockchain = Blockchain::load(root_dir, name);
+    let mut blockchain = Blockchain::load(root_dir, name);
+    blockchain.storage.enable_block_cache(::storage::DEFAULT_BLOCK_CACHE_CAPACITY);
 
     for np in blockchain.peers() {
         if peers.is_empty() || peers.contains(&np.name().to_owned()) {
@@ -87,6 +89,49 @@ pub fn remote_fetch( mut term: Term
     }
 }
 
+/// check the connectivity with the remote nodes (optionally only the
+/// given ones) of the blockchain
+///
+/// For every native remote, this connects and performs the handshake,
+/// without fetching anything. http remotes are skipped.
+///
+pub fn remote_ping( mut term: Term
+                  , root_dir: PathBuf
+                  , name: String
+                  , peers: Vec<String>
+                  )
+{
+    let blockchain = Blockchain::load(root_dir, name);
+
+    for np in blockchain.peers() {
+        if ! peers.is_empty() && ! peers.contains(&np.name().to_owned()) { continue; }
+        let peer = peer::Peer::prepare(&blockchain, np.name().to_owned());
+
+        term.info(&format!("{}", peer.name)).unwrap();
+        term.simply(" (").unwrap();
+        term.success(&format!("{}", peer.config)).unwrap();
+        term.simply("): ").unwrap();
+
+        let address = match peer.config {
+            net::Peer::Native(ref address) => address.clone(),
+            net::Peer::Http(_) => {
+                term.warn("skipped, not a native peer\n").unwrap();
+                continue;
+            }
+        };
+
+        match native::ping(blockchain.config.protocol_magic, &address, &blockchain.config.timeouts) {
+            Ok(latency) => {
+                let millis = latency.as_secs() * 1000 + latency.subsec_millis() as u64;
+                term.success(&format!("handshake ok in {}ms\n", millis)).unwrap();
+            },
+            Err(err) => {
+                term.error(&format!("handshake failed: {:?}\n", err)).unwrap();
+            },
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub enum RemoteDetail {
     Short,
@@ -1,6 +1,6 @@
 
 use exe_common;
-use exe_common::network::{api::Api, api::BlockRef};
+use exe_common::network::{api::Api, api::BlockRef, api::Fetch};
 use cardano::block::{BlockDate, EpochId, HeaderHash};
 use utils::term::Term;
 use storage::{self, tag};
@@ -82,7 +82,7 @@ impl<'a> ConnectedPeer<'a> {
         // ancestor of tip. In that case we should start from the last
         // stable epoch before our_tip.
 
-        info!("Fetching from        : {} ({})", best_tip.0.hash, best_tip.0.date);
+        info!("Fetching from        : {} ({})", best_tip.0.hash.to_short_hex(), best_tip.0.date);
 
         // Determine whether the previous epoch is stable yet. Note: This
         // assumes that k is smaller than the number of blocks in an
@@ -134,7 +134,7 @@ impl<'a> ConnectedPeer<'a> {
                 let block_raw = storage::block_read(&peer.blockchain.storage, cur_hash.bytes()).unwrap();
                 let block = block_raw.decode().unwrap();
                 let hdr = block.get_header();
-                assert!(hdr.get_blockdate().get_epochid() == first_unstable_epoch);
+                assert!(hdr.epoch() == first_unstable_epoch);
                 cur_hash = hdr.get_previous_header();
                 if hdr.get_blockdate().is_genesis() { break }
             }
@@ -180,6 +180,7 @@ impl<'a> ConnectedPeer<'a> {
             }
 
             last_block = Some(block_hash.clone());
+            Fetch::Continue
         }).unwrap();
         pbr.end();
 
@@ -224,20 +225,22 @@ impl<'a> Peer<'a> {
 
     /// initialise the connection by performing initial handshake (if necessary).
     pub fn connect(self, term: &mut Term) -> Result<ConnectedPeer<'a>, ()> {
-        let peer_handshake = exe_common::network::Peer::new(
+        let peer_handshake = exe_common::network::Peer::new_with_timeouts(
             self.blockchain.name.clone(),
             self.name.to_owned(),
             self.config.clone(),
-            self.blockchain.config.protocol_magic
+            self.blockchain.config.protocol_magic,
+            &self.blockchain.config.timeouts
         );
 
-        let connection = match peer_handshake {
+        let mut connection = match peer_handshake {
             Err(err) => {
                 term.warn(&format!("Unable to initiate handshake with peer {} ({})\n\t{:?}\n", self.name, self.config, err)).unwrap();
                 return Err(());
             },
             Ok(peer) => peer
         };
+        connection.set_header_cache(&self.blockchain.storage_config);
 
         Ok(ConnectedPeer {
             peer: self,
@@ -360,6 +363,7 @@ mod internal {
         // TODO: should test that epoch <epoch_id - 1> exists.
 
         storage::epoch::epoch_create(&storage.config, &packhash, epoch_id);
+        storage::remove_cached_headers(&storage.config, epoch_id);
 
         info!( "=> pack {} written for epoch {} in {}"
              , hex::encode(&packhash[..])
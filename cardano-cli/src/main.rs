@@ -240,6 +240,12 @@ fn subcommand_blockchain<'a>(mut term: term::Term, root_dir: PathBuf, matches: &
 
             blockchain::commands::remote_fetch(term, root_dir, name, peers);
         },
+        ("remote-ping", Some(matches)) => {
+            let name = blockchain_argument_name_match(&matches);
+            let peers = values_t!(matches, "BLOCKCHAIN_REMOTE_ALIAS", String).unwrap_or_else(|_| Vec::new());
+
+            blockchain::commands::remote_ping(term, root_dir, name, peers);
+        },
         ("remote-ls", Some(matches)) => {
             let name = blockchain_argument_name_match(&matches);
             let detailed = if matches.is_present("REMOTE_LS_DETAILED_SHORT") {
@@ -310,6 +316,14 @@ fn blockchain_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .required(false) // we allow user not to set any values here
             )
         )
+        .subcommand(SubCommand::with_name("remote-ping")
+            .about("Check the connection and the handshake with the remote nodes (optionally specified by the aliases), without fetching any block.")
+            .arg(blockchain_argument_name_definition())
+            .arg(blockchain_argument_remote_alias_definition()
+                .multiple(true)
+                .required(false)
+            )
+        )
         .subcommand(SubCommand::with_name("remote-ls")
             .about("List all the remote nodes of the given blockchain")
             .arg(blockchain_argument_name_definition())
@@ -472,6 +486,22 @@ fn wallet_argument_mnemonic_size_match<'a>(matches: &ArgMatches<'a>) -> cardano:
         _ => unreachable!() // default is "24"
     }
 }
+fn wallet_argument_account<'a, 'b>(help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name("WALLET_ACCOUNT")
+        .help(help)
+        .long("account")
+        .value_name("N")
+        .takes_value(true)
+        .validator(|account| {
+            let account = account.parse::<u32>().map_err(|err| format!("{}", err))?;
+            cardano::bip::bip44::Account::new(account).map(|_| ())
+                .map_err(|_| format!("account {} is in the hardened range, it must be less than 0x80000000", account))
+        })
+        .default_value("0")
+}
+fn wallet_argument_account_match<'a>(matches: &ArgMatches<'a>) -> u32 {
+    value_t!(matches, "WALLET_ACCOUNT", u32).unwrap_or_else(|e| e.exit())
+}
 
 const WALLET_COMMAND : &'static str = "wallet";
 
@@ -486,6 +516,15 @@ fn subcommand_wallet<'a>(mut term: term::Term, root_dir: PathBuf, matches: &ArgM
 
             wallet::commands::new(term, root_dir, name, wallet_scheme, derivation_scheme, mnemonic_length, mnemonic_langs);
         },
+        ("restore", Some(matches)) => {
+            let name = wallet_argument_name_match(&matches);
+            let wallet_scheme = wallet_argument_wallet_scheme_match(&matches);
+            let derivation_scheme = wallet_argument_derivation_scheme_match(&matches);
+            // the mnemonic phrase is given in a single language, use the first one
+            let mnemonic_lang = wallet_argument_mnemonic_language_match(&matches).into_iter().next().unwrap();
+
+            wallet::commands::restore(term, root_dir, name, wallet_scheme, derivation_scheme, mnemonic_lang);
+        },
         ("attach", Some(matches)) => {
             let name = wallet_argument_name_match(&matches);
             let blockchain = blockchain_argument_name_match(&matches);
@@ -497,6 +536,20 @@ fn subcommand_wallet<'a>(mut term: term::Term, root_dir: PathBuf, matches: &ArgM
 
             wallet::commands::detach(term, root_dir, name);
         },
+        ("address", Some(matches)) => {
+            let name = wallet_argument_name_match(&matches);
+            let account = wallet_argument_account_match(&matches);
+            let count = value_t!(matches, "ADDRESS_COUNT", u32).unwrap_or_else(|e| e.exit());
+            let is_internal = matches.is_present("ADDRESS_INTERNAL");
+
+            wallet::commands::address(term, root_dir, name, account, count, is_internal);
+        },
+        ("balance", Some(matches)) => {
+            let name = wallet_argument_name_match(&matches);
+            let account = wallet_argument_account_match(&matches);
+
+            wallet::commands::balance(term, root_dir, name, account);
+        },
         _ => {
             term.error(matches.usage()).unwrap();
             ::std::process::exit(1)
@@ -514,8 +567,12 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
             .arg(wallet_argument_mnemonic_language())
             .arg(wallet_argument_name_definition())
         )
-        .subcommand(SubCommand::with_name("recover")
-            .about("recover a wallet")
+        .subcommand(SubCommand::with_name("restore")
+            .about("restore a wallet from its mnemonic phrase")
+            .visible_alias("recover")
+            .arg(wallet_argument_derivation_scheme())
+            .arg(wallet_argument_wallet_scheme())
+            .arg(wallet_argument_mnemonic_language())
             .arg(wallet_argument_name_definition())
         )
         .subcommand(SubCommand::with_name("destroy")
@@ -531,6 +588,27 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
             .about("detach the wallet from its associated blockchain")
             .arg(wallet_argument_name_definition())
         )
+        .subcommand(SubCommand::with_name("address")
+            .about("print the addresses of the given account of a wallet (BIP44 wallets only)")
+            .arg(wallet_argument_name_definition())
+            .arg(wallet_argument_account("the account index to generate the addresses from"))
+            .arg(Arg::with_name("ADDRESS_COUNT")
+                .help("the number of addresses to print")
+                .long("count")
+                .value_name("M")
+                .takes_value(true)
+                .default_value("20")
+            )
+            .arg(Arg::with_name("ADDRESS_INTERNAL")
+                .help("print the internal (change) addresses instead of the external ones")
+                .long("internal")
+            )
+        )
+        .subcommand(SubCommand::with_name("balance")
+            .about("compute the balance of the given account of a wallet (BIP44 wallets only)")
+            .arg(wallet_argument_name_definition())
+            .arg(wallet_argument_account("the account index to compute the balance of"))
+        )
         .subcommand(SubCommand::with_name("sync")
             .about("synchronize the wallet with the attached blockchain")
             .arg(Arg::with_name("DRY_RUN")
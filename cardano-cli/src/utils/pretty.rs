@@ -275,6 +275,7 @@ impl Pretty for normal::Consensus {
         Val::Tree(vec![
             ("slot", self.slot_id.to_pretty()),
             ("leader key", self.leader_key.to_pretty()),
+            ("leader stakeholder", from_display(self.leader_stakeholder_id())),
             ("chain difficulty", self.chain_difficulty.to_pretty()),
             ("block signature", self.block_signature.to_pretty()),
         ])
@@ -303,7 +304,6 @@ impl Pretty for types::SlotId {
     }
 }
 
-// XXX: EpochId is only a type alias; make sure this instance isn't used for any u32
 impl Pretty for types::EpochId {
     fn to_pretty(&self) -> Val {
         Val::BlockDate(BlockDate::Genesis(*self))
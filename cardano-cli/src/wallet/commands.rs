@@ -1,8 +1,9 @@
 use super::config::{encrypt_primary_key, Config, HDWalletModel};
 use super::{Wallet};
 
-use std::{path::PathBuf};
-use cardano::{hdwallet::{self, DerivationScheme}, wallet, bip::bip39};
+use std::{io, path::PathBuf};
+use cardano::{hdwallet::{self, DerivationScheme}, wallet::{self, bip44, balance::Balance, scheme::{Wallet as WalletScheme}}, bip::bip39, block::{Block}, util::base58};
+use storage;
 use rand::random;
 
 use utils::term::Term;
@@ -50,11 +51,84 @@ pub fn new<D>( mut term: Term
         term.simply(&format!("{}\n", mnemonic_phrase)).unwrap();
     }
 
-    // 3. normalize the seed to make it a valid private key
+    // 3. create and save the wallet
+    let wallet = create_wallet(&mut term, root_dir, name, config, seed);
+
+    term.success(&format!("wallet `{}' successfully created.\n", &wallet.name)).unwrap();
+}
+
+/// function to restore a wallet from its mnemonic phrase
+///
+pub fn restore<D>( mut term: Term
+                 , root_dir: PathBuf
+                 , name: String
+                 , wallet_scheme: HDWalletModel
+                 , derivation_scheme: DerivationScheme
+                 , language: D
+                 )
+    where D: bip39::dictionary::Language
+{
+    let config = Config {
+        attached_blockchain: None,
+        derivation_scheme: derivation_scheme,
+        hdwallet_model: wallet_scheme
+    };
+
+    // 1. retrieve the entropy from the mnemonics
+
+    let mnemonic_phrase = term.password(&format!("mnemonic phrase ({}): ", language.name())).unwrap();
+    let entropy = match entropy_from_mnemonic_phrase(&language, &mnemonic_phrase) {
+        Ok(entropy) => entropy,
+        Err(bip39::Error::WrongNumberOfWords(count)) => {
+            term.error(&format!("Invalid number of mnemonic words: {}, expected 12, 15, 18, 21 or 24 words.\n", count)).unwrap();
+            ::std::process::exit(1);
+        },
+        Err(bip39::Error::InvalidChecksum(_, _)) => {
+            term.error("Invalid mnemonic phrase checksum, some words are probably misspelled or in the wrong order.\n").unwrap();
+            ::std::process::exit(1);
+        },
+        Err(err) => {
+            term.error(&format!("Invalid mnemonic phrase: {}\n", err)).unwrap();
+            ::std::process::exit(1);
+        }
+    };
+
+    // 2. perform the seed generation from the entropy
+
+    let recovery_password = term.password("recovery password: ").unwrap();
+
+    let mut seed = [0;hdwallet::XPRV_SIZE];
+    wallet::keygen::generate_seed(&entropy, recovery_password.as_bytes(), &mut seed);
+
+    // 3. create and save the wallet
+    let wallet = create_wallet(&mut term, root_dir, name, config, seed);
+
+    term.success(&format!("wallet `{}' successfully restored.\n", &wallet.name)).unwrap();
+}
+
+fn entropy_from_mnemonic_phrase<D>(language: &D, mnemonic_phrase: &str) -> bip39::Result<bip39::Entropy>
+    where D: bip39::dictionary::Language
+{
+    // be lenient with the spaces the user may have typed between the words
+    let words : Vec<&str> = mnemonic_phrase.split_whitespace().collect();
+    let mnemonics = bip39::Mnemonics::from_string(language, &words.join(language.separator()))?;
+    bip39::Entropy::from_mnemonics(&mnemonics)
+}
+
+/// normalize the given seed into the wallet's private key, encrypt it
+/// with the spending password and save the new wallet.
+fn create_wallet( term: &mut Term
+                , root_dir: PathBuf
+                , name: String
+                , config: Config
+                , seed: [u8;hdwallet::XPRV_SIZE]
+                ) -> Wallet
+{
+    // 1. normalize the seed to make it a valid private key
 
     let xprv = hdwallet::XPrv::normalize_bytes(seed);
 
-    // 4. encrypt the private key
+    // 2. encrypt the private key
     term.info("Set a wallet password. This is for local usage only, allows you to protect your cached private key and prevent from creating non desired transactions.\n").unwrap();
     let password              = term.password("spending password: ").unwrap();
     let password_confirmation = term.password("confirm password: ").unwrap();
@@ -64,13 +138,13 @@ pub fn new<D>( mut term: Term
     }
     let encrypted_xprv = encrypt_primary_key(password.as_bytes(), &xprv);
 
-    // 5. create the wallet
+    // 3. create the wallet
     let wallet = Wallet::new(root_dir, name, config, encrypted_xprv);
 
-    // 6. save the wallet
+    // 4. save the wallet
     wallet.save();
 
-    term.success(&format!("wallet `{}' successfully created.\n", &wallet.name)).unwrap();
+    wallet
 }
 
 pub fn attach( mut term: Term
@@ -140,3 +214,216 @@ pub fn detach( mut term: Term
 
     term.success("Wallet successfully attached to blockchain.").unwrap()
 }
+
+/// print the `count` first addresses of the given `account` of the wallet
+///
+/// only the BIP44 wallets are supported as the addresses of the random
+/// indices wallets cannot be enumerated.
+pub fn address( mut term: Term
+              , root_dir: PathBuf
+              , name: String
+              , account: u32
+              , count: u32
+              , is_internal: bool
+              )
+{
+    // load the wallet
+    let wallet = Wallet::load(root_dir, name);
+
+    // 1. check the wallet model
+    if let HDWalletModel::RandomIndex2Levels = wallet.config.hdwallet_model {
+        term.error("Cannot list the addresses of a random index wallet\n").unwrap();
+        ::std::process::exit(1);
+    }
+
+    // 2. retrieve the wallet's private key
+    let password = term.password("spending password: ").unwrap();
+    let mut bip44_wallet = match wallet.get_wallet_bip44(password.as_bytes()) {
+        Ok(bip44_wallet) => bip44_wallet,
+        Err(_) => {
+            term.error("Cannot retrieve the wallet's private key, invalid password?\n").unwrap();
+            ::std::process::exit(1);
+        }
+    };
+
+    // 3. generate and print the addresses
+    let addr_type = if is_internal { bip44::AddrType::Internal } else { bip44::AddrType::External };
+    for address in bip44_addresses(&mut bip44_wallet, account, addr_type, count) {
+        term.simply(&format!("{}\n", address)).unwrap();
+    }
+}
+
+/// compute the balance of the given `account` of the wallet
+///
+/// go through the epochs stored in the attached blockchain and sum up the
+/// outputs paying to the account that have not been spent yet.
+pub fn balance( mut term: Term
+              , root_dir: PathBuf
+              , name: String
+              , account: u32
+              )
+{
+    // load the wallet
+    let wallet = Wallet::load(root_dir.clone(), name);
+
+    // 1. check the wallet model
+    if let HDWalletModel::RandomIndex2Levels = wallet.config.hdwallet_model {
+        term.error("Cannot compute the balance of a random index wallet\n").unwrap();
+        ::std::process::exit(1);
+    }
+
+    // 2. get the wallet's blockchain
+    let blockchain = match wallet.config.attached_blockchain {
+        None => {
+            term.error("Wallet is not attached to any blockchain\n").unwrap();
+            ::std::process::exit(1);
+        },
+        Some(ref blockchain) => {
+            Blockchain::load(root_dir, blockchain.clone())
+        }
+    };
+
+    // 3. retrieve the account's public key
+    let password = term.password("spending password: ").unwrap();
+    let mut bip44_wallet = match wallet.get_wallet_bip44(password.as_bytes()) {
+        Ok(bip44_wallet) => bip44_wallet,
+        Err(_) => {
+            term.error("Cannot retrieve the wallet's private key, invalid password?\n").unwrap();
+            ::std::process::exit(1);
+        }
+    };
+    let account = bip44_wallet.create_account(&format!("{}", account), account).public();
+    let mut lookup = bip44::AddressLookup::new(account, bip44::DEFAULT_GAP_LIMIT);
+
+    let mut balance = Balance::new();
+    let mut apply_block = |block: &Block| {
+        if let Some(txs) = block.get_transactions() {
+            for txaux in txs.iter() {
+                balance.apply_tx(&txaux.tx, |address| lookup.is_ours(address));
+            }
+        }
+    };
+
+    // 4. go through the packed epochs, in order
+    let mut epoch_id = blockchain.config.epoch_start;
+    loop {
+        let blocks = match storage::epoch_blocks(&blockchain.storage, epoch_id) {
+            Ok(blocks) => blocks,
+            Err(storage::Error::BlockError(storage::block::Error::IoError(ref err))) if err.kind() == io::ErrorKind::NotFound => break,
+            Err(err) => {
+                term.error(&format!("Cannot read the blocks of the epoch {}: {:?}\n", epoch_id, err)).unwrap();
+                ::std::process::exit(1);
+            }
+        };
+        for block in blocks { apply_block(&block); }
+        epoch_id += 1;
+    }
+
+    // 5. then the blocks of the epochs not packed yet, from the tip back
+    //    to the last packed epoch
+    let (tip, is_genesis) = blockchain.load_tip();
+    if ! is_genesis && tip.date.get_epochid() >= epoch_id {
+        let blocks = match storage::iter_back_from(&blockchain.storage, &tip.hash, &blockchain.config.genesis_prev) {
+            Ok(blocks) => blocks,
+            Err(err) => {
+                term.error(&format!("Cannot read the blocks from the tip {}: {:?}\n", tip.hash, err)).unwrap();
+                ::std::process::exit(1);
+            }
+        };
+        let mut unpacked = Vec::new();
+        for block in blocks {
+            match block {
+                Ok(block) => {
+                    if block.get_header().get_blockdate().get_epochid() < epoch_id { break }
+                    unpacked.push(block);
+                },
+                Err(err) => {
+                    term.error(&format!("Cannot read the blocks from the tip {}: {:?}\n", tip.hash, err)).unwrap();
+                    ::std::process::exit(1);
+                }
+            }
+        }
+        for block in unpacked.iter().rev() { apply_block(block); }
+    }
+
+    match balance.total() {
+        Ok(total) => term.simply(&format!("{}\n", total)).unwrap(),
+        Err(err) => {
+            term.error(&format!("Cannot compute the wallet's balance: {}\n", err)).unwrap();
+            ::std::process::exit(1);
+        }
+    }
+}
+
+fn bip44_addresses(wallet: &mut bip44::Wallet, account: u32, addr_type: bip44::AddrType, count: u32) -> Vec<String> {
+    let account = wallet.create_account(&format!("{}", account), account);
+    account.gen_addresses(addr_type, 0, count).iter()
+        .map(|address| base58::encode(&address.to_bytes()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MNEMONICS : &'static str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn wallet() -> bip44::Wallet {
+        let mnemonics = bip39::MnemonicString::new(&bip39::dictionary::ENGLISH, MNEMONICS.to_owned()).unwrap();
+        bip44::Wallet::from_bip39_mnemonics(&mnemonics, b"", DerivationScheme::V2)
+    }
+
+    #[test]
+    fn restore_from_mnemonic_phrase() {
+        // extra spaces are not significant
+        let phrase = format!("  {}\n", MNEMONICS.replace(" ", "  "));
+        let entropy = entropy_from_mnemonic_phrase(&bip39::dictionary::ENGLISH, &phrase).unwrap();
+        let mut wallet = bip44::Wallet::from_entropy(&entropy, b"", DerivationScheme::V2);
+
+        assert_eq!(
+            bip44_addresses(&mut wallet, 0, bip44::AddrType::External, 1),
+            vec!["Ae2tdPwUPEZKcVUy5JAhPjdXa6PuWMnHDgjWdK4ZyGK33L8YWjBv2saUwaa"]
+        );
+    }
+
+    #[test]
+    fn restore_from_invalid_mnemonic_phrase() {
+        let too_short = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        match entropy_from_mnemonic_phrase(&bip39::dictionary::ENGLISH, too_short) {
+            Err(bip39::Error::WrongNumberOfWords(11)) => {},
+            r => panic!("expected WrongNumberOfWords(11), got {:?}", r.map(|_| ())),
+        }
+
+        let bad_checksum = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        match entropy_from_mnemonic_phrase(&bip39::dictionary::ENGLISH, bad_checksum) {
+            Err(bip39::Error::InvalidChecksum(_, _)) => {},
+            r => panic!("expected InvalidChecksum, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn bip44_addresses_are_deterministic() {
+        let mut wallet = wallet();
+
+        assert_eq!(
+            bip44_addresses(&mut wallet, 0, bip44::AddrType::External, 3),
+            vec![ "Ae2tdPwUPEZGtHjfB4RrCJhsv7c7TZ5iGR6XVDXoeTcNdZ3NTdDrdCFds7d"
+                , "Ae2tdPwUPEZ2Zrwoj1Qe2tUWQUFg9Ft462FsjPjNQ2pM2rQsvj2bbBMxyjh"
+                , "Ae2tdPwUPEYyyBbqXLvjKpV2R2QbjmEzszBb8Ebwe4HpUjUqRw97cWh2gRb"
+                ]
+        );
+        assert_eq!(
+            bip44_addresses(&mut wallet, 0, bip44::AddrType::Internal, 1),
+            vec!["Ae2tdPwUPEZGypBzVUdagHdV6dje32xbnBQ17B4JKkMds5mocy3VyxpG7LE"]
+        );
+        assert_eq!(
+            bip44_addresses(&mut wallet, 1, bip44::AddrType::External, 1),
+            vec!["Ae2tdPwUPEYwUJjJHgjFBX6t55LbgGV8fxXsRbjGMgAsnGyY5owFog6jsdo"]
+        );
+        // the same request gives the same addresses
+        assert_eq!(
+            bip44_addresses(&mut wallet, 0, bip44::AddrType::External, 3),
+            bip44_addresses(&mut wallet, 0, bip44::AddrType::External, 3)
+        );
+    }
+}
@@ -3,10 +3,10 @@ use std::fmt;
 use serde;
 
 use cryptoxide::digest::Digest;
-use cryptoxide::blake2b::Blake2b;
 use cryptoxide::sha3::Sha3;
 
 use redeem;
+use hash::{Hash28, HASH_SIZE_224};
 use util::{base58, hex};
 use cbor;
 use cbor_event::{self, de::RawCbor, se::{Serializer}};
@@ -15,31 +15,27 @@ use hdpayload::{HDAddressPayload};
 
 /// Digest of the composition of `Blake2b_224 . Sha3_256`
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
-pub struct DigestBlake2b224([u8;28]);
+pub struct DigestBlake2b224([u8;HASH_SIZE_224]);
 impl DigestBlake2b224 {
     /// create digest from the given inputs by computing the SHA3_256 and
     /// then the Blake2b_224.
     ///
     pub fn new(buf: &[u8]) -> Self
     {
-        let mut b2b = Blake2b::new(28);
         let mut sh3 = Sha3::sha3_256();
         let mut out1 = [0;32];
-        let mut out2 = [0;28];
         sh3.input(buf);
         sh3.result(&mut out1);
-        b2b.input(&out1);
-        b2b.result(&mut out2);
-        DigestBlake2b224::from_bytes(out2)
+        DigestBlake2b224::from_bytes(Hash28::new(&out1).into_bytes())
     }
 
     /// create a Digest from the given 224 bits
-    pub fn from_bytes(bytes :[u8;28]) -> Self { DigestBlake2b224(bytes) }
+    pub fn from_bytes(bytes :[u8;HASH_SIZE_224]) -> Self { DigestBlake2b224(bytes) }
     pub fn from_slice(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() != 28 { return None; }
-        let mut buf = [0;28];
+        if bytes.len() != HASH_SIZE_224 { return None; }
+        let mut buf = [0;HASH_SIZE_224];
 
-        buf[0..28].clone_from_slice(bytes);
+        buf[0..HASH_SIZE_224].clone_from_slice(bytes);
         Some(DigestBlake2b224::from_bytes(buf))
     }
 }
@@ -377,6 +373,61 @@ impl ExtendedAddr {
         let mut raw = RawCbor::from(buf);
         cbor_event::de::Deserialize::deserialize(&mut raw)
     }
+
+    /// same as `from_bytes` but only accept the canonical encoding of the
+    /// address, as the nodes do: the decoded address must encode back to
+    /// the same bytes (e.g. the attributes keys must be in order and
+    /// there must be no trailing bytes). A non canonical encoding is
+    /// reported as `NonCanonical` with the offset of the first differing byte.
+    ///
+    /// `from_bytes` remains the lenient decoding.
+    pub fn decode_strict(buf: &[u8]) -> cbor_event::Result<Self> {
+        let addr = ExtendedAddr::from_bytes(buf)?;
+        let canonical = addr.to_bytes();
+        if canonical != buf {
+            // offset of the first byte differing from the canonical encoding
+            let offset = canonical.iter().zip(buf.iter()).position(|(a, b)| a != b)
+                .unwrap_or(canonical.len().min(buf.len()));
+            return Err(cbor_event::Error::NonCanonical(offset));
+        }
+        Ok(addr)
+    }
+
+    /// the canonical base58 encoding of the address (with its `crc32`),
+    /// as displayed to the users.
+    ///
+    /// ```
+    /// use cardano::address::{ExtendedAddr};
+    ///
+    /// let s = "DdzFFzCqrhsi8XFMabbnHecVusaebqQCkXTqDnCumx5esKB1pk1zbhX5BtdAivZbQePFVujgzNCpBVXactPSmphuHRC5Xk8qmBd49QjW";
+    /// let ea : ExtendedAddr = s.parse().unwrap();
+    /// assert_eq!(ea.to_base58(), s);
+    /// ```
+    pub fn to_base58(&self) -> String {
+        base58::encode(&self.to_bytes())
+    }
+}
+
+/// error parsing an `ExtendedAddr` from its base58 encoding
+#[derive(Debug)]
+pub enum ParseExtendedAddrError {
+    Base58Error(base58::Error),
+    EncodingError(cbor_event::Error),
+}
+impl fmt::Display for ParseExtendedAddrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ParseExtendedAddrError::Base58Error(ref err) => write!(f, "invalid base58: {}", err),
+            &ParseExtendedAddrError::EncodingError(ref err) => write!(f, "invalid address encoding: {}", err),
+        }
+    }
+}
+impl ::std::str::FromStr for ExtendedAddr {
+    type Err = ParseExtendedAddrError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = base58::decode(s).map_err(ParseExtendedAddrError::Base58Error)?;
+        ExtendedAddr::from_bytes(&bytes).map_err(ParseExtendedAddrError::EncodingError)
+    }
 }
 impl cbor_event::se::Serialize for ExtendedAddr {
     fn serialize<W: ::std::io::Write>(&self, serializer: Serializer<W>) -> cbor_event::Result<Serializer<W>> {
@@ -387,10 +438,7 @@ impl cbor_event::de::Deserialize for ExtendedAddr {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
         let bytes = cbor::hs::util::raw_with_crc32(raw)?;
         let mut raw = RawCbor::from(&bytes);
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid ExtendedAddr: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "ExtendedAddr")?;
         let addr = cbor_event::de::Deserialize::deserialize(&mut raw)?;
         let attributes = cbor_event::de::Deserialize::deserialize(&mut raw)?;
         let addr_type = cbor_event::de::Deserialize::deserialize(&mut raw)?;
@@ -400,7 +448,7 @@ impl cbor_event::de::Deserialize for ExtendedAddr {
 }
 impl fmt::Display for ExtendedAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", base58::encode(&self.to_bytes()))
+        write!(f, "{}", self.to_base58())
     }
 }
 impl serde::Serialize for ExtendedAddr
@@ -409,11 +457,10 @@ impl serde::Serialize for ExtendedAddr
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: serde::Serializer,
     {
-        let vec = self.to_bytes();
         if serializer.is_human_readable() {
-            serializer.serialize_str(&base58::encode(&vec))
+            serializer.serialize_str(&self.to_base58())
         } else {
-            serializer.serialize_bytes(&vec)
+            serializer.serialize_bytes(&self.to_bytes())
         }
     }
 }
@@ -564,6 +611,49 @@ mod tests {
         assert_eq!(ea, r);
     }
 
+    #[test]
+    fn decode_strict_rejects_reordered_attributes() {
+        use cbor_event::{se::Serializer, Len};
+        use crc32::crc32;
+
+        let seed = hdwallet::Seed::from_bytes([0;hdwallet::SEED_SIZE]);
+        let pk = hdwallet::XPrv::generate_from_seed(&seed).public();
+        let hdap = HDAddressPayload::from_vec(vec![1,2,3,4,5]);
+        let ea = ExtendedAddr::new(AddrType::ATPubKey, SpendingData::PubKeyASD(pk.clone()), Attributes::new_single_key(&pk, Some(hdap.clone())));
+
+        let canonical = ea.to_bytes();
+        assert_eq!(ExtendedAddr::decode_strict(&canonical).unwrap(), ea);
+
+        // the same address, with the derivation path before the stake distribution
+        let payload = Serializer::new_vec()
+            .write_array(Len::Len(3)).unwrap()
+            .serialize(&ea.addr).unwrap()
+            .write_map(Len::Len(2)).unwrap()
+            .write_unsigned_integer(1).unwrap().serialize(&hdap).unwrap()
+            .write_unsigned_integer(0).unwrap().serialize(&ea.attributes.stake_distribution).unwrap()
+            .serialize(&ea.addr_type).unwrap()
+            .finalize();
+        let reordered = Serializer::new_vec()
+            .write_array(Len::Len(2)).unwrap()
+            .write_tag(24).unwrap().write_bytes(&payload).unwrap()
+            .write_unsigned_integer(crc32(&payload) as u64).unwrap()
+            .finalize();
+
+        assert_eq!(ExtendedAddr::from_bytes(&reordered).unwrap(), ea);
+        match ExtendedAddr::decode_strict(&reordered) {
+            Err(cbor_event::Error::NonCanonical(offset)) => assert!(offset > 0 && offset < reordered.len()),
+            r => panic!("expected NonCanonical, got {:?}", r),
+        }
+
+        // trailing bytes are not canonical either
+        let mut trailing = canonical.clone();
+        trailing.push(0);
+        match ExtendedAddr::decode_strict(&trailing) {
+            Err(cbor_event::Error::NonCanonical(offset)) => assert_eq!(offset, canonical.len()),
+            r => panic!("expected NonCanonical, got {:?}", r),
+        }
+    }
+
     #[test]
     fn encode_decode_digest_blake2b() {
         let digest = DigestBlake2b224::new(b"some random bytes...");
@@ -624,6 +714,31 @@ mod tests {
         assert_eq!(r.attributes.stake_distribution, StakeDistribution::BootstrapEraDistr);
     }
 
+    #[test]
+    fn base58_round_trip() {
+        let seed = hdwallet::Seed::from_bytes([0;32]);
+        let pk = hdwallet::XPrv::generate_from_seed(&seed).public();
+        let hdap = HDAddressPayload::from_vec(vec![1,2,3,4,5]);
+        let addresses = vec![ ExtendedAddr::new_simple(pk.clone())
+                            , ExtendedAddr::new(AddrType::ATPubKey, SpendingData::PubKeyASD(pk.clone()), Attributes::new_single_key(&pk, Some(hdap)))
+                            ];
+        for ea in addresses {
+            let s = ea.to_base58();
+            assert_eq!(s, format!("{}", ea));
+            assert_eq!(s.parse::<ExtendedAddr>().unwrap(), ea);
+        }
+
+        match "DdzFF0".parse::<ExtendedAddr>() {
+            Err(ParseExtendedAddrError::Base58Error(_)) => {},
+            r => panic!("expected a base58 error, got {:?}", r),
+        }
+        // valid base58, but not an address
+        match base58::encode(&[1, 2, 3]).parse::<ExtendedAddr>() {
+            Err(ParseExtendedAddrError::EncodingError(_)) => {},
+            r => panic!("expected an encoding error, got {:?}", r),
+        }
+    }
+
     #[test]
     fn decode_address_no_derivation_path() {
         let bytes     = vec![0x82, 0xd8, 0x18, 0x58, 0x21, 0x83, 0x58, 0x1c, 0x10, 0x2a, 0x74, 0xca, 0x44, 0x05, 0xb8, 0xc1, 0x8d, 0x20, 0x84, 0x1e, 0x8c, 0x66, 0x4f, 0xe1, 0xde, 0x7d, 0x66, 0x07, 0x48, 0x08, 0x70, 0x4f, 0x91, 0x79, 0xe0, 0xfa, 0xa0, 0x00, 0x1a, 0xad, 0xf7, 0x10, 0x68];
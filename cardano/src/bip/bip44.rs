@@ -17,7 +17,7 @@
 //! ```
 
 use hdpayload::{Path};
-use std::{fmt, result};
+use std::{fmt, result, convert::TryFrom};
 use serde;
 
 /// the BIP44 derivation path has a specific length
@@ -340,6 +340,35 @@ impl Addressing {
         Ok(addr)
     }
 
+    /// compact form of the addressing: the account, the change and the
+    /// index as big endian `u32`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cardano::bip::bip44::{Addressing, AddrType};
+    ///
+    /// let addr = Addressing::new(1, AddrType::Internal).unwrap().incr(2).unwrap();
+    ///
+    /// assert_eq!(addr.to_bytes(), [0,0,0,1, 0,0,0,1, 0,0,0,2]);
+    /// assert_eq!(Addressing::from_bytes(&addr.to_bytes()), Ok(addr));
+    /// ```
+    pub fn to_bytes(&self) -> [u8;12] {
+        let mut bytes = [0;12];
+        write_u32_be(&mut bytes[0..4], self.account.get_account_number());
+        write_u32_be(&mut bytes[4..8], self.change);
+        write_u32_be(&mut bytes[8..12], self.index.get_scheme_value());
+        bytes
+    }
+
+    /// read the compact form of `to_bytes`, checking the bounds of
+    /// the account, the change and the index.
+    pub fn from_bytes(bytes: &[u8;12]) -> Result<Self> {
+        let account = Account::new(read_u32_be(&bytes[0..4]))?;
+        let change = Change::new(account, read_u32_be(&bytes[4..8]))?;
+        Addressing::new_from_change(change, read_u32_be(&bytes[8..12]))
+    }
+
     /// generate a sequence of Addressing from the given
     /// addressing as starting point up to the `chunk_size`.
     ///
@@ -358,3 +387,63 @@ impl Addressing {
         Ok(v)
     }
 }
+
+fn write_u32_be(out: &mut [u8], v: u32) {
+    out[0] = (v >> 24) as u8;
+    out[1] = (v >> 16) as u8;
+    out[2] = (v >> 8) as u8;
+    out[3] = v as u8;
+}
+fn read_u32_be(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |acc, b| acc << 8 | *b as u32)
+}
+
+impl TryFrom<Path> for Addressing {
+    type Error = Error;
+
+    /// same as `Addressing::from_path`
+    fn try_from(path: Path) -> Result<Self> { Addressing::from_path(path) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn addressing_try_from_path() {
+        let path = Path::new(vec![BIP44_PURPOSE, BIP44_COIN_TYPE, 0, 1, 42]);
+        let addressing = Addressing::try_from(path).unwrap();
+        assert_eq!(addressing.account.get_account_number(), 0);
+        assert_eq!(addressing.address_type(), AddrType::Internal);
+        assert_eq!(addressing.index, Index::new(42).unwrap());
+    }
+
+    #[test]
+    fn addressing_try_from_invalid_path() {
+        let path = Path::new(vec![0x8000002B, BIP44_COIN_TYPE, 0, 1, 42]);
+        assert_eq!(Addressing::try_from(path), Err(Error::InvalidPurpose(0x8000002B)));
+
+        let path = Path::new(vec![BIP44_PURPOSE, BIP44_COIN_TYPE, 0, 1]);
+        assert_eq!(Addressing::try_from(path), Err(Error::InvalidLength(4)));
+    }
+
+    #[test]
+    fn addressing_bytes_round_trip() {
+        let addressings = [
+            Addressing::new(0, AddrType::External).unwrap(),
+            Addressing::new(0x7fffffff, AddrType::Internal).unwrap().incr(0x7fffffff).unwrap(),
+            Addressing::new(0x01020304, AddrType::Internal).unwrap().incr(0x05060708).unwrap(),
+        ];
+        for addressing in addressings.iter() {
+            assert_eq!(Addressing::from_bytes(&addressing.to_bytes()), Ok(*addressing));
+        }
+        assert_eq!(addressings[2].to_bytes(), [1,2,3,4, 0,0,0,1, 5,6,7,8]);
+    }
+
+    #[test]
+    fn addressing_from_bytes_out_of_bound() {
+        assert_eq!(Addressing::from_bytes(&[0x80,0,0,0, 0,0,0,0, 0,0,0,0]), Err(Error::AccountOutOfBound(0x80000000)));
+        assert_eq!(Addressing::from_bytes(&[0,0,0,0, 0x80,0,0,1, 0,0,0,0]), Err(Error::ChangeOutOfBound(0x80000001)));
+        assert_eq!(Addressing::from_bytes(&[0,0,0,0, 0,0,0,0, 0xff,0xff,0xff,0xff]), Err(Error::IndexOutOfBound(0xffffffff)));
+    }
+}
@@ -1,13 +1,25 @@
 use {address, hash::{Blake2b256}};
 use config::{ProtocolMagic};
-use std::{fmt};
+use std::{fmt, collections::BTreeMap};
 
 use cbor_event::{self, de::RawCbor};
 use super::types;
 use super::types::{HeaderHash, ChainDifficulty};
 
-#[derive(Debug, Clone)]
+/// the proof of a genesis block's body: the hash of its slot leaders
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BodyProof(Blake2b256);
+impl BodyProof {
+    pub fn new(hash: Blake2b256) -> Self { BodyProof(hash) }
+
+    /// compute the proof of the given body
+    pub fn from_body(body: &Body) -> Self {
+        BodyProof(Blake2b256::new(&cbor!(body).expect("serialize the slot leaders")))
+    }
+}
+impl AsRef<Blake2b256> for BodyProof {
+    fn as_ref(&self) -> &Blake2b256 { &self.0 }
+}
 
 impl cbor_event::se::Serialize for BodyProof {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
@@ -61,7 +73,7 @@ pub struct BlockHeader {
 impl fmt::Display for BlockHeader {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!( f
-            , "Magic: 0x{:?} Previous Header: {}"
+            , "Magic: 0x{:x} Previous Header: {}"
             , self.protocol_magic
             , self.previous_header
             )
@@ -90,10 +102,7 @@ impl cbor_event::se::Serialize for BlockHeader {
 }
 impl cbor_event::de::Deserialize for BlockHeader {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(5) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid BlockHeader: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(5, "BlockHeader")?;
         let p_magic    = cbor_event::de::Deserialize::deserialize(raw)?;
         let prv_header = cbor_event::de::Deserialize::deserialize(raw)?;
         let body_proof = cbor_event::de::Deserialize::deserialize(raw)?;
@@ -111,6 +120,33 @@ pub struct Block {
     pub extra: cbor_event::Value
 }
 
+impl Block {
+    /// the stakeholder allowed to create the block of the given slot of
+    /// the epoch, `None` if the slot is not part of the epoch.
+    pub fn leader_for_slot(&self, slot: u32) -> Option<address::StakeholderId> {
+        self.body.slot_leaders.get(slot as usize).cloned()
+    }
+
+    /// the number of slot leaders of the epoch, i.e. the number of slots
+    pub fn slot_leader_count(&self) -> usize {
+        self.body.slot_leaders.len()
+    }
+
+    /// the attributes of the block's extra data, see
+    /// [`types::block_extra_attributes`](../types/fn.block_extra_attributes.html)
+    pub fn extra_attributes(&self) -> cbor_event::Result<&BTreeMap<cbor_event::ObjectKey, cbor_event::Value>> {
+        types::block_extra_attributes(&self.extra)
+    }
+
+    /// check the slot leaders of the body match the header's body proof.
+    ///
+    /// There is no special case for an all-zero proof: like any other
+    /// proof, it is only valid if it is the hash of the slot leaders.
+    pub fn verify_body_proof(&self) -> bool {
+        BodyProof::from_body(&self.body) == self.header.body_proof
+    }
+}
+
 impl fmt::Display for Block {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "{}", self.header)?;
@@ -127,13 +163,11 @@ impl cbor_event::se::Serialize for Block {
 }
 impl cbor_event::de::Deserialize for Block {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Block: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "Block")?;
         let header = raw.deserialize()?;
         let body   = raw.deserialize()?;
         let extra  = raw.deserialize()?;
+        types::block_extra_attributes(&extra)?;
         Ok(Block { header, body, extra })
     }
 }
@@ -146,18 +180,123 @@ pub struct Consensus {
 impl cbor_event::se::Serialize for Consensus {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
         serializer.write_array(cbor_event::Len::Len(2))?
-            .write_unsigned_integer(self.epoch as u64)?
+            .serialize(&self.epoch)?
             .serialize(&self.chain_difficulty)
     }
 }
 impl cbor_event::de::Deserialize for Consensus {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Consensus: recieved array of {:?} elements", len)));
-        }
-        let epoch = raw.unsigned_integer()? as u32;
+        raw.tuple(2, "Consensus")?;
+        let epoch = raw.deserialize()?;
         let chain_difficulty = cbor_event::de::Deserialize::deserialize(raw)?;
         Ok(Consensus { epoch, chain_difficulty })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use block::test_vectors::{GENESIS_BLOCK_HEADER};
+    use hdwallet;
+    use testing;
+
+    fn stakeholder(seed: u8) -> address::StakeholderId {
+        let xprv = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([seed;hdwallet::SEED_SIZE]));
+        address::StakeholderId::new(&xprv.public())
+    }
+
+    #[test]
+    fn leader_for_slot() {
+        let leaders : Vec<_> = (0..3).map(stakeholder).collect();
+        let block = testing::decode_genesis(&testing::genesis_block_with_leaders(1, 0, &HeaderHash::new(&[]), &leaders));
+
+        assert_eq!(block.leader_for_slot(0), Some(leaders[0]));
+        assert_eq!(block.leader_for_slot(2), Some(leaders[2]));
+        assert_eq!(block.leader_for_slot(3), None);
+        assert_eq!(block.leader_for_slot(::std::u32::MAX), None);
+        assert_eq!(block.slot_leader_count(), 3);
+    }
+
+    #[test]
+    fn verify_body_proof() {
+        let header : BlockHeader = RawCbor::from(&GENESIS_BLOCK_HEADER[2..]).deserialize().unwrap();
+        let mut block = Block {
+            header: header,
+            body: Body { slot_leaders: vec![] },
+            extra: RawCbor::from(&[0x81, 0xa0][..]).deserialize().unwrap(),
+        };
+        // the proof of the test vector is not the one of an empty body
+        assert!(! block.verify_body_proof());
+
+        // the slot leaders are encoded as an indefinite array
+        let empty = "afc0da64183bf2664f3d4eec7238d524ba607faeeab24fc100eb861dba69971b";
+        assert_eq!(format!("{}", BodyProof::from_body(&block.body).as_ref()), empty);
+        block.header.body_proof = BodyProof::from_body(&block.body);
+        assert!(block.verify_body_proof());
+
+        block.body.slot_leaders = (0..3).map(stakeholder).collect();
+        assert!(! block.verify_body_proof());
+        block.header.body_proof = BodyProof::from_body(&block.body);
+        assert!(block.verify_body_proof());
+
+        // the proof survives the round trip
+        let decoded : Block = RawCbor::from(&cbor!(&block).unwrap()).deserialize().unwrap();
+        assert!(decoded.verify_body_proof());
+
+        block.header.body_proof = BodyProof::new(Blake2b256::from_bytes([0;32]));
+        assert!(! block.verify_body_proof());
+    }
+
+    // an epoch boundary block with 3 slot leaders, encoded and hashed
+    // outside of this crate (Blake2b-256 of the body's CBOR)
+    const ENCODED_BLOCK : &'static [u8] =
+    &[ 0x83, 0x85, 0x00, 0x58, 0x20, 0xc4, 0xe0, 0xfc, 0x3a, 0x4f, 0xfb, 0x31, 0x91, 0xf8, 0x8b, 0x26
+     , 0xa9, 0x83, 0x44, 0x53, 0xcb, 0xac, 0x0e, 0x6b, 0x9c, 0x8d, 0x8f, 0x7a, 0xe8, 0x10, 0x69, 0x6b
+     , 0xee, 0x57, 0x5d, 0x1d, 0x22, 0x58, 0x20, 0xc9, 0xd2, 0x7f, 0xf8, 0xfd, 0xd9, 0x10, 0xf4, 0x57
+     , 0xbe, 0xa1, 0x23, 0x4a, 0x70, 0xbe, 0xfc, 0xe3, 0x9f, 0xde, 0xcb, 0x61, 0x67, 0xa7, 0x27, 0xbe
+     , 0xf4, 0x19, 0x83, 0x0c, 0x2f, 0x8b, 0x55, 0x82, 0x01, 0x81, 0x00, 0x81, 0xa0, 0x9f, 0x58, 0x1c
+     , 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01
+     , 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x58, 0x1c, 0x02, 0x02
+     , 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02
+     , 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x58, 0x1c, 0x03, 0x03, 0x03, 0x03
+     , 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03
+     , 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0xff, 0x81, 0xa0
+     ];
+    const ENCODED_BLOCK_BODY_PROOF : &'static str = "c9d27ff8fdd910f457bea1234a70befce39fdecb6167a727bef419830c2f8b55";
+
+    #[test]
+    fn verify_body_proof_of_an_encoded_block() {
+        let block : Block = RawCbor::from(ENCODED_BLOCK).deserialize().unwrap();
+        assert_eq!(block.body.slot_leaders.len(), 3);
+        assert_eq!(format!("{}", block.header.body_proof.as_ref()), ENCODED_BLOCK_BODY_PROOF);
+        assert_eq!(format!("{}", BodyProof::from_body(&block.body).as_ref()), ENCODED_BLOCK_BODY_PROOF);
+        assert!(block.verify_body_proof());
+
+        // a different slot leader in the body
+        let mut bytes = ENCODED_BLOCK.to_vec();
+        let last_leader = bytes.len() - 4;
+        bytes[last_leader] = 0x04;
+        let block : Block = RawCbor::from(&bytes).deserialize().unwrap();
+        assert!(! block.verify_body_proof());
+    }
+
+    #[test]
+    fn extra_round_trip() {
+        use cbor_event::{Value, ObjectKey};
+        let header = RawCbor::from(&GENESIS_BLOCK_HEADER[2..]).deserialize().unwrap();
+        let mut attributes = BTreeMap::new();
+        attributes.insert(ObjectKey::Integer(1), Value::Text("extra".to_owned()));
+        let extra = Value::Array(vec![Value::Object(attributes.clone())]);
+        let block = Block {
+            header: header,
+            body: Body { slot_leaders: vec![stakeholder(0)] },
+            extra: extra.clone(),
+        };
+
+        let bytes = cbor!(&block).unwrap();
+        let decoded : Block = RawCbor::from(&bytes).deserialize().unwrap();
+        assert_eq!(decoded.extra, extra);
+        assert_eq!(decoded.extra_attributes().unwrap(), &attributes);
+        assert_eq!(cbor!(&decoded).unwrap(), bytes);
+    }
+}
@@ -7,6 +7,8 @@ pub mod types;
 pub mod genesis; /* genesis block related value */
 pub mod normal; /* normal block related value */
 pub mod block;
+#[cfg(test)]
+mod test_vectors;
 
 pub use block::types::*;
 pub use block::block::*;
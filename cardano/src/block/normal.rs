@@ -2,11 +2,11 @@ use {address, tx, hdwallet, vss, hash::{Blake2b256}};
 use config::{ProtocolMagic};
 use std::{fmt};
 use std::slice::{Iter};
-use std::collections::{BTreeMap, btree_map};
+use std::collections::{BTreeMap, BTreeSet, btree_map};
 
 use cbor_event::{self, de::RawCbor};
-use super::types;
-use super::types::{HeaderHash, HeaderExtraData, SlotId, ChainDifficulty};
+use super::{types, genesis};
+use super::types::{HeaderHash, HeaderExtraData, SlotId, EpochId, ChainDifficulty, EPOCH_SLOTS};
 
 #[derive(Debug, Clone)]
 pub struct BodyProof {
@@ -37,10 +37,7 @@ impl cbor_event::se::Serialize for BodyProof {
 }
 impl cbor_event::de::Deserialize for BodyProof {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(4) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid BodyProof: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(4, "BodyProof")?;
         let tx       = cbor_event::de::Deserialize::deserialize(raw)?;
         let mpc      = cbor_event::de::Deserialize::deserialize(raw)?;
         let proxy_sk = cbor_event::de::Deserialize::deserialize(raw)?;
@@ -73,6 +70,51 @@ impl TxPayload {
         TxPayload::new(Vec::new())
     }
     pub fn iter(&self) -> Iter<tx::TxAux> { self.txaux.iter() }
+
+    /// check if the same transaction (by its id) is included more than once
+    pub fn has_duplicates(&self) -> bool {
+        let mut ids = BTreeSet::new();
+        !self.txaux.iter().all(|txaux| ids.insert(txaux.tx.id()))
+    }
+
+    /// decode the transactions of a block body, skipping the ones that
+    /// cannot be decoded instead of failing on the first one.
+    ///
+    /// returns the decoded transactions and, for each of the skipped
+    /// transactions, its index in the body and the decoding error.
+    /// A transaction that is not even valid CBOR cannot be skipped: it
+    /// is reported and the remaining elements are not decoded.
+    pub fn decode_lenient<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<(Self, Vec<(usize, cbor_event::Error)>)> {
+        let len = raw.array()?;
+        if len != cbor_event::Len::Indefinite {
+            return Err(cbor_event::Error::CustomError(format!("Invalid TxPayload: recieved array of {:?} elements", len)));
+        }
+        let mut txaux = Vec::new();
+        let mut errors = Vec::new();
+        for index in 0.. {
+            if raw.cbor_type()? == cbor_event::Type::Special {
+                match raw.special()? {
+                    cbor_event::Special::Break => break,
+                    special => {
+                        errors.push((index, cbor_event::Error::CustomError(format!("Invalid TxPayload: unexpected {:?}", special))));
+                        continue;
+                    }
+                }
+            }
+            // the transaction is decoded from a copy of the decoder while
+            // the element is skipped by decoding it as a generic value
+            let mut element = *raw;
+            if let Err(err) = raw.deserialize::<cbor_event::Value>() {
+                errors.push((index, err));
+                break;
+            }
+            match element.deserialize() {
+                Ok(tx) => txaux.push(tx),
+                Err(err) => errors.push((index, err)),
+            }
+        }
+        Ok((TxPayload::new(txaux), errors))
+    }
 }
 impl cbor_event::se::Serialize for TxPayload {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
@@ -111,6 +153,129 @@ impl Body {
     pub fn new(tx: TxPayload, ssc: SscPayload, dlg: cbor_event::Value, upd: cbor_event::Value) -> Self {
         Body { tx: tx, ssc: ssc, delegation: dlg, update: upd }
     }
+
+    /// decode the delegation payload of the body, kept as is in `delegation`
+    pub fn delegation_payload(&self) -> cbor_event::Result<DelegationPayload> {
+        RawCbor::from(&cbor!(&self.delegation)?).deserialize()
+    }
+
+    /// decode the update payload of the body, kept as is in `update`
+    pub fn update_payload(&self) -> cbor_event::Result<UpdatePayload> {
+        RawCbor::from(&cbor!(&self.update)?).deserialize()
+    }
+}
+
+/// the update payload of a block: an optional proposal to update the
+/// protocol or the software, and the votes of the stakeholders on the
+/// pending proposals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdatePayload {
+    pub proposal: Option<UpdateProposal>,
+    pub votes: Vec<UpdateVote>,
+}
+impl UpdatePayload {
+    pub fn empty() -> Self { UpdatePayload { proposal: None, votes: Vec::new() } }
+
+    pub fn is_empty(&self) -> bool { self.proposal.is_none() && self.votes.is_empty() }
+}
+impl cbor_event::se::Serialize for UpdatePayload {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        let serializer = serializer.write_array(cbor_event::Len::Len(2))?;
+        let serializer = match self.proposal {
+            None => serializer.write_array(cbor_event::Len::Len(0))?,
+            Some(ref proposal) => serializer.write_array(cbor_event::Len::Len(1))?.serialize(proposal)?,
+        };
+        // the votes are encoded as an indefinite array, as the nodes do
+        cbor_event::se::serialize_indefinite_array(self.votes.iter(), serializer)
+    }
+}
+impl cbor_event::de::Deserialize for UpdatePayload {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        raw.tuple(2, "UpdatePayload")?;
+        let proposal = match raw.array()? {
+            cbor_event::Len::Len(0) => None,
+            cbor_event::Len::Len(1) => Some(raw.deserialize()?),
+            len => return Err(cbor_event::Error::CustomError(format!("Invalid UpdatePayload: recieved {:?} proposals", len))),
+        };
+        let votes = raw.deserialize()?;
+        Ok(UpdatePayload { proposal, votes })
+    }
+}
+
+/// a proposal to update the protocol parameters (`block_version_mod`)
+/// and/or the software (`data`), signed by the stakeholder `from`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateProposal {
+    pub block_version: types::BlockVersion,
+    pub block_version_mod: cbor_event::Value,
+    pub software_version: types::SoftwareVersion,
+    pub data: cbor_event::Value,
+    pub attributes: cbor_event::Value,
+    pub from: hdwallet::XPub,
+    pub signature: hdwallet::Signature<SignData>,
+}
+impl UpdateProposal {
+    /// the identifier of the proposal, as referred to by the votes
+    pub fn id(&self) -> Blake2b256 {
+        Blake2b256::new(&cbor!(self).expect("serialize the update proposal"))
+    }
+}
+impl cbor_event::se::Serialize for UpdateProposal {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        serializer.write_array(cbor_event::Len::Len(7))?
+            .serialize(&self.block_version)?
+            .serialize(&self.block_version_mod)?
+            .serialize(&self.software_version)?
+            .serialize(&self.data)?
+            .serialize(&self.attributes)?
+            .serialize(&self.from)?
+            .serialize(&self.signature)
+    }
+}
+impl cbor_event::de::Deserialize for UpdateProposal {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        raw.tuple(7, "UpdateProposal")?;
+        let block_version     = raw.deserialize()?;
+        let block_version_mod = raw.deserialize()?;
+        let software_version  = raw.deserialize()?;
+        let data              = raw.deserialize()?;
+        let attributes        = raw.deserialize()?;
+        let from              = raw.deserialize()?;
+        let signature         = raw.deserialize()?;
+        Ok(UpdateProposal { block_version, block_version_mod, software_version, data, attributes, from, signature })
+    }
+}
+
+/// the vote of the stakeholder `voter` for or against (`decision`) the
+/// proposal of the given id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateVote {
+    pub voter: hdwallet::XPub,
+    pub proposal_id: Blake2b256,
+    pub decision: bool,
+    pub signature: hdwallet::Signature<SignData>,
+}
+impl cbor_event::se::Serialize for UpdateVote {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        serializer.write_array(cbor_event::Len::Len(4))?
+            .serialize(&self.voter)?
+            .serialize(&self.proposal_id)?
+            .write_special(cbor_event::Special::Bool(self.decision))?
+            .serialize(&self.signature)
+    }
+}
+impl cbor_event::de::Deserialize for UpdateVote {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        raw.tuple(4, "UpdateVote")?;
+        let voter       = raw.deserialize()?;
+        let proposal_id = raw.deserialize()?;
+        let decision    = match raw.special()? {
+            cbor_event::Special::Bool(b) => b,
+            special => return Err(cbor_event::Error::CustomError(format!("Invalid UpdateVote: expected a boolean, received {:?}", special))),
+        };
+        let signature   = raw.deserialize()?;
+        Ok(UpdateVote { voter, proposal_id, decision, signature })
+    }
 }
 impl fmt::Display for Body {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -128,11 +293,11 @@ impl cbor_event::se::Serialize for Body {
 }
 impl cbor_event::de::Deserialize for Body {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(4) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Body: recieved array of {:?} elements", len)));
+        raw.tuple(4, "Body")?;
+        let tx : TxPayload = raw.deserialize()?;
+        if tx.has_duplicates() {
+            return Err(cbor_event::Error::CustomError("Invalid Body: duplicated transactions".to_owned()));
         }
-        let tx  = raw.deserialize()?;
         let scc = raw.deserialize()?;
         let dlg = raw.deserialize()?;
         let upd = raw.deserialize()?;
@@ -249,10 +414,7 @@ impl cbor_event::se::Serialize for SignedCommitment {
 }
 impl cbor_event::de::Deserialize for SignedCommitment {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Body: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "SignedCommitment")?;
         let public_key = raw.deserialize()?;
         let commitment = raw.deserialize()?;
         let signature  = raw.deserialize()?;
@@ -275,10 +437,7 @@ impl cbor_event::se::Serialize for Commitment {
 }
 impl cbor_event::de::Deserialize for Commitment {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Body: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "Commitment")?;
         let shares = raw.deserialize()?;
         let proof  = raw.deserialize()?;
 
@@ -304,10 +463,7 @@ impl cbor_event::se::Serialize for SecretProof {
 }
 impl cbor_event::de::Deserialize for SecretProof {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(4) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Body: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(4, "SecretProof")?;
         let extra_gen       = raw.deserialize()?;
         let proof           = raw.deserialize()?;
         let parallel_proofs = raw.deserialize()?;
@@ -437,12 +593,9 @@ impl cbor_event::se::Serialize for VssCertificate {
 }
 impl cbor_event::de::Deserialize for VssCertificate {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(4) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Body: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(4, "VssCertificate")?;
         let vss_key      = raw.deserialize()?;
-        let expiry_epoch = raw.unsigned_integer()? as u32;
+        let expiry_epoch = raw.deserialize()?;
         let signature    = raw.deserialize()?;
         let signing_key  = raw.deserialize()?;
 
@@ -461,7 +614,7 @@ pub struct BlockHeader {
 impl fmt::Display for BlockHeader {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!( f
-            , "Magic: 0x{:?} Previous Header: {}"
+            , "Magic: 0x{:x} Previous Header: {}"
             , self.protocol_magic
             , self.previous_header
             )
@@ -477,6 +630,13 @@ impl BlockHeader {
             extra_data: ed
         }
 }
+
+    /// verify the signature of the header: by the leader key, or by the
+    /// delegate of a proxy signature in which case the delegation
+    /// certificate is verified too.
+    pub fn verify(&self) -> bool {
+        MainToSign::from_header(self).verify(self.protocol_magic, &self.consensus.leader_key, &self.consensus.block_signature)
+    }
 }
 impl cbor_event::se::Serialize for BlockHeader {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
@@ -490,10 +650,7 @@ impl cbor_event::se::Serialize for BlockHeader {
 }
 impl cbor_event::de::Deserialize for BlockHeader {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(5) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid BlockHeader: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(5, "BlockHeader")?;
 
         let p_magic    = cbor_event::de::Deserialize::deserialize(raw)?;
         let prv_header = cbor_event::de::Deserialize::deserialize(raw)?;
@@ -505,6 +662,38 @@ impl cbor_event::de::Deserialize for BlockHeader {
     }
 }
 
+/// the reason a main block failed [`Block::verify`](./struct.Block.html#method.verify)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    /// the block is for another network
+    WrongMagic { expected: ProtocolMagic, got: ProtocolMagic },
+    /// the slot is not within its epoch
+    SlotOutOfEpoch(SlotId),
+    /// the number of transactions differs from the header's body proof
+    WrongTxCount { proof: u32, count: usize },
+    /// the delegation payload does not match the header's body proof
+    WrongDelegationProof,
+    /// the update payload does not match the header's body proof
+    WrongUpdateProof,
+    /// the extra data does not match the header's extra data proof
+    WrongExtraDataProof,
+    /// the header's signature is invalid
+    InvalidSignature,
+}
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockError::WrongMagic { expected, got } => write!(f, "wrong protocol magic: expected {}, got {}", expected, got),
+            BlockError::SlotOutOfEpoch(slot_id) => write!(f, "slot {} is out of its epoch", slot_id),
+            BlockError::WrongTxCount { proof, count } => write!(f, "body proof has {} transactions, body has {}", proof, count),
+            BlockError::WrongDelegationProof => write!(f, "delegation payload does not match the body proof"),
+            BlockError::WrongUpdateProof => write!(f, "update payload does not match the body proof"),
+            BlockError::WrongExtraDataProof => write!(f, "extra data does not match the extra data proof"),
+            BlockError::InvalidSignature => write!(f, "invalid block signature"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Block {
     pub header: BlockHeader,
@@ -515,6 +704,93 @@ impl Block {
     pub fn new(h: BlockHeader, b: Body, e: cbor_event::Value) -> Self {
         Block { header: h, body: b, extra: e }
     }
+
+    /// the attributes of the block's extra data, see
+    /// [`types::block_extra_attributes`](../types/fn.block_extra_attributes.html)
+    pub fn extra_attributes(&self) -> cbor_event::Result<&BTreeMap<cbor_event::ObjectKey, cbor_event::Value>> {
+        types::block_extra_attributes(&self.extra)
+    }
+
+    /// the number of transactions of the block
+    pub fn transaction_count(&self) -> usize {
+        self.body.tx.iter().len()
+    }
+
+    /// the inputs of all the transactions of the block, in the order of
+    /// the transactions.
+    pub fn spent_inputs(&self) -> Vec<tx::TxIn> {
+        self.body.tx.iter().flat_map(|txaux| txaux.tx.inputs.iter().cloned()).collect()
+    }
+
+    /// compute the hash of the block's extra data, as committed to in the
+    /// header's `extra_data.extra_data_proof`
+    pub fn compute_extra_data_proof(&self) -> Blake2b256 {
+        Blake2b256::new(&cbor!(&self.extra).expect("serialize the block's extra data"))
+    }
+
+    /// check the block's extra data matches the proof of the header
+    pub fn verify_extra_data_proof(&self) -> bool {
+        self.compute_extra_data_proof() == self.header.extra_data.extra_data_proof
+    }
+
+    /// check the block is consistent with its header: the protocol magic,
+    /// the slot, the body proof (the number of transactions and the
+    /// hashes of the delegation and update payloads, as received), the
+    /// extra data proof and the header's signature.
+    ///
+    /// the transactions merkle root and the SSC proof are not checked.
+    pub fn verify(&self, expected_magic: ProtocolMagic) -> Result<(), BlockError> {
+        let header = &self.header;
+        if header.protocol_magic != expected_magic {
+            return Err(BlockError::WrongMagic { expected: expected_magic, got: header.protocol_magic });
+        }
+        if header.consensus.slot_id.slotid >= EPOCH_SLOTS {
+            return Err(BlockError::SlotOutOfEpoch(header.consensus.slot_id));
+        }
+        let count = self.transaction_count();
+        if header.body_proof.tx.number as usize != count {
+            return Err(BlockError::WrongTxCount { proof: header.body_proof.tx.number, count });
+        }
+        let hash = |value: &cbor_event::Value| Blake2b256::new(&cbor!(value).expect("serialize the block's payload"));
+        if hash(&self.body.delegation) != header.body_proof.proxy_sk {
+            return Err(BlockError::WrongDelegationProof);
+        }
+        if hash(&self.body.update) != header.body_proof.update {
+            return Err(BlockError::WrongUpdateProof);
+        }
+        if !self.verify_extra_data_proof() {
+            return Err(BlockError::WrongExtraDataProof);
+        }
+        if !header.verify() {
+            return Err(BlockError::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// the key of the stakeholder on behalf of whom the block was created:
+    /// the leader key if the block is directly signed, the issuer of the
+    /// delegation if it is signed by a delegate.
+    pub fn issuer_key(&self) -> Option<hdwallet::XPub> {
+        match self.header.consensus.block_signature {
+            BlockSignature::Signature(_) => Some(self.header.consensus.leader_key.clone()),
+            ref proxy => proxy.proxy_issuer(),
+        }
+    }
+
+    /// check the block was created by the leader of its slot, as scheduled
+    /// in the genesis block of its epoch.
+    ///
+    /// this does not check the block's signature.
+    pub fn verify_leader(&self, genesis: &genesis::Block, slots_per_epoch: u32) -> bool {
+        let slot_id = &self.header.consensus.slot_id;
+        if slot_id.epoch != genesis.header.consensus.epoch || slot_id.slotid >= slots_per_epoch {
+            return false;
+        }
+        match (self.issuer_key(), genesis.leader_for_slot(slot_id.slotid)) {
+            (Some(key), Some(leader)) => address::StakeholderId::new(&key) == leader,
+            _ => false,
+        }
+    }
 }
 impl fmt::Display for Block {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -532,24 +808,223 @@ impl cbor_event::se::Serialize for Block {
 }
 impl cbor_event::de::Deserialize for Block {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Block: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "Block")?;
         let header = raw.deserialize()?;
         let body   = raw.deserialize()?;
         let extra  = raw.deserialize()?;
+        types::block_extra_attributes(&extra)?;
         Ok(Block::new(header, body, extra))
     }
 }
 
 type SignData = ();
 
+/// epochs for which a light delegation certificate is valid (inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LightDlgIndices(pub EpochId, pub EpochId);
+impl LightDlgIndices {
+    pub fn contains(&self, epoch: EpochId) -> bool {
+        self.0 <= epoch && epoch <= self.1
+    }
+}
+impl cbor_event::se::Serialize for LightDlgIndices {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        serializer.write_array(cbor_event::Len::Len(2))?
+            .serialize(&self.0)?
+            .serialize(&self.1)
+    }
+}
+impl cbor_event::de::Deserialize for LightDlgIndices {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        raw.tuple(2, "LightDlgIndices")?;
+        let start = raw.deserialize()?;
+        let end   = raw.deserialize()?;
+        Ok(LightDlgIndices(start, end))
+    }
+}
+
+/// epoch in which a heavy delegation certificate was issued
+pub type HeavyDlgIndex = EpochId;
+
+/// the delegation certificate: the issuer allows the delegate to sign
+/// blocks on its behalf, `omega` being the delegation indices
+/// ([`LightDlgIndices`](./struct.LightDlgIndices.html) or
+/// [`HeavyDlgIndex`](./type.HeavyDlgIndex.html)).
+#[derive(Debug, Clone)]
+pub struct ProxySecretKey<Omega> {
+    pub omega: Omega,
+    pub issuer_pk: hdwallet::XPub,
+    pub delegate_pk: hdwallet::XPub,
+    pub cert: hdwallet::Signature<SignData>,
+}
+impl<Omega: cbor_event::se::Serialize> ProxySecretKey<Omega> {
+    /// check the certificate was signed by the issuer
+    pub fn verify(&self, protocol_magic: ProtocolMagic) -> bool {
+        self.issuer_pk.verify(&self.to_sign(protocol_magic), &self.cert)
+    }
+
+    // the signed data: the signing tag, the protocol magic and the bytes
+    // of `"00" | delegate_pk | omega`
+    fn to_sign(&self, protocol_magic: ProtocolMagic) -> Vec<u8> {
+        let mut cert = b"00".to_vec();
+        cert.extend_from_slice(self.delegate_pk.as_ref());
+        let cert = cbor_event::se::Serializer::new(cert)
+            .serialize(&self.omega).expect("serialize delegation indices")
+            .finalize();
+        cbor_event::se::Serializer::new_vec()
+            .write_unsigned_integer(SIGN_TAG_PROXY_SK).expect("write signing tag")
+            .serialize(&protocol_magic).expect("serialize protocol magic")
+            .write_bytes(&cert).expect("write certificate data")
+            .finalize()
+    }
+}
+impl<Omega: cbor_event::se::Serialize> cbor_event::se::Serialize for ProxySecretKey<Omega> {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        serializer.write_array(cbor_event::Len::Len(4))?
+            .serialize(&self.omega)?
+            .serialize(&self.issuer_pk)?
+            .serialize(&self.delegate_pk)?
+            .serialize(&self.cert)
+    }
+}
+impl<Omega: cbor_event::de::Deserialize> cbor_event::de::Deserialize for ProxySecretKey<Omega> {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        raw.tuple(4, "ProxySecretKey")?;
+        let omega       = raw.deserialize()?;
+        let issuer_pk   = raw.deserialize()?;
+        let delegate_pk = raw.deserialize()?;
+        let cert        = raw.deserialize()?;
+        Ok(ProxySecretKey { omega, issuer_pk, delegate_pk, cert })
+    }
+}
+
+/// the delegation payload of a block: the heavyweight delegation
+/// certificates published in the block
+#[derive(Debug, Clone)]
+pub struct DelegationPayload(Vec<ProxySecretKey<HeavyDlgIndex>>);
+impl DelegationPayload {
+    pub fn new(certificates: Vec<ProxySecretKey<HeavyDlgIndex>>) -> Self { DelegationPayload(certificates) }
+    pub fn empty() -> Self { DelegationPayload::new(Vec::new()) }
+
+    pub fn len(&self) -> usize { self.0.len() }
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+    pub fn iter(&self) -> Iter<ProxySecretKey<HeavyDlgIndex>> { self.0.iter() }
+}
+impl cbor_event::se::Serialize for DelegationPayload {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        // encoded as an indefinite array, as the nodes do
+        cbor_event::se::serialize_indefinite_array(self.0.iter(), serializer)
+    }
+}
+impl cbor_event::de::Deserialize for DelegationPayload {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        Ok(DelegationPayload(raw.deserialize()?))
+    }
+}
+
+/// a signature made by the delegate of a `ProxySecretKey`
+#[derive(Debug, Clone)]
+pub struct ProxySignature<Omega> {
+    pub psk: ProxySecretKey<Omega>,
+    pub sig: hdwallet::Signature<MainToSign>,
+}
+impl<Omega: cbor_event::se::Serialize> ProxySignature<Omega> {
+    /// check the delegation certificate and the delegate's signature
+    /// of the given data, `to_sign` being the signing tag, the protocol
+    /// magic and the signed data already serialized.
+    fn verify(&self, protocol_magic: ProtocolMagic, to_sign: &[u8]) -> bool {
+        let mut buf = b"01".to_vec();
+        buf.extend_from_slice(self.psk.issuer_pk.as_ref());
+        buf.extend_from_slice(to_sign);
+        self.psk.verify(protocol_magic) && self.psk.delegate_pk.verify(&buf, &self.sig)
+    }
+}
+impl<Omega: cbor_event::se::Serialize> cbor_event::se::Serialize for ProxySignature<Omega> {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        serializer.write_array(cbor_event::Len::Len(2))?
+            .serialize(&self.psk)?
+            .serialize(&self.sig)
+    }
+}
+impl<Omega: cbor_event::de::Deserialize> cbor_event::de::Deserialize for ProxySignature<Omega> {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        raw.tuple(2, "ProxySignature")?;
+        let psk = raw.deserialize()?;
+        let sig = raw.deserialize()?;
+        Ok(ProxySignature { psk, sig })
+    }
+}
+
+/// the data of a main block header signed by the slot leader, or by its
+/// delegate: `[previous_header, body_proof, slot_id, chain_difficulty, extra_data]`
+#[derive(Debug, Clone)]
+pub struct MainToSign {
+    pub previous_header: HeaderHash,
+    pub body_proof: BodyProof,
+    pub slot_id: SlotId,
+    pub chain_difficulty: ChainDifficulty,
+    pub extra_data: HeaderExtraData,
+}
+impl MainToSign {
+    pub fn from_header(header: &BlockHeader) -> Self {
+        MainToSign {
+            previous_header: header.previous_header.clone(),
+            body_proof: header.body_proof.clone(),
+            slot_id: header.consensus.slot_id,
+            chain_difficulty: header.consensus.chain_difficulty,
+            extra_data: header.extra_data.clone(),
+        }
+    }
+
+    /// verify the block signature of the data: by the leader key, or by
+    /// the delegate of a proxy signature in which case the delegation
+    /// certificate is verified too.
+    pub fn verify(&self, protocol_magic: ProtocolMagic, leader_key: &hdwallet::XPub, signature: &BlockSignature) -> bool {
+        match signature {
+            BlockSignature::Signature(sig) => {
+                leader_key.verify(&self.to_sign(protocol_magic, SIGN_TAG_MAIN_BLOCK), sig)
+            },
+            BlockSignature::ProxyLight(proxy) => {
+                proxy.psk.omega.contains(self.slot_id.epoch)
+                    && proxy.verify(protocol_magic, &self.to_sign(protocol_magic, SIGN_TAG_MAIN_BLOCK_LIGHT))
+            },
+            BlockSignature::ProxyHeavy(proxy) => {
+                proxy.verify(protocol_magic, &self.to_sign(protocol_magic, SIGN_TAG_MAIN_BLOCK_HEAVY))
+            },
+        }
+    }
+
+    // the signed bytes: the signing tag, the protocol magic and the data
+    fn to_sign(&self, protocol_magic: ProtocolMagic, sign_tag: u64) -> Vec<u8> {
+        cbor_event::se::Serializer::new_vec()
+            .write_unsigned_integer(sign_tag).expect("write signing tag")
+            .serialize(&protocol_magic).expect("serialize protocol magic")
+            .serialize(self).expect("serialize signed data")
+            .finalize()
+    }
+}
+impl cbor_event::se::Serialize for MainToSign {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        serializer.write_array(cbor_event::Len::Len(5))?
+            .serialize(&self.previous_header)?
+            .serialize(&self.body_proof)?
+            .serialize(&self.slot_id)?
+            .serialize(&self.chain_difficulty)?
+            .serialize(&self.extra_data)
+    }
+}
+
+// signing tags of the different signed objects
+const SIGN_TAG_MAIN_BLOCK       : u64 = 0x07;
+const SIGN_TAG_MAIN_BLOCK_LIGHT : u64 = 0x08;
+const SIGN_TAG_MAIN_BLOCK_HEAVY : u64 = 0x09;
+const SIGN_TAG_PROXY_SK         : u64 = 0x0a;
+
 #[derive(Debug, Clone)]
 pub enum BlockSignature {
-    Signature(hdwallet::Signature<SignData>),
-    ProxyLight(Vec<cbor_event::Value>),
-    ProxyHeavy(Vec<cbor_event::Value>),
+    Signature(hdwallet::Signature<MainToSign>),
+    ProxyLight(ProxySignature<LightDlgIndices>),
+    ProxyHeavy(ProxySignature<HeavyDlgIndex>),
 }
 impl BlockSignature {
     pub fn to_bytes<'a>(&'a self) -> Option<&'a [u8;hdwallet::SIGNATURE_SIZE]> {
@@ -558,6 +1033,26 @@ impl BlockSignature {
             _ => None,
         }
     }
+
+    /// public key of the issuer of the delegation certificate of a proxy
+    /// signature, `None` for a direct signature.
+    pub fn proxy_issuer(&self) -> Option<hdwallet::XPub> {
+        match self {
+            BlockSignature::Signature(_) => None,
+            BlockSignature::ProxyLight(proxy) => Some(proxy.psk.issuer_pk.clone()),
+            BlockSignature::ProxyHeavy(proxy) => Some(proxy.psk.issuer_pk.clone()),
+        }
+    }
+
+    /// public key of the delegate of a proxy signature, `None` for a
+    /// direct signature.
+    pub fn proxy_delegate(&self) -> Option<hdwallet::XPub> {
+        match self {
+            BlockSignature::Signature(_) => None,
+            BlockSignature::ProxyLight(proxy) => Some(proxy.psk.delegate_pk.clone()),
+            BlockSignature::ProxyHeavy(proxy) => Some(proxy.psk.delegate_pk.clone()),
+        }
+    }
 }
 impl cbor_event::se::Serialize for BlockSignature {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
@@ -566,25 +1061,20 @@ impl cbor_event::se::Serialize for BlockSignature {
                 serializer.write_array(cbor_event::Len::Len(2))?
                     .write_unsigned_integer(0)?.serialize(sig)
             },
-            &BlockSignature::ProxyLight(ref v) => {
-                let serializer = serializer.write_array(cbor_event::Len::Len(2))?
-                    .write_unsigned_integer(1)?;
-                cbor_event::se::serialize_fixed_array(v.iter(), serializer)
+            &BlockSignature::ProxyLight(ref proxy) => {
+                serializer.write_array(cbor_event::Len::Len(2))?
+                    .write_unsigned_integer(1)?.serialize(proxy)
             },
-            &BlockSignature::ProxyHeavy(ref v) => {
-                let serializer = serializer.write_array(cbor_event::Len::Len(2))?
-                    .write_unsigned_integer(2)?;
-                cbor_event::se::serialize_fixed_array(v.iter(), serializer)
+            &BlockSignature::ProxyHeavy(ref proxy) => {
+                serializer.write_array(cbor_event::Len::Len(2))?
+                    .write_unsigned_integer(2)?.serialize(proxy)
             },
         }
     }
 }
 impl cbor_event::de::Deserialize for BlockSignature {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid BlockSignature: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "BlockSignature")?;
         let sum_type_idx = raw.unsigned_integer()?;
         match sum_type_idx {
             0 => {
@@ -610,6 +1100,12 @@ pub struct Consensus {
     pub chain_difficulty: ChainDifficulty,
     pub block_signature: BlockSignature,
 }
+impl Consensus {
+    /// the stakeholder id of the block's leader: the hash of its key
+    pub fn leader_stakeholder_id(&self) -> address::StakeholderId {
+        address::StakeholderId::new(&self.leader_key)
+    }
+}
 impl cbor_event::se::Serialize for Consensus {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
         serializer.write_array(cbor_event::Len::Len(4))?
@@ -621,10 +1117,7 @@ impl cbor_event::se::Serialize for Consensus {
 }
 impl cbor_event::de::Deserialize for Consensus {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(4) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Consensus: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(4, "Consensus")?;
         let slot_id = cbor_event::de::Deserialize::deserialize(raw)?;
         let leader_key = cbor_event::de::Deserialize::deserialize(raw)?;
         let chain_difficulty = cbor_event::de::Deserialize::deserialize(raw)?;
@@ -632,3 +1125,404 @@ impl cbor_event::de::Deserialize for Consensus {
         Ok(Consensus {slot_id, leader_key, chain_difficulty, block_signature })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use testing;
+
+    const SLOTS_PER_EPOCH : u32 = 3;
+    // epoch of the test vector's genesis block
+    const E : u32 = 1;
+
+    fn key(seed: u8) -> hdwallet::XPub {
+        hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([seed;hdwallet::SEED_SIZE])).public()
+    }
+
+    fn decode<T: cbor_event::de::Deserialize>(bytes: &[u8]) -> T {
+        RawCbor::from(bytes).deserialize().unwrap()
+    }
+
+    // genesis block of the epoch 1, with the leaders 1, 2 and 3
+    fn genesis_block() -> genesis::Block {
+        let leaders : Vec<_> = (1..4).map(|seed| address::StakeholderId::new(&key(seed))).collect();
+        testing::decode_genesis(&testing::genesis_block_with_leaders(E, 0, &HeaderHash::new(&[]), &leaders))
+    }
+
+    fn main_block(slot_id: SlotId, leader_key: hdwallet::XPub, block_signature: BlockSignature) -> Block {
+        testing::normal_block(slot_id, &HeaderHash::new(&[]), leader_key, block_signature)
+    }
+
+    fn slot(epoch: u32, slotid: u32) -> SlotId { SlotId { epoch: types::EpochId::new(epoch), slotid: slotid } }
+
+    fn txaux(index: u32) -> tx::TxAux {
+        let mut tx = tx::Tx::new();
+        tx.add_input(tx::TxIn::new(tx::TxId::new(&[0;32]), index));
+        tx.add_output(tx::TxOut::new(address::ExtendedAddr::new_simple(key(1)), ::coin::Coin::new(1000).unwrap()));
+        tx::TxAux::new(tx, vec![])
+    }
+
+    #[test]
+    fn tx_payload_decode_lenient() {
+        let payload = TxPayload::new(vec![txaux(0), txaux(1), txaux(2)]);
+        let bytes = cbor!(&payload).unwrap();
+
+        // replace the second transaction with a well formed CBOR value
+        // which is not a transaction
+        let first = cbor!(&txaux(0)).unwrap();
+        let second = cbor!(&txaux(1)).unwrap();
+        let mut garbage = bytes[..1 + first.len()].to_vec();
+        garbage.extend_from_slice(&[0x82, 0x01, 0x02]);
+        garbage.extend_from_slice(&bytes[1 + first.len() + second.len()..]);
+
+        assert!(RawCbor::from(&garbage).deserialize::<TxPayload>().is_err());
+        let (decoded, errors) = TxPayload::decode_lenient(&mut RawCbor::from(&garbage)).unwrap();
+        assert_eq!(decoded.iter().cloned().collect::<Vec<_>>(), vec![txaux(0), txaux(2)]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 1);
+
+        let (decoded, errors) = TxPayload::decode_lenient(&mut RawCbor::from(&bytes)).unwrap();
+        assert_eq!(decoded.iter().count(), 3);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn delegation_payload() {
+        use block::test_vectors::{MAINNET_BLOCK_HEADER};
+
+        let header : BlockHeader = decode(&MAINNET_BLOCK_HEADER[2..]);
+        let mut body = main_block(slot(E, 0), key(1), testing::signature()).body;
+
+        // the proof of the mainnet block is the hash of the empty payload
+        body.delegation = decode(&[0x9f, 0xff]);
+        let payload = body.delegation_payload().unwrap();
+        assert!(payload.is_empty());
+        assert_eq!(Blake2b256::new(&cbor!(&payload).unwrap()), header.body_proof.proxy_sk);
+
+        // the certificate of the mainnet block's heavy delegation
+        let psk = match header.consensus.block_signature {
+            BlockSignature::ProxyHeavy(ref proxy) => proxy.psk.clone(),
+            ref sig => panic!("expected a heavy proxy signature, got {:?}", sig),
+        };
+        let mut other = psk.clone();
+        other.omega = EpochId::new(1);
+        body.delegation = decode(&cbor!(&DelegationPayload::new(vec![psk, other])).unwrap());
+
+        let payload = body.delegation_payload().unwrap();
+        assert_eq!(payload.len(), 2);
+        let valid : Vec<bool> = payload.iter().map(|psk| psk.verify(header.protocol_magic)).collect();
+        assert_eq!(valid, vec![true, false]);
+    }
+
+    #[test]
+    fn empty_update_payload() {
+        use block::test_vectors::{MAINNET_BLOCK_HEADER};
+
+        let mut block = main_block(slot(E, 0), key(1), testing::signature());
+        block.body.update = decode(&[0x82, 0x80, 0x9f, 0xff]);
+        let payload = block.body.update_payload().unwrap();
+        assert!(payload.is_empty());
+        assert_eq!(payload, UpdatePayload::empty());
+        assert_eq!(cbor!(&payload).unwrap(), vec![0x82, 0x80, 0x9f, 0xff]);
+
+        // the update proof of the mainnet block is the hash of the empty payload
+        let header : BlockHeader = decode(&MAINNET_BLOCK_HEADER[2..]);
+        assert_eq!(Blake2b256::new(&cbor!(&payload).unwrap()), header.body_proof.update);
+    }
+
+    #[test]
+    fn update_payload_with_proposal() {
+        use cbor_event::Value;
+
+        let proposer = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([3;hdwallet::SEED_SIZE]));
+        let voter = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([4;hdwallet::SEED_SIZE]));
+        let software_version : types::SoftwareVersion = decode(&[0x82, 0x6a, 0x63, 0x61, 0x72, 0x64, 0x61, 0x6e, 0x6f, 0x2d, 0x73, 0x6c, 0x01]);
+        let proposal = UpdateProposal {
+            block_version: types::BlockVersion::new(0, 1, 0),
+            block_version_mod: decode(&[0x8a, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80]),
+            software_version: software_version,
+            data: Value::Object(BTreeMap::new()),
+            attributes: Value::Object(BTreeMap::new()),
+            from: proposer.public(),
+            signature: proposer.sign(b"proposal"),
+        };
+        let vote = UpdateVote {
+            voter: voter.public(),
+            proposal_id: proposal.id(),
+            decision: true,
+            signature: voter.sign(b"vote"),
+        };
+        let payload = UpdatePayload { proposal: Some(proposal.clone()), votes: vec![vote.clone()] };
+        assert!(!payload.is_empty());
+
+        let mut body = main_block(slot(E, 0), key(1), testing::signature()).body;
+        body.update = decode(&cbor!(&payload).unwrap());
+        let decoded = body.update_payload().unwrap();
+        assert_eq!(decoded, payload);
+        assert_eq!(decoded.votes[0].proposal_id, decoded.proposal.unwrap().id());
+
+        // the body is re-encoded as received
+        let bytes = cbor!(&body).unwrap();
+        assert_eq!(cbor!(&decode::<Body>(&bytes)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn transaction_count() {
+        let mut block = main_block(slot(E, 0), key(1), testing::signature());
+        assert_eq!(block.transaction_count(), 0);
+        block.body.tx = TxPayload::new(vec![txaux(0), txaux(1), txaux(2)]);
+        assert_eq!(block.transaction_count(), 3);
+    }
+
+    #[test]
+    fn spent_inputs() {
+        let mut block = main_block(slot(E, 0), key(1), testing::signature());
+        assert!(block.spent_inputs().is_empty());
+
+        let mut tx = txaux(1);
+        tx.tx.add_input(tx::TxIn::new(tx::TxId::new(&[1;32]), 0));
+        block.body.tx = TxPayload::new(vec![txaux(0), tx.clone(), txaux(2)]);
+        let inputs = block.spent_inputs();
+        assert_eq!(inputs.len(), 4);
+        assert_eq!(inputs[0], txaux(0).tx.inputs[0]);
+        assert_eq!(&inputs[1..3], &tx.tx.inputs[..]);
+        assert_eq!(inputs[3], txaux(2).tx.inputs[0]);
+    }
+
+    #[test]
+    fn body_with_duplicated_transactions() {
+        let body = |txs| Body::new(TxPayload::new(txs), SscPayload::CertificatesPayload(decode(&[0xd9, 0x01, 0x02, 0x80])), cbor_event::Value::Array(vec![]), cbor_event::Value::Array(vec![]));
+
+        let valid = body(vec![txaux(0), txaux(1)]);
+        assert!(!valid.tx.has_duplicates());
+        assert!(RawCbor::from(&cbor!(&valid).unwrap()).deserialize::<Body>().is_ok());
+
+        // the same transaction, even with different witnesses
+        let mut twice = txaux(0);
+        twice.witnesses.push(tx::TxInWitness::new(Default::default(), &hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([1;hdwallet::SEED_SIZE])), &twice.tx.id()));
+        let invalid = body(vec![txaux(0), txaux(1), twice]);
+        assert!(invalid.tx.has_duplicates());
+        assert!(RawCbor::from(&cbor!(&invalid).unwrap()).deserialize::<Body>().is_err());
+    }
+
+    #[test]
+    fn extra_data_proof() {
+        use block::test_vectors::{MAINNET_BLOCK_HEADER};
+        use cbor_event::{Value, ObjectKey};
+
+        // the mainnet block's extra data has no attributes
+        let mut block = main_block(slot(E, 0), key(1), testing::signature());
+        block.header = decode(&MAINNET_BLOCK_HEADER[2..]);
+        assert_eq!(block.compute_extra_data_proof(), block.header.extra_data.extra_data_proof);
+        assert!(block.verify_extra_data_proof());
+
+        let mut attributes = BTreeMap::new();
+        attributes.insert(ObjectKey::Integer(0), Value::Bytes(vec![1, 2, 3]));
+        block.extra = Value::Array(vec![Value::Object(attributes)]);
+        assert!(!block.verify_extra_data_proof());
+    }
+
+    #[test]
+    fn extra_round_trip() {
+        use cbor_event::{Value, ObjectKey};
+        let mut attributes = BTreeMap::new();
+        attributes.insert(ObjectKey::Integer(0), Value::Bytes(vec![1, 2, 3]));
+        let extra = Value::Array(vec![Value::Object(attributes.clone())]);
+        let mut block = main_block(slot(E, 0), key(1), testing::signature());
+        block.extra = extra.clone();
+
+        let bytes = cbor!(&block).unwrap();
+        let decoded : Block = decode(&bytes);
+        assert_eq!(decoded.extra, extra);
+        assert_eq!(decoded.extra_attributes().unwrap(), &attributes);
+        assert_eq!(cbor!(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn extra_invalid_shape() {
+        let mut block = main_block(slot(E, 0), key(1), testing::signature());
+        block.extra = cbor_event::Value::Array(vec![]);
+        assert!(block.extra_attributes().is_err());
+
+        let bytes = cbor!(&block).unwrap();
+        assert!(RawCbor::from(&bytes).deserialize::<Block>().is_err());
+    }
+
+    #[test]
+    fn verify_leader_scheduled() {
+        let genesis = genesis_block();
+        assert!(main_block(slot(E, 0), key(1), testing::signature()).verify_leader(&genesis, SLOTS_PER_EPOCH));
+        assert!(main_block(slot(E, 2), key(3), testing::signature()).verify_leader(&genesis, SLOTS_PER_EPOCH));
+    }
+
+    #[test]
+    fn verify_leader_spoofed() {
+        let genesis = genesis_block();
+        // leader of another slot
+        assert!(! main_block(slot(E, 1), key(1), testing::signature()).verify_leader(&genesis, SLOTS_PER_EPOCH));
+        // not a leader at all
+        assert!(! main_block(slot(E, 1), key(42), testing::signature()).verify_leader(&genesis, SLOTS_PER_EPOCH));
+        // the genesis block of another epoch
+        assert!(! main_block(slot(E + 1, 0), key(1), testing::signature()).verify_leader(&genesis, SLOTS_PER_EPOCH));
+        // slot out of the epoch
+        assert!(! main_block(slot(E, 3), key(1), testing::signature()).verify_leader(&genesis, SLOTS_PER_EPOCH));
+    }
+
+    fn proxy(issuer: hdwallet::XPub) -> BlockSignature {
+        let psk = ProxySecretKey { omega: EpochId::new(0), issuer_pk: issuer, delegate_pk: key(9), cert: hdwallet::Signature::from_bytes([0;64]) };
+        BlockSignature::ProxyHeavy(ProxySignature { psk, sig: hdwallet::Signature::from_bytes([0;64]) })
+    }
+
+    #[test]
+    fn verify_leader_delegated() {
+        let genesis = genesis_block();
+        // the leader key is the delegate's, the issuer is the scheduled leader
+        assert!(main_block(slot(E, 1), key(9), proxy(key(2))).verify_leader(&genesis, SLOTS_PER_EPOCH));
+        assert!(! main_block(slot(E, 1), key(9), proxy(key(9))).verify_leader(&genesis, SLOTS_PER_EPOCH));
+    }
+
+    #[test]
+    fn proxy_heavy_signature() {
+        use block::test_vectors::{MAINNET_BLOCK_HEADER};
+
+        let header : BlockHeader = decode(&MAINNET_BLOCK_HEADER[2..]);
+        let delegate = hdwallet::XPub::from_slice(&MAINNET_BLOCK_HEADER[0x171..0x1b1]).unwrap();
+        match &header.consensus.block_signature {
+            BlockSignature::ProxyHeavy(proxy) => {
+                assert_eq!(proxy.psk.omega, EpochId::new(0));
+                assert_eq!(proxy.psk.issuer_pk, header.consensus.leader_key);
+                assert_eq!(proxy.psk.delegate_pk, delegate);
+                assert!(proxy.psk.verify(header.protocol_magic));
+            },
+            sig => panic!("expected a heavy proxy signature, got {:?}", sig),
+        }
+        assert_eq!(header.consensus.block_signature.proxy_delegate(), Some(delegate));
+        assert!(header.verify());
+        assert_eq!(cbor!(&header).unwrap().as_slice(), &MAINNET_BLOCK_HEADER[2..]);
+
+        // the signature of another header
+        let mut other = header.clone();
+        other.consensus.chain_difficulty = other.consensus.chain_difficulty + 1;
+        assert!(! other.verify());
+    }
+
+    #[test]
+    fn main_to_sign() {
+        use block::test_vectors::{MAINNET_BLOCK_HEADER};
+
+        // the mainnet block is signed by a heavyweight delegate
+        let header : BlockHeader = decode(&MAINNET_BLOCK_HEADER[2..]);
+        let to_sign = MainToSign::from_header(&header);
+        let signature = &header.consensus.block_signature;
+        assert!(to_sign.verify(header.protocol_magic, &header.consensus.leader_key, signature));
+        assert!(! to_sign.verify(ProtocolMagic::new(1), &header.consensus.leader_key, signature));
+        let mut other = to_sign.clone();
+        other.slot_id.slotid += 1;
+        assert!(! other.verify(header.protocol_magic, &header.consensus.leader_key, signature));
+
+        // signed by the leader
+        let leader = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([1;hdwallet::SEED_SIZE]));
+        let signature = BlockSignature::Signature(leader.sign(&to_sign.to_sign(header.protocol_magic, SIGN_TAG_MAIN_BLOCK)));
+        assert!(to_sign.verify(header.protocol_magic, &leader.public(), &signature));
+        assert!(! to_sign.verify(header.protocol_magic, &key(2), &signature));
+        assert!(! other.verify(header.protocol_magic, &leader.public(), &signature));
+    }
+
+    #[test]
+    fn header_display() {
+        use block::test_vectors::{MAINNET_BLOCK_HEADER};
+
+        let header : BlockHeader = decode(&MAINNET_BLOCK_HEADER[2..]);
+        assert_eq!( format!("{}", header)
+                  , format!("Magic: 0x2d964a09 Previous Header: {}", header.previous_header));
+    }
+
+    #[test]
+    fn leader_stakeholder_id() {
+        use block::test_vectors::{MAINNET_BLOCK_HEADER};
+
+        // one of the boot stakeholders of the mainnet genesis data
+        let header : BlockHeader = decode(&MAINNET_BLOCK_HEADER[2..]);
+        assert_eq!( format!("{}", header.consensus.leader_stakeholder_id())
+                  , "6c9e14978b9d6629b8703f4f25e9df6ed4814b930b8403b0d45350ea");
+    }
+
+    #[test]
+    fn proxy_light_signature_epochs() {
+        let issuer = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([1;hdwallet::SEED_SIZE]));
+        let delegate = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([9;hdwallet::SEED_SIZE]));
+        let mut block = main_block(slot(E, 1), issuer.public(), testing::signature());
+        let sign = |block: &Block, omega: LightDlgIndices| {
+            let mut psk = ProxySecretKey { omega, issuer_pk: issuer.public(), delegate_pk: delegate.public(), cert: hdwallet::Signature::from_bytes([0;64]) };
+            psk.cert = issuer.sign(&psk.to_sign(block.header.protocol_magic));
+            let mut sig = b"01".to_vec();
+            sig.extend_from_slice(issuer.public().as_ref());
+            sig.extend_from_slice(&MainToSign::from_header(&block.header).to_sign(block.header.protocol_magic, SIGN_TAG_MAIN_BLOCK_LIGHT));
+            BlockSignature::ProxyLight(ProxySignature { psk, sig: delegate.sign(&sig) })
+        };
+
+        block.header.consensus.block_signature = sign(&block, LightDlgIndices(EpochId::new(E), EpochId::new(E + 1)));
+        assert!(block.header.verify());
+        assert_eq!(block.header.consensus.block_signature.proxy_delegate(), Some(delegate.public()));
+
+        block.header.consensus.block_signature = sign(&block, LightDlgIndices(EpochId::new(E + 1), EpochId::new(E + 2)));
+        assert!(! block.header.verify());
+    }
+
+    // a block with valid proofs, signed by the leader
+    fn valid_block(leader: &hdwallet::XPrv) -> Block {
+        let mut block = main_block(slot(E, 1), leader.public(), testing::signature());
+        block.body.tx = TxPayload::new(vec![txaux(0), txaux(1)]);
+        block.header.body_proof.tx.number = 2;
+        block.header.body_proof.proxy_sk = Blake2b256::new(&cbor!(&block.body.delegation).unwrap());
+        block.header.body_proof.update = Blake2b256::new(&cbor!(&block.body.update).unwrap());
+        block.header.extra_data.extra_data_proof = block.compute_extra_data_proof();
+        sign(&mut block, leader);
+        block
+    }
+
+    fn sign(block: &mut Block, leader: &hdwallet::XPrv) {
+        let sig = leader.sign(&MainToSign::from_header(&block.header).to_sign(block.header.protocol_magic, SIGN_TAG_MAIN_BLOCK));
+        block.header.consensus.block_signature = BlockSignature::Signature(sig);
+    }
+
+    #[test]
+    fn verify_block() {
+        let leader = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([1;hdwallet::SEED_SIZE]));
+        let magic = ProtocolMagic::default();
+        assert_eq!(valid_block(&leader).verify(magic), Ok(()));
+
+        let other_magic = ProtocolMagic::new(42);
+        assert_eq!( valid_block(&leader).verify(other_magic)
+                  , Err(BlockError::WrongMagic { expected: other_magic, got: magic }));
+
+        let mut block = valid_block(&leader);
+        block.header.consensus.slot_id = slot(E, EPOCH_SLOTS);
+        sign(&mut block, &leader);
+        assert_eq!(block.verify(magic), Err(BlockError::SlotOutOfEpoch(slot(E, EPOCH_SLOTS))));
+
+        let mut block = valid_block(&leader);
+        block.body.tx = TxPayload::new(vec![txaux(0)]);
+        assert_eq!(block.verify(magic), Err(BlockError::WrongTxCount { proof: 2, count: 1 }));
+
+        let mut block = valid_block(&leader);
+        block.body.delegation = decode(&[0x9f, 0xff]);
+        assert_eq!(block.verify(magic), Err(BlockError::WrongDelegationProof));
+
+        let mut block = valid_block(&leader);
+        block.body.update = decode(&[0x82, 0x80, 0x9f, 0xff]);
+        assert_eq!(block.verify(magic), Err(BlockError::WrongUpdateProof));
+
+        let mut block = valid_block(&leader);
+        block.extra = cbor_event::Value::Array(vec![]);
+        assert_eq!(block.verify(magic), Err(BlockError::WrongExtraDataProof));
+
+        let mut block = valid_block(&leader);
+        block.header.consensus.chain_difficulty = block.header.consensus.chain_difficulty + 1;
+        assert_eq!(block.verify(magic), Err(BlockError::InvalidSignature));
+
+        let mut block = valid_block(&leader);
+        block.header.consensus.leader_key = key(2);
+        assert_eq!(block.verify(magic), Err(BlockError::InvalidSignature));
+    }
+}
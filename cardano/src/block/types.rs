@@ -1,4 +1,4 @@
-use std::{fmt};
+use std::{fmt, collections::BTreeMap};
 use hash;
 use hash::{HASH_SIZE, Blake2b256};
 use cbor_event::{self, de::RawCbor};
@@ -13,6 +13,7 @@ impl Version {
     pub fn new(major: u32, minor: u32, revision: u32) -> Self {
         Version { major: major, minor: minor, revision: revision }
     }
+    pub fn major(&self) -> u32 { self.major }
 }
 impl Default for Version {
     fn default() -> Self { Version::new(0,1,0) }
@@ -40,6 +41,24 @@ impl HeaderHash {
         Blake2b256::from_hex(hex).map(|h| HeaderHash(h))
     }
     pub fn new(bytes: &[u8]) -> Self { HeaderHash(Blake2b256::new(bytes))  }
+
+    /// shortened hexadecimal representation of the hash, handy for logs.
+    ///
+    /// Keeps the first 8 and the last 8 hexadecimal characters, `Display`
+    /// is still to be used when the full hash is needed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cardano::block::{HeaderHash};
+    ///
+    /// let hash = HeaderHash::from_hex(&"9d63d466fe1b5a3b2a5b1a0e5c1c9a2e8f0c5cee2e5d6b74ad6c5b6d4e8edfff").unwrap();
+    /// assert_eq!(hash.to_short_hex(), "9d63d466..4e8edfff");
+    /// ```
+    pub fn to_short_hex(&self) -> String {
+        let hex = format!("{}", self);
+        format!("{}..{}", &hex[..8], &hex[hex.len() - 8..])
+    }
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
@@ -88,6 +107,20 @@ impl Default for SoftwareVersion {
 #[derive(Debug, Clone)]
 pub struct BlockHeaderAttributes(cbor_event::Value);
 
+/// the attributes of the extra data of a block.
+///
+/// Both the genesis and the main blocks' extra data are expected to be
+/// `[attributes]` with `attributes` a map (usually empty).
+pub fn block_extra_attributes(extra: &cbor_event::Value) -> cbor_event::Result<&BTreeMap<cbor_event::ObjectKey, cbor_event::Value>> {
+    match extra {
+        cbor_event::Value::Array(v) if v.len() == 1 => match &v[0] {
+            cbor_event::Value::Object(attributes) => Ok(attributes),
+            value => Err(cbor_event::Error::CustomError(format!("Invalid block extra data: expected attributes, received {:?}", value))),
+        },
+        value => Err(cbor_event::Error::CustomError(format!("Invalid block extra data: expected an array of 1 element, received {:?}", value))),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HeaderExtraData {
     pub block_version: BlockVersion,
@@ -114,8 +147,33 @@ pub enum SscProof {
     Certificate(Blake2b256)
 }
 
-#[derive(Debug,Clone,Copy)]
+/// Chain difficulty of a block: the number of main blocks since the
+/// beginning of the chain. A genesis block has the same difficulty as
+/// the last main block of the previous epoch.
+///
+/// ```
+/// use cardano::block::ChainDifficulty;
+///
+/// let difficulty = ChainDifficulty::new(41);
+/// assert_eq!(difficulty + 1, ChainDifficulty::new(42));
+/// assert_eq!((difficulty + 1).height(), 42);
+/// assert!(difficulty < difficulty + 1);
+/// ```
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord)]
 pub struct ChainDifficulty(u64);
+impl ChainDifficulty {
+    pub fn new(difficulty: u64) -> Self { ChainDifficulty(difficulty) }
+    /// the height of the block in the chain, i.e. its number of main
+    /// block ancestors plus one for a main block.
+    pub fn height(&self) -> u64 { self.0 }
+}
+impl From<ChainDifficulty> for u64 {
+    fn from(cd: ChainDifficulty) -> u64 { cd.0 }
+}
+impl ::std::ops::Add<u64> for ChainDifficulty {
+    type Output = ChainDifficulty;
+    fn add(self, rhs: u64) -> Self::Output { ChainDifficulty(self.0 + rhs) }
+}
 
 impl fmt::Display for ChainDifficulty {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -123,9 +181,58 @@ impl fmt::Display for ChainDifficulty {
     }
 }
 
-pub type EpochId = u32;
+/// Epoch identifier, counting from the genesis epoch `0`.
+///
+/// ```
+/// use cardano::block::EpochId;
+///
+/// let epoch = EpochId::new(41);
+/// assert_eq!(epoch + 1, EpochId::new(42));
+/// assert_eq!(format!("{}", epoch), "41");
+/// assert_eq!("41".parse::<EpochId>().unwrap(), epoch);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EpochId(u32);
+impl EpochId {
+    pub fn new(epoch: u32) -> Self { EpochId(epoch) }
+    pub fn as_u32(&self) -> u32 { self.0 }
+}
+impl From<u32> for EpochId {
+    fn from(epoch: u32) -> Self { EpochId(epoch) }
+}
+impl From<EpochId> for u32 {
+    fn from(epoch: EpochId) -> Self { epoch.0 }
+}
+impl fmt::Display for EpochId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl ::std::str::FromStr for EpochId {
+    type Err = ::std::num::ParseIntError;
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        s.parse().map(EpochId)
+    }
+}
+impl ::std::ops::Add<u32> for EpochId {
+    type Output = EpochId;
+    fn add(self, rhs: u32) -> Self::Output { EpochId(self.0 + rhs) }
+}
+impl ::std::ops::AddAssign<u32> for EpochId {
+    fn add_assign(&mut self, rhs: u32) { self.0 += rhs }
+}
+impl ::std::ops::Sub<u32> for EpochId {
+    type Output = EpochId;
+    fn sub(self, rhs: u32) -> Self::Output { EpochId(self.0 - rhs) }
+}
+impl ::std::ops::SubAssign<u32> for EpochId {
+    fn sub_assign(&mut self, rhs: u32) { self.0 -= rhs }
+}
+
+/// number of slots in an epoch
+pub const EPOCH_SLOTS : u32 = 21600;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SlotId {
     pub epoch: EpochId,
     pub slotid: u32,
@@ -135,7 +242,29 @@ impl SlotId {
         SlotId { epoch: self.epoch, slotid: self.slotid + 1 }
     }
     pub fn slot_number(&self) -> usize {
-        (self.epoch as usize) * 21600 + (self.slotid as usize)
+        (self.epoch.0 as usize) * (EPOCH_SLOTS as usize) + (self.slotid as usize)
+    }
+    /// the number of the slot counting from the first slot of the epoch 0,
+    /// with epochs of `slots_per_epoch` slots.
+    ///
+    /// `None` if it does not fit in a `u64`.
+    ///
+    /// ```
+    /// use cardano::block::{EpochId, SlotId, EPOCH_SLOTS};
+    ///
+    /// let slot = SlotId { epoch: EpochId::new(2), slotid: 10 };
+    /// assert_eq!(slot.to_absolute(EPOCH_SLOTS as u64), Some(43210));
+    /// assert_eq!(slot.to_absolute(u64::max_value()), None);
+    /// ```
+    pub fn to_absolute(&self, slots_per_epoch: u64) -> Option<u64> {
+        (self.epoch.0 as u64).checked_mul(slots_per_epoch)?.checked_add(self.slotid as u64)
+    }
+    /// tell if one of the slots directly follows the other, including
+    /// across an epoch boundary. The sync uses it to report the slots
+    /// without a block.
+    pub fn is_adjacent(&self, other: &Self) -> bool {
+        let (a, b) = (self.slot_number(), other.slot_number());
+        a == b + 1 || b == a + 1
     }
 }
 impl fmt::Display for SlotId {
@@ -164,10 +293,7 @@ impl cbor_event::se::Serialize for Version {
 }
 impl cbor_event::de::Deserialize for Version {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Version: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "Version")?;
         let major = raw.unsigned_integer()? as u32;
         let minor = raw.unsigned_integer()? as u32;
         let revision = raw.unsigned_integer()? as u32;
@@ -186,10 +312,7 @@ impl cbor_event::se::Serialize for BlockVersion {
 }
 impl cbor_event::de::Deserialize for BlockVersion {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid BlockVersion: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "BlockVersion")?;
         let major = raw.unsigned_integer()? as u16;
         let minor = raw.unsigned_integer()? as u16;
         let revision = raw.unsigned_integer()? as u8;
@@ -207,10 +330,7 @@ impl cbor_event::se::Serialize for SoftwareVersion {
 }
 impl cbor_event::de::Deserialize for SoftwareVersion {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid SoftwareVersion: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "SoftwareVersion")?;
         let name  = raw.text()?;
         let version = raw.unsigned_integer()? as u32;
 
@@ -251,10 +371,7 @@ impl cbor_event::se::Serialize for HeaderExtraData {
 }
 impl cbor_event::de::Deserialize for HeaderExtraData {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(4) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid HeaderExtraData: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(4, "HeaderExtraData")?;
         let block_version    = cbor_event::de::Deserialize::deserialize(raw)?;
         let software_version = cbor_event::de::Deserialize::deserialize(raw)?;
         let attributes       = cbor_event::de::Deserialize::deserialize(raw)?;
@@ -327,6 +444,17 @@ impl cbor_event::de::Deserialize for SscProof {
     }
 }
 
+impl cbor_event::se::Serialize for EpochId {
+    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+        serializer.write_unsigned_integer(self.0 as u64)
+    }
+}
+impl cbor_event::de::Deserialize for EpochId {
+    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+        Ok(EpochId(raw.unsigned_integer()? as u32))
+    }
+}
+
 impl cbor_event::se::Serialize for ChainDifficulty {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
         serializer.write_array(cbor_event::Len::Len(1))?.write_unsigned_integer(self.0)
@@ -334,10 +462,7 @@ impl cbor_event::se::Serialize for ChainDifficulty {
 }
 impl cbor_event::de::Deserialize for ChainDifficulty {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(1) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid ChainDifficulty: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(1, "ChainDifficulty")?;
         Ok(ChainDifficulty(raw.unsigned_integer()?))
     }
 }
@@ -345,18 +470,65 @@ impl cbor_event::de::Deserialize for ChainDifficulty {
 impl cbor_event::se::Serialize for SlotId {
     fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
         serializer.write_array(cbor_event::Len::Len(2))?
-            .write_unsigned_integer(self.epoch as u64)?
+            .serialize(&self.epoch)?
             .write_unsigned_integer(self.slotid as u64)
     }
 }
 impl cbor_event::de::Deserialize for SlotId {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid SlotId: recieved array of {:?} elements", len)));
-        }
-        let epoch  = raw.unsigned_integer()? as u32;
+        raw.tuple(2, "SlotId")?;
+        let epoch  = raw.deserialize()?;
         let slotid = raw.unsigned_integer()? as u32;
         Ok(SlotId { epoch: epoch, slotid: slotid })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HASH : &'static str = "12d339c93f216d1b775297dcf465428aa43f73518466bf72fc6413448ec27069";
+
+    #[test]
+    fn header_hash_short_hex() {
+        let hash = HeaderHash::from_hex(&HASH).unwrap();
+        let full = format!("{}", hash);
+        let short = hash.to_short_hex();
+
+        assert_eq!(short.len(), 18);
+        assert_eq!(&short[..8], &full[..8]);
+        assert_eq!(&short[8..10], "..");
+        assert_eq!(&short[10..], &full[56..]);
+    }
+
+    #[test]
+    fn slot_id_set_and_gaps() {
+        use std::collections::HashSet;
+        let slot = |epoch, slotid| SlotId { epoch: EpochId::new(epoch), slotid: slotid };
+
+        let received = [slot(0, EPOCH_SLOTS - 2), slot(0, EPOCH_SLOTS - 1), slot(1, 0), slot(1, 2)];
+        let seen : HashSet<SlotId> = received.iter().cloned().collect();
+        assert!(seen.contains(&slot(1, 0)));
+        assert!(!seen.contains(&slot(1, 1)));
+
+        let gaps : Vec<_> = received.windows(2).filter(|w| !w[0].is_adjacent(&w[1])).map(|w| w[0].next()).collect();
+        assert_eq!(gaps, vec![slot(1, 1)]);
+        assert!(slot(1, 0).is_adjacent(&slot(0, EPOCH_SLOTS - 1)));
+        assert!(!slot(1, 0).is_adjacent(&slot(1, 0)));
+    }
+
+    #[test]
+    fn slot_id_to_absolute_overflow() {
+        let slot = |epoch, slotid| SlotId { epoch: EpochId::new(epoch), slotid: slotid };
+        let max = u64::max_value();
+
+        assert_eq!(slot(0, 5).to_absolute(max), Some(5));
+        assert_eq!(slot(1, 0).to_absolute(max), Some(max));
+        assert_eq!(slot(1, 1).to_absolute(max), None);
+        assert_eq!(slot(2, 0).to_absolute(max), None);
+        assert_eq!(slot(u32::max_value(), 0).to_absolute(max / u32::max_value() as u64), Some(max));
+        assert_eq!(slot(u32::max_value(), 1).to_absolute(max / u32::max_value() as u64), None);
+        assert_eq!(slot(u32::max_value(), u32::max_value()).to_absolute(EPOCH_SLOTS as u64),
+                   Some(u32::max_value() as u64 * (EPOCH_SLOTS as u64 + 1)));
+    }
+}
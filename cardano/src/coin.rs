@@ -5,7 +5,7 @@
 //!
 
 use cbor_event::{self, de::RawCbor, se::{Serializer}};
-use std::{ops, fmt, result};
+use std::{ops, fmt, result, convert::TryFrom};
 
 /// maximum value of a Lovelace.
 pub const MAX_COIN: u64 = 45_000_000_000__000_000;
@@ -19,19 +19,37 @@ pub enum Error {
     /// Max bound being: `MAX_COIN`.
     OutOfBound(u64),
 
-    Negative
+    Negative,
+
+    /// the weights to split a coin with are empty or all zero
+    NoWeight,
 }
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             &Error::OutOfBound(ref v) => write!(f, "Coin of value {} is out of bound. Max coin value: {}.", v, MAX_COIN),
             &Error::Negative          => write!(f, "Coin cannot hold a negative value"),
+            &Error::NoWeight          => write!(f, "Coin cannot be split without weights"),
         }
     }
 }
 
 pub type Result<T> = result::Result<T, Error>;
 
+/// error of `Coin::checked_sub_chain`: the subtraction of the operand
+/// at `index` would have made the `value` left so far negative
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Underflow {
+    pub index: usize,
+    pub value: Coin,
+    pub operand: Coin,
+}
+impl fmt::Display for Underflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot subtract {} (operand {}) from {}", self.operand, self.index, self.value)
+    }
+}
+
 // TODO: add custom implementation of `serde::de::Deserialize` so we can check the
 // upper bound of the `Coin`.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -75,6 +93,64 @@ impl Coin {
     pub fn new(v: u64) -> Result<Self> {
         if v <= MAX_COIN { Ok(Coin(v)) } else { Err(Error::OutOfBound(v)) }
     }
+
+    /// get the raw value of the coin (in Lovelace)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cardano::coin::{Coin};
+    ///
+    /// let coin = Coin::new(42).unwrap();
+    /// assert_eq!(coin.as_u64(), 42);
+    /// ```
+    pub fn as_u64(&self) -> u64 { self.0 }
+
+    /// split the coin in parts proportional to the given weights. The
+    /// rounding remainder goes to the first part, so the parts sum up to
+    /// the coin exactly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cardano::coin::{Coin};
+    ///
+    /// let parts = Coin::new(100).unwrap().split_weighted(&[1, 1, 1]).unwrap();
+    ///
+    /// assert_eq!(parts, vec![Coin::new(34).unwrap(), Coin::new(33).unwrap(), Coin::new(33).unwrap()]);
+    /// ```
+    pub fn split_weighted(self, weights: &[u64]) -> Result<Vec<Coin>> {
+        let total = weights.iter().map(|w| *w as u128).sum::<u128>();
+        if total == 0 { return Err(Error::NoWeight); }
+
+        let mut parts : Vec<Coin> = weights.iter().map(|w| {
+            Coin((self.0 as u128 * *w as u128 / total) as u64)
+        }).collect();
+        let remainder = self.0 - parts.iter().map(|c| c.0).sum::<u64>();
+        parts[0].0 += remainder;
+        Ok(parts)
+    }
+
+    /// subtract all the operands, in order, from the coin.
+    ///
+    /// Unlike chaining `-`, the error tells which subtraction failed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cardano::coin::{Coin};
+    ///
+    /// let coin = Coin::new(10).unwrap();
+    /// let operands = [Coin::new(3).unwrap(), Coin::new(8).unwrap()];
+    ///
+    /// assert_eq!(coin.checked_sub_chain(&operands[..1]).unwrap(), Coin::new(7).unwrap());
+    /// assert_eq!(coin.checked_sub_chain(&operands).unwrap_err().index, 1);
+    /// ```
+    pub fn checked_sub_chain(self, operands: &[Coin]) -> result::Result<Coin, Underflow> {
+        operands.iter().enumerate().try_fold(self, |value, (index, operand)| {
+            (value - operand).map_err(|_| Underflow { index, value, operand: *operand })
+        })
+    }
 }
 impl fmt::Display for Coin {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -92,6 +168,7 @@ impl cbor_event::de::Deserialize for Coin {
             match err {
                 Error::OutOfBound(v) => cbor_event::Error::CustomError(format!("coin ({}) out of bound, max: {}", v, MAX_COIN)),
                 Error::Negative => cbor_event::Error::CustomError("coin cannot hold negative value".to_owned()),
+                Error::NoWeight => cbor_event::Error::CustomError("coin cannot be split without weights".to_owned()),
             }
         })
     }
@@ -146,9 +223,75 @@ impl From<Coin> for u64 {
     fn from(c: Coin) -> u64 { c.0 }
 }
 
+impl TryFrom<u64> for Coin {
+    type Error = Error;
+    fn try_from(v: u64) -> Result<Coin> { Coin::new(v) }
+}
+
 impl From<u32> for Coin {
     fn from(c: u32) -> Coin { Coin(c as u64) }
 }
 pub fn sum_coins(coins: &[Coin]) -> Result<Coin> {
     coins.iter().fold(Coin::new(0), |acc, ref c| acc.and_then(|v| v + *c))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn try_from_u64() {
+        let coin = Coin::try_from(MAX_COIN).unwrap();
+        assert_eq!(coin.as_u64(), MAX_COIN);
+        assert_eq!(u64::from(coin), MAX_COIN);
+    }
+
+    #[test]
+    fn try_from_u64_out_of_bound() {
+        assert_eq!(Coin::try_from(MAX_COIN + 1), Err(Error::OutOfBound(MAX_COIN + 1)));
+    }
+
+    #[test]
+    fn split_weighted_even() {
+        let parts = Coin::new(900).unwrap().split_weighted(&[2, 2, 2]).unwrap();
+        assert_eq!(parts, vec![Coin::new(300).unwrap(); 3]);
+    }
+
+    #[test]
+    fn split_weighted_uneven() {
+        let parts = Coin::new(1000).unwrap().split_weighted(&[1, 3, 0, 6]).unwrap();
+        assert_eq!(parts.iter().map(Coin::as_u64).collect::<Vec<_>>(), vec![100, 300, 0, 600]);
+
+        // the weights do not overflow with the maximum coin
+        let parts = Coin::new(MAX_COIN).unwrap().split_weighted(&[u64::max_value(), u64::max_value()]).unwrap();
+        assert_eq!(parts, vec![Coin::new(MAX_COIN / 2).unwrap(); 2]);
+    }
+
+    #[test]
+    fn split_weighted_remainder_to_the_first() {
+        let parts = Coin::new(10).unwrap().split_weighted(&[1, 1, 1]).unwrap();
+        assert_eq!(parts.iter().map(Coin::as_u64).collect::<Vec<_>>(), vec![4, 3, 3]);
+        // even when the first has the smallest weight
+        let parts = Coin::new(11).unwrap().split_weighted(&[1, 2, 2]).unwrap();
+        assert_eq!(parts.iter().map(Coin::as_u64).collect::<Vec<_>>(), vec![3, 4, 4]);
+        assert_eq!(sum_coins(&parts), Ok(Coin::new(11).unwrap()));
+
+        assert_eq!(Coin::new(10).unwrap().split_weighted(&[]), Err(Error::NoWeight));
+        assert_eq!(Coin::new(10).unwrap().split_weighted(&[0, 0]), Err(Error::NoWeight));
+    }
+
+    #[test]
+    fn checked_sub_chain_underflow() {
+        let coin = Coin::new(100).unwrap();
+        let operands = [Coin::new(30).unwrap(), Coin::new(60).unwrap(), Coin::new(20).unwrap(), Coin::new(5).unwrap()];
+
+        assert_eq!(coin.checked_sub_chain(&operands[..2]), Ok(Coin::new(10).unwrap()));
+        assert_eq!(coin.checked_sub_chain(&operands), Err(Underflow {
+            index: 2,
+            value: Coin::new(10).unwrap(),
+            operand: Coin::new(20).unwrap(),
+        }));
+        // the chained `-` only tells that one of them underflowed
+        assert_eq!(coin - operands[0] - operands[1] - operands[2] - operands[3], Err(Error::Negative));
+    }
+}
@@ -5,7 +5,7 @@
 //!
 
 use cbor_event::{self, de::RawCbor, se::{Serializer}};
-use std::fmt;
+use std::{fmt, num, str};
 
 /// this is the protocol magic number
 ///
@@ -32,6 +32,32 @@ impl fmt::Display for ProtocolMagic {
         write!(f, "{}", self.0)
     }
 }
+impl fmt::LowerHex for ProtocolMagic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+impl str::FromStr for ProtocolMagic {
+    type Err = num::ParseIntError;
+
+    /// parse the protocol magic from its decimal notation, or from its
+    /// hexadecimal notation when prefixed by `0x`.
+    ///
+    /// ```
+    /// use cardano::config::{ProtocolMagic};
+    ///
+    /// assert_eq!("764824073".parse(), Ok(ProtocolMagic::default()));
+    /// assert_eq!("0x2d964a09".parse(), Ok(ProtocolMagic::default()));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let v = if s.starts_with("0x") || s.starts_with("0X") {
+            u32::from_str_radix(&s[2..], 16)?
+        } else {
+            s.parse()?
+        };
+        Ok(ProtocolMagic::new(v))
+    }
+}
 impl Default for ProtocolMagic {
     fn default() -> Self { ProtocolMagic::new(764824073) }
 }
@@ -47,15 +73,37 @@ impl cbor_event::Deserialize for ProtocolMagic {
     }
 }
 
+/// default number of unused addresses the
+/// [`AddressLookup`](../wallet/bip44/struct.AddressLookup.html) keeps ahead
+/// of the last used address of each chain.
+pub const DEFAULT_GAP_LIMIT : u32 = 20;
+
+/// default maximum size, in bytes, of the encoded `TxAux` the nodes accept
+pub const DEFAULT_MAX_TX_BYTES : usize = 4096;
+
 /// Configuration for the wallet-crypto
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct Config {
-    pub protocol_magic: ProtocolMagic
+    pub protocol_magic: ProtocolMagic,
+
+    /// number of unused addresses to look ahead of the last used one
+    /// when scanning for the wallet's addresses, see
+    /// `wallet::bip44::AddressLookup`.
+    #[serde(default = "default_gap_limit")]
+    pub gap_limit: u32,
+
+    /// maximum size, in bytes, of the transactions the wallet creates
+    #[serde(default = "default_max_tx_bytes")]
+    pub max_tx_bytes: usize,
 }
+fn default_gap_limit() -> u32 { DEFAULT_GAP_LIMIT }
+fn default_max_tx_bytes() -> usize { DEFAULT_MAX_TX_BYTES }
 impl Config {
     pub fn new(protocol_magic: ProtocolMagic) -> Self {
         Config {
-            protocol_magic: protocol_magic
+            protocol_magic: protocol_magic,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            max_tx_bytes: DEFAULT_MAX_TX_BYTES,
         }
     }
 }
@@ -64,3 +112,35 @@ impl Default for Config {
         Config::new(ProtocolMagic::default())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn protocol_magic_format() {
+        let mainnet = ProtocolMagic::default();
+        assert_eq!(format!("{}", mainnet), "764824073");
+        assert_eq!(format!("0x{:x}", mainnet), "0x2d964a09");
+    }
+
+    #[test]
+    fn protocol_magic_parse() {
+        assert_eq!("633343913".parse(), Ok(ProtocolMagic::new(633343913)));
+        assert_eq!("0x2D964A09".parse(), Ok(ProtocolMagic::default()));
+        assert!("0x".parse::<ProtocolMagic>().is_err());
+        assert!("mainnet".parse::<ProtocolMagic>().is_err());
+        assert!("4294967296".parse::<ProtocolMagic>().is_err());
+    }
+
+    #[test]
+    fn config_default_gap_limit() {
+        let config : Config = ::serde_json::from_str(r#"{"protocol_magic":633343913}"#).unwrap();
+        assert_eq!(config, Config::new(ProtocolMagic::new(633343913)));
+        assert_eq!(config.gap_limit, DEFAULT_GAP_LIMIT);
+        assert_eq!(config.max_tx_bytes, DEFAULT_MAX_TX_BYTES);
+
+        let config : Config = ::serde_json::from_str(r#"{"protocol_magic":633343913,"gap_limit":50}"#).unwrap();
+        assert_eq!(config.gap_limit, 50);
+    }
+}
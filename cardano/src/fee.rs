@@ -3,9 +3,10 @@
 use std::{fmt, result, ops::{Add, Mul}};
 use coin;
 use coin::{Coin};
-use tx::{TxOut, Tx, TxInWitness, TxAux, txaux_serialize};
+use tx::{TxIn, TxOut, Tx, TxInWitness, TxAux, txaux_serialize};
 use txutils::{Input, OutputPolicy, output_sum};
 use cbor_event;
+use hash::{Blake2b256};
 
 /// A fee value that represent either a fee to pay, or a fee paid.
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
@@ -20,6 +21,18 @@ pub enum Error {
     NoInputs,
     NoOutputs,
     NotEnoughInput,
+    /// the key derived from the addressing of the input does not
+    /// match the address of the spent output
+    AddressMismatch(TxIn),
+    /// the signature of the input's witness does not match the transaction
+    InvalidWitness(TxIn),
+    /// the number of witnesses (second) is not the number of inputs (first)
+    WitnessCountMismatch(usize, usize),
+    /// the number of spent outputs (second) is not the number of inputs (first)
+    SpentCountMismatch(usize, usize),
+    /// the encoded transaction (of the given size in bytes) is larger
+    /// than the maximum size accepted by the nodes
+    TxTooLarge(usize),
     CoinError(coin::Error),
     CborError(cbor_event::Error)
 }
@@ -29,6 +42,11 @@ impl fmt::Display for Error {
             &Error::NoInputs => write!(f, "No inputs given for fee estimation"),
             &Error::NoOutputs => write!(f, "No outputs given for fee estimation"),
             &Error::NotEnoughInput => write!(f, "Not enough funds to cover outputs and fees"),
+            &Error::AddressMismatch(ref txin) => write!(f, "The key of the input {} does not match its address", txin),
+            &Error::InvalidWitness(ref txin) => write!(f, "The witness of the input {} does not sign the transaction", txin),
+            &Error::WitnessCountMismatch(inputs, witnesses) => write!(f, "{} witnesses given for {} inputs", witnesses, inputs),
+            &Error::SpentCountMismatch(inputs, spent) => write!(f, "{} spent outputs given for {} inputs", spent, inputs),
+            &Error::TxTooLarge(size) => write!(f, "The transaction is too large ({} bytes), try to spend fewer inputs", size),
             &Error::CoinError(ref err) => write!(f, "Error on coin operations: {}", err),
             &Error::CborError(ref err) => write!(f, "Error while performing cbor serialization: {}", err),
         }
@@ -47,7 +65,7 @@ impl From<cbor_event::Error> for Error {
 /// Algorithm trait for input selections
 pub trait SelectionAlgorithm {
     /// This takes from input:
-    /// * Selection Policy
+    /// * Selection strategy (see `SelectionStrategy`)
     /// * The tx inputs with at minimum 1 entry
     /// * The tx outputs with at minimum 1 entry
     /// * Extended address of where to send the remain
@@ -57,14 +75,15 @@ pub trait SelectionAlgorithm {
     /// * The computed fee associated
     /// * The inputs selected
     /// * The number of coin remaining that will be associated to the extended address specified
-    fn compute<'a, 'b, I, O, Addressing>( &self
-                                        , policy: SelectionPolicy
-                                        , inputs: I
-                                        , outputs: O
-                                        , output_policy: &OutputPolicy
-                                        )
+    fn compute<'a, 'b, S, I, O, Addressing>( &self
+                                           , policy: S
+                                           , inputs: I
+                                           , outputs: O
+                                           , output_policy: &OutputPolicy
+                                           )
             -> Result<(Fee, Vec<&'a Input<Addressing>>, Coin)>
-        where I : 'a + Iterator<Item = &'a Input<Addressing>> + ExactSizeIterator
+        where S : SelectionStrategy
+            , I : 'a + Iterator<Item = &'a Input<Addressing>> + ExactSizeIterator
             , O : 'b + Iterator<Item = &'b TxOut> + Clone
             , Addressing: 'a
     ;
@@ -125,6 +144,13 @@ impl LinearFee {
         let coin = Coin::new(fee.to_integral())?;
         Ok(Fee(coin))
     }
+
+    /// estimate the fee of the given transaction once it is signed, with
+    /// one witness per input.
+    pub fn estimate_for_tx(&self, tx: &Tx) -> Result<Fee> {
+        let txbytes = cbor!(tx)?;
+        self.estimate(txbytes.len() + CBOR_TXAUX_OVERHEAD + (TX_IN_WITNESS_CBOR_SIZE * tx.inputs.len()))
+    }
 }
 
 /// Calculation of fees for a specific chosen algorithm
@@ -154,14 +180,15 @@ impl Default for LinearFee {
 const TX_IN_WITNESS_CBOR_SIZE: usize = 140;
 const CBOR_TXAUX_OVERHEAD: usize = 51;
 impl SelectionAlgorithm for LinearFee {
-    fn compute<'a, 'b, I, O, Addressing>( &self
-                                        , policy: SelectionPolicy
-                                        , inputs: I
-                                        , outputs: O
-                                        , output_policy: &OutputPolicy
-                                        )
+    fn compute<'a, 'b, S, I, O, Addressing>( &self
+                                           , policy: S
+                                           , inputs: I
+                                           , outputs: O
+                                           , output_policy: &OutputPolicy
+                                           )
             -> Result<(Fee, Vec<&'a Input<Addressing>>, Coin)>
-        where I : 'a + Iterator<Item = &'a Input<Addressing>> + ExactSizeIterator
+        where S : SelectionStrategy
+            , I : 'a + Iterator<Item = &'a Input<Addressing>> + ExactSizeIterator
             , O : 'b + Iterator<Item = &'b TxOut> + Clone
             , Addressing: 'a
     {
@@ -176,12 +203,7 @@ impl SelectionAlgorithm for LinearFee {
         let mut txins = Vec::new();
         let     txouts : Vec<TxOut> = outputs.cloned().collect();
 
-        // for now we only support this selection algorithm
-        // we need to remove this assert when we extend to more
-        // granulated selection policy
-        assert!(policy == SelectionPolicy::FirstMatchFirst);
-
-        for input in inputs {
+        for input in policy.select(inputs.collect(), output_value) {
             input_value = (input_value + input.value())?;
             selected_inputs.push(input);
             txins.push(input.ptr.clone());
@@ -222,11 +244,48 @@ impl SelectionAlgorithm for LinearFee {
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
 pub enum SelectionPolicy {
     /// select the first inputs that matches, no optimisation
-    FirstMatchFirst
+    FirstMatchFirst,
+    /// select the inputs in a pseudo random order given by the seed: the
+    /// same seed selects the same inputs, whatever order they are given in
+    Deterministic(u64),
 }
 impl Default for SelectionPolicy {
     fn default() -> Self { SelectionPolicy::FirstMatchFirst }
 }
+impl SelectionStrategy for SelectionPolicy {
+    fn select<'a, Addressing>(&self, inputs: Vec<&'a Input<Addressing>>, _target: Coin) -> Vec<&'a Input<Addressing>> {
+        match self {
+            SelectionPolicy::FirstMatchFirst => inputs,
+            SelectionPolicy::Deterministic(seed) => {
+                let mut keyed : Vec<_> = inputs.into_iter().map(|input| {
+                    let mut buf = Vec::with_capacity(8 + 32 + 4);
+                    buf.extend_from_slice(&seed.to_be_bytes());
+                    buf.extend_from_slice(input.ptr.id.as_ref());
+                    buf.extend_from_slice(&input.ptr.index.to_be_bytes());
+                    (Blake2b256::new(&buf), input)
+                }).collect();
+                keyed.sort_by_key(|&(hash, _)| hash);
+                keyed.into_iter().map(|(_, input)| input).collect()
+            },
+        }
+    }
+}
+
+/// strategy choosing which inputs to spend, for custom selections
+/// (privacy preserving, consolidating the small inputs...).
+///
+/// The strategy returns the inputs to consider, in order of preference.
+/// The selection algorithm takes them in this order until the outputs
+/// and the fee are covered, as the fee depends on the number of inputs.
+pub trait SelectionStrategy {
+    /// `target` is the value of the outputs, the fee is not included.
+    fn select<'a, Addressing>(&self, inputs: Vec<&'a Input<Addressing>>, target: Coin) -> Vec<&'a Input<Addressing>>;
+}
+impl<'s, S: SelectionStrategy> SelectionStrategy for &'s S {
+    fn select<'a, Addressing>(&self, inputs: Vec<&'a Input<Addressing>>, target: Coin) -> Vec<&'a Input<Addressing>> {
+        (*self).select(inputs, target)
+    }
+}
 
 
 #[cfg(test)]
@@ -257,6 +316,70 @@ mod test {
         test_milli_add_eq(         241,        407);
     }
 
+    use address::ExtendedAddr;
+    use hdwallet;
+    use tx::{TxId, TxIn};
+
+    /// prefer the most recently added inputs
+    struct Reverse;
+    impl SelectionStrategy for Reverse {
+        fn select<'a, Addressing>(&self, mut inputs: Vec<&'a Input<Addressing>>, _target: Coin) -> Vec<&'a Input<Addressing>> {
+            inputs.reverse();
+            inputs
+        }
+    }
+
+    #[test]
+    fn custom_selection_strategy() {
+        let address = ExtendedAddr::new_simple(hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([0;hdwallet::SEED_SIZE])).public());
+        let inputs : Vec<Input<()>> = (0..3).map(|index| {
+            Input::new(TxIn::new(TxId::new(&[0;32]), index), TxOut::new(address.clone(), Coin::new(10_000_000).unwrap()), ())
+        }).collect();
+        let outputs = vec![TxOut::new(address.clone(), Coin::new(1_000_000).unwrap())];
+        let change = OutputPolicy::One(address.clone());
+        let alg = LinearFee::default();
+        let selected = |inputs: Vec<&Input<()>>| inputs.iter().map(|input| input.ptr.index).collect::<Vec<_>>();
+
+        let (fee, first, _) = alg.compute(SelectionPolicy::FirstMatchFirst, inputs.iter(), outputs.iter(), &change).unwrap();
+        assert_eq!(selected(first), vec![0]);
+
+        let (reverse_fee, last, _) = alg.compute(Reverse, inputs.iter(), outputs.iter(), &change).unwrap();
+        assert_eq!(selected(last), vec![2]);
+        assert_eq!(fee, reverse_fee);
+
+        // more than one input is needed, they are taken in the given order
+        let outputs = vec![TxOut::new(address.clone(), Coin::new(15_000_000).unwrap())];
+        let (_, last, _) = alg.compute(&Reverse, inputs.iter(), outputs.iter(), &change).unwrap();
+        assert_eq!(selected(last), vec![2, 1]);
+    }
+
+    #[test]
+    fn deterministic_selection() {
+        let address = ExtendedAddr::new_simple(hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([0;hdwallet::SEED_SIZE])).public());
+        let inputs : Vec<Input<()>> = (0..20).map(|index| {
+            Input::new(TxIn::new(TxId::new(&[0;32]), index), TxOut::new(address.clone(), Coin::new(1_000_000).unwrap()), ())
+        }).collect();
+        let outputs = vec![TxOut::new(address.clone(), Coin::new(4_000_000).unwrap())];
+        let change = OutputPolicy::One(address.clone());
+        let alg = LinearFee::default();
+        let select = |seed, inputs: &[Input<()>]| {
+            let (_, selected, _) = alg.compute(SelectionPolicy::Deterministic(seed), inputs.iter(), outputs.iter(), &change).unwrap();
+            selected.iter().map(|input| input.ptr.index).collect::<Vec<_>>()
+        };
+
+        let selected = select(42, &inputs);
+        assert_eq!(selected.len(), 5);
+        assert_eq!(select(42, &inputs), selected);
+        assert_ne!(selected, vec![0, 1, 2, 3, 4]);
+
+        // the order the inputs are given in does not matter
+        let mut reversed = inputs.clone();
+        reversed.reverse();
+        assert_eq!(select(42, &reversed), selected);
+
+        assert_ne!(select(43, &inputs), selected);
+    }
+
     #[test]
     fn check_fee_mul() {
         test_milli_mul_eq(10124128_192, 802_192);
@@ -10,14 +10,15 @@ use serde;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum Error {
-    InvalidHashSize(usize),
+    /// the expected and the received number of bytes
+    InvalidHashSize(usize, usize),
     HexadecimalError(hex::Error),
 }
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &Error::InvalidHashSize(sz) => {
-                write!(f, "invalid hash size, expected {} but received {} bytes.", HASH_SIZE, sz)
+            &Error::InvalidHashSize(expected, sz) => {
+                write!(f, "invalid hash size, expected {} but received {} bytes.", expected, sz)
             },
             &Error::HexadecimalError(err) => {
                 write!(f, "Invalid hexadecimal input: {}", err)
@@ -32,92 +33,128 @@ impl From<hex::Error> for Error {
 pub type Result<T> = result::Result<T, Error>;
 
 pub const HASH_SIZE : usize = 32;
+pub const HASH_SIZE_224 : usize = 28;
 
-/// Blake2b 256 bits
-#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
-pub struct Blake2b256([u8;HASH_SIZE]);
-impl AsRef<[u8]> for Blake2b256 {
-    fn as_ref(&self) -> &[u8] { self.0.as_ref() }
+/// compute the Blake2b of `buf` into `out`, the length of the digest
+/// being the length of `out`.
+pub fn blake2b_into(buf: &[u8], out: &mut [u8]) {
+    let mut b2b = Blake2b::new(out.len());
+    b2b.input(buf);
+    b2b.result(out);
 }
-impl Blake2b256 {
-    pub fn new(buf: &[u8]) -> Self
-    {
-        let mut b2b = Blake2b::new(HASH_SIZE);
-        let mut out = [0;HASH_SIZE];
-        b2b.input(buf);
-        b2b.result(&mut out);
-        Self::from_bytes(out)
-    }
 
-    pub fn bytes<'a>(&'a self) -> &'a [u8;HASH_SIZE] { &self.0 }
-    pub fn into_bytes(self) -> [u8;HASH_SIZE] { self.0 }
+macro_rules! blake2b_hash {
+    ($name:ident, $size:expr, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+        pub struct $name([u8;$size]);
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] { self.0.as_ref() }
+        }
+        impl $name {
+            pub fn new(buf: &[u8]) -> Self
+            {
+                let mut out = [0;$size];
+                blake2b_into(buf, &mut out);
+                Self::from_bytes(out)
+            }
 
-    pub fn from_bytes(bytes :[u8;HASH_SIZE]) -> Self { Blake2b256(bytes) }
-    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
-        if bytes.len() != HASH_SIZE { return Err(Error::InvalidHashSize(bytes.len())); }
-        let mut buf = [0;HASH_SIZE];
+            pub fn bytes<'a>(&'a self) -> &'a [u8;$size] { &self.0 }
+            pub fn into_bytes(self) -> [u8;$size] { self.0 }
 
-        buf[0..HASH_SIZE].clone_from_slice(bytes);
-        Ok(Self::from_bytes(buf))
-    }
-    pub fn from_hex<S: AsRef<str>>(hex: &S) -> Result<Self> {
-        let bytes = hex::decode(hex.as_ref())?;
-        Self::from_slice(&bytes)
-    }
-}
-impl fmt::Debug for Blake2b256 {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", hex::encode(&self.0[..]))
-    }
-}
-impl fmt::Display for Blake2b256 {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", hex::encode(&self.0[..]))
-    }
-}
-impl cbor_event::de::Deserialize for Blake2b256 {
-    fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let bytes = raw.bytes()?;
-        match Blake2b256::from_slice(&bytes) {
-            Ok(digest) => Ok(digest),
-            Err(Error::InvalidHashSize(sz)) => Err(cbor_event::Error::NotEnough(sz, HASH_SIZE)),
-            Err(err) => Err(cbor_event::Error::CustomError(format!("unexpected error: {:?}", err))),
+            pub fn from_bytes(bytes :[u8;$size]) -> Self { $name(bytes) }
+            pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+                if bytes.len() != $size { return Err(Error::InvalidHashSize($size, bytes.len())); }
+                let mut buf = [0;$size];
+
+                buf[0..$size].clone_from_slice(bytes);
+                Ok(Self::from_bytes(buf))
+            }
+            pub fn from_hex<S: AsRef<str>>(hex: &S) -> Result<Self> {
+                let bytes = hex::decode(hex.as_ref())?;
+                Self::from_slice(&bytes)
+            }
         }
-    }
-}
-impl cbor_event::se::Serialize for Blake2b256 {
-    fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
-        serializer.write_bytes(&self.0)
-    }
-}
-impl serde::Serialize for Blake2b256
-{
-    #[inline]
-    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
-        where S: serde::Serializer,
-    {
-        if serializer.is_human_readable() {
-            serializer.serialize_str(&hex::encode(self.as_ref()))
-        } else {
-            serializer.serialize_bytes(&self.as_ref())
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(&self.0[..]))
+            }
+        }
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(&self.0[..]))
+            }
+        }
+        impl cbor_event::de::Deserialize for $name {
+            fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
+                let bytes = raw.bytes()?;
+                match $name::from_slice(&bytes) {
+                    Ok(digest) => Ok(digest),
+                    Err(Error::InvalidHashSize(_, sz)) => Err(cbor_event::Error::NotEnough(sz, $size)),
+                    Err(err) => Err(cbor_event::Error::CustomError(format!("unexpected error: {:?}", err))),
+                }
+            }
+        }
+        impl cbor_event::se::Serialize for $name {
+            fn serialize<W: ::std::io::Write>(&self, serializer: cbor_event::se::Serializer<W>) -> cbor_event::Result<cbor_event::se::Serializer<W>> {
+                serializer.write_bytes(&self.0)
+            }
+        }
+        impl serde::Serialize for $name
+        {
+            #[inline]
+            fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+                where S: serde::Serializer,
+            {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&hex::encode(self.as_ref()))
+                } else {
+                    serializer.serialize_bytes(&self.as_ref())
+                }
+            }
+        }
+        impl<'de> serde::Deserialize<'de> for $name
+        {
+            fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+                where D: serde::Deserializer<'de>
+            {
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_str(HashVisitor::<$name>::new())
+                } else {
+                    deserializer.deserialize_bytes(HashVisitor::<$name>::new())
+                }
+            }
+        }
+        impl FromHashVisitor for $name {
+            const NAME : &'static str = stringify!($name);
+            fn from_hex(hex: &str) -> Result<Self> { $name::from_hex(&hex) }
+            fn from_slice(bytes: &[u8]) -> Result<Self> { $name::from_slice(bytes) }
         }
     }
 }
-struct HashVisitor();
-impl HashVisitor { fn new() -> Self { HashVisitor {} } }
-impl<'de> serde::de::Visitor<'de> for HashVisitor {
-    type Value = Blake2b256;
+
+// the hashes the `HashVisitor` can deserialize
+trait FromHashVisitor : Sized {
+    const NAME : &'static str;
+    fn from_hex(hex: &str) -> Result<Self>;
+    fn from_slice(bytes: &[u8]) -> Result<Self>;
+}
+
+struct HashVisitor<T>(::std::marker::PhantomData<T>);
+impl<T> HashVisitor<T> { fn new() -> Self { HashVisitor(::std::marker::PhantomData) } }
+impl<'de, T: FromHashVisitor> serde::de::Visitor<'de> for HashVisitor<T> {
+    type Value = T;
 
     fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "Expecting a Blake2b_256 hash (`Hash`)")
+        write!(fmt, "Expecting a {} hash", T::NAME)
     }
 
     fn visit_str<'a, E>(self, v: &'a str) -> result::Result<Self::Value, E>
         where E: serde::de::Error
     {
-        match Self::Value::from_hex(&v) {
+        match T::from_hex(v) {
             Err(Error::HexadecimalError(err)) => Err(E::custom(format!("{}", err))),
-            Err(Error::InvalidHashSize(sz)) => Err(E::invalid_length(sz, &"32 bytes")),
+            Err(Error::InvalidHashSize(expected, sz)) => Err(E::invalid_length(sz, &format!("{} bytes", expected).as_str())),
             Ok(h) => Ok(h)
         }
     }
@@ -125,25 +162,19 @@ impl<'de> serde::de::Visitor<'de> for HashVisitor {
     fn visit_bytes<'a, E>(self, v: &'a [u8]) -> result::Result<Self::Value, E>
         where E: serde::de::Error
     {
-        match Self::Value::from_slice(v) {
-            Err(Error::InvalidHashSize(sz)) => Err(E::invalid_length(sz, &"32 bytes")),
+        match T::from_slice(v) {
+            Err(Error::InvalidHashSize(expected, sz)) => Err(E::invalid_length(sz, &format!("{} bytes", expected).as_str())),
             Err(err) => panic!("unexpected error: {}", err),
             Ok(h) => Ok(h)
         }
     }
 }
-impl<'de> serde::Deserialize<'de> for Blake2b256
-{
-    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
-        where D: serde::Deserializer<'de>
-    {
-        if deserializer.is_human_readable() {
-            deserializer.deserialize_str(HashVisitor::new())
-        } else {
-            deserializer.deserialize_bytes(HashVisitor::new())
-        }
-    }
-}
+
+blake2b_hash!(Blake2b256, HASH_SIZE, "Blake2b 256 bits");
+blake2b_hash!(Blake2b224, HASH_SIZE_224, "Blake2b 224 bits");
+
+/// 28 bytes hash, the size of the address and stakeholder hashes
+pub type Hash28 = Blake2b224;
 
 #[cfg(test)]
 mod test {
@@ -154,4 +185,40 @@ mod test {
     fn encode_decode() {
         assert!(cbor_event::test_encode_decode(&Blake2b256::new([0;32].as_ref())).unwrap())
     }
+
+    #[test]
+    fn encode_decode_224() {
+        assert!(cbor_event::test_encode_decode(&Blake2b224::new([0;32].as_ref())).unwrap())
+    }
+
+    #[test]
+    fn blake2b_lengths() {
+        assert_eq!(Blake2b256::new(b"").to_string(), "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8");
+        assert_eq!(Hash28::new(b"").to_string(), "836cc68931c2e4e3e838602eca1902591d216837bafddfe6f0c8cb07");
+        assert_eq!(Hash28::new(b"abc").to_string(), "9bd237b02a29e43bdd6738afa5b53ff0eee178d6210b618e4511aec8");
+
+        let mut out = [0;20];
+        blake2b_into(b"", &mut out);
+        assert_eq!(hex::encode(&out), "3345524abf6bbe1809449224b5972c41790b6cf2");
+    }
+
+    #[test]
+    fn from_slice_checks_the_length() {
+        let bytes = [0;32];
+        assert_eq!(Blake2b256::from_slice(&bytes[..28]), Err(Error::InvalidHashSize(32, 28)));
+        assert_eq!(Blake2b224::from_slice(&bytes), Err(Error::InvalidHashSize(28, 32)));
+        assert!(Blake2b224::from_slice(&bytes[..28]).is_ok());
+
+        let decoded : cbor_event::Result<Blake2b224> = RawCbor::from(&cbor!(Blake2b256::new(b"")).unwrap()[..]).deserialize();
+        assert!(decoded.is_err());
+    }
+
+    #[test]
+    fn serde_hex() {
+        let hash = Hash28::new(b"abc");
+        let json = ::serde_json::to_string(&hash).unwrap();
+        assert_eq!(json, "\"9bd237b02a29e43bdd6738afa5b53ff0eee178d6210b618e4511aec8\"");
+        assert_eq!(::serde_json::from_str::<Hash28>(&json).unwrap(), hash);
+        assert!(::serde_json::from_str::<Blake2b256>(&json).is_err());
+    }
 }
@@ -60,6 +60,7 @@ pub enum Error {
     InvalidXPrv(&'static str),
     HexadecimalError(hex::Error),
     ExpectedSoftDerivation,
+    ExpectedHardDerivation,
     InvalidDerivation
 }
 
@@ -87,6 +88,9 @@ impl fmt::Display for Error {
             &Error::ExpectedSoftDerivation => {
                write!(f, "expected soft derivation")
             },
+            &Error::ExpectedHardDerivation => {
+               write!(f, "expected hard derivation")
+            },
             &Error::InvalidDerivation => {
                write!(f, "invalid derivation")
             },
@@ -312,6 +316,24 @@ impl XPrv {
     pub fn derive(&self, scheme: DerivationScheme, index: DerivationIndex) -> Self {
         derive_private(self, index, scheme)
     }
+
+    /// same as `derive` but fails with `Error::ExpectedHardDerivation`
+    /// if the index is not in the hardened range (`0x80000000` and above).
+    pub fn derive_hardened(&self, scheme: DerivationScheme, index: DerivationIndex) -> Result<Self> {
+        match to_type(index) {
+            DerivationType::Hard(_) => Ok(self.derive(scheme, index)),
+            DerivationType::Soft(_) => Err(Error::ExpectedHardDerivation),
+        }
+    }
+
+    /// same as `derive` but fails with `Error::ExpectedSoftDerivation`
+    /// if the index is in the hardened range, as `XPub::derive` does.
+    pub fn derive_soft(&self, scheme: DerivationScheme, index: DerivationIndex) -> Result<Self> {
+        match to_type(index) {
+            DerivationType::Soft(_) => Ok(self.derive(scheme, index)),
+            DerivationType::Hard(_) => Err(Error::ExpectedSoftDerivation),
+        }
+    }
 }
 impl PartialEq for XPrv {
     fn eq(&self, rhs: &XPrv) -> bool { fixed_time_eq(self.as_ref(), rhs.as_ref()) }
@@ -1029,6 +1051,23 @@ mod tests {
         assert_eq!(child_prv.public(), child_xpub);
     }
 
+    #[test]
+    fn xprv_derive_checks_the_derivation_type() {
+        let prv = XPrv::from_bytes_verified(D1).unwrap();
+        assert_eq!(prv.derive_hardened(DerivationScheme::V2, 0x80000000).unwrap(), prv.derive(DerivationScheme::V2, 0x80000000));
+        assert_eq!(prv.derive_soft(DerivationScheme::V2, 0x1).unwrap(), prv.derive(DerivationScheme::V2, 0x1));
+
+        // a soft account value where a hardened one is expected
+        match prv.derive_hardened(DerivationScheme::V2, 0x1) {
+            Err(Error::ExpectedHardDerivation) => (),
+            _ => panic!("a soft index must be rejected"),
+        }
+        match prv.derive_soft(DerivationScheme::V2, 0x80000001) {
+            Err(Error::ExpectedSoftDerivation) => (),
+            _ => panic!("a hard index must be rejected"),
+        }
+    }
+
     #[test]
     fn xpub_derive_v2()  {
         let derivation_index = 0x10000000;
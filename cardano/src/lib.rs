@@ -49,3 +49,6 @@ pub mod wallet;
 pub mod block;
 
 pub mod vss;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -113,7 +113,10 @@ impl PrivateKey {
     }
 
     pub fn public(&self) -> PublicKey {
-        PublicKey::from_bytes(ed25519::to_public(&self.0))
+        // the private key is the ed25519 seed followed by its public key
+        let mut pk = [0;PUBLICKEY_SIZE];
+        pk.clone_from_slice(&self.0[32..]);
+        PublicKey::from_bytes(pk)
     }
 
     pub fn sign(&self, bytes: &[u8]) -> Signature {
@@ -0,0 +1,89 @@
+//! Block fixtures for the tests
+//!
+//! Available to the tests of this crate, and to the crates depending on it
+//! with the `testing` feature.
+
+use address::StakeholderId;
+use block::{genesis, normal, types, Block, RawBlock, HeaderHash, EpochId, SlotId, ChainDifficulty};
+use cbor_event::{Len, Special, Value, se::Serializer, de::{Deserialize, RawCbor}};
+use hash::Blake2b256;
+use hdwallet;
+use tx::TxProof;
+
+fn decode<T: Deserialize>(bytes: &[u8]) -> T { RawCbor::from(bytes).deserialize().unwrap() }
+
+/// a minimal genesis block of the given epoch and chain difficulty,
+/// child of `previous`
+pub fn genesis_block(epoch: u32, difficulty: u64, previous: &HeaderHash) -> RawBlock {
+    genesis_block_with_leaders(epoch, difficulty, previous, &[])
+}
+
+/// same as `genesis_block`, with the given slot leaders
+pub fn genesis_block_with_leaders(epoch: u32, difficulty: u64, previous: &HeaderHash, slot_leaders: &[StakeholderId]) -> RawBlock {
+    let mut se = Serializer::new_vec()
+        .write_array(Len::Len(2)).unwrap()
+        .write_unsigned_integer(0).unwrap()
+        .write_array(Len::Len(3)).unwrap()
+        // header
+        .write_array(Len::Len(5)).unwrap()
+        .write_unsigned_integer(764824073).unwrap()
+        .write_bytes(previous.as_ref()).unwrap()
+        .write_bytes(&[0u8;32][..]).unwrap()
+        .write_array(Len::Len(2)).unwrap()
+        .write_unsigned_integer(epoch as u64).unwrap()
+        .write_array(Len::Len(1)).unwrap()
+        .write_unsigned_integer(difficulty).unwrap()
+        .write_map(Len::Len(0)).unwrap()
+        // body
+        .write_array(Len::Indefinite).unwrap();
+    for leader in slot_leaders {
+        se = se.serialize(leader).unwrap();
+    }
+    let se = se.write_special(Special::Break).unwrap()
+        // extra
+        .write_array(Len::Len(1)).unwrap()
+        .write_map(Len::Len(0)).unwrap();
+    RawBlock::from_dat(se.finalize())
+}
+
+/// a main block of the given epoch and slot, child of `previous`, with
+/// valid body proofs
+pub fn main_block(epoch: u32, slotid: u32, previous: &HeaderHash) -> RawBlock {
+    let leader = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([0;hdwallet::SEED_SIZE])).public();
+    let block = normal_block(SlotId { epoch: EpochId::new(epoch), slotid: slotid }, previous, leader, signature());
+    RawBlock::from_dat(cbor!(&Block::MainBlock(block)).unwrap())
+}
+
+/// a main block without transactions with valid body proofs, of the given
+/// slot and leader, child of `previous`. Its header is not signed.
+pub fn normal_block(slot_id: SlotId, previous: &HeaderHash, leader_key: hdwallet::XPub, block_signature: normal::BlockSignature) -> normal::Block {
+    let hash = Blake2b256::new(&[]);
+    let body_proof = normal::BodyProof::new(TxProof::new(0, hash.clone(), hash.clone()), types::SscProof::Certificate(hash.clone()), hash.clone(), hash.clone());
+    let consensus = normal::Consensus {
+        chain_difficulty: ChainDifficulty::new(slot_id.slotid as u64),
+        slot_id: slot_id,
+        leader_key: leader_key,
+        block_signature: block_signature,
+    };
+    let extra_data = types::HeaderExtraData::new(Default::default(), Default::default(), decode(&[0xa0]), hash.clone());
+    let header = normal::BlockHeader::new(Default::default(), previous.clone(), body_proof, consensus, extra_data);
+    let ssc = normal::SscPayload::CertificatesPayload(decode(&[0xd9, 0x01, 0x02, 0x80]));
+    let body = normal::Body::new(normal::TxPayload::empty(), ssc, Value::Array(vec![]), Value::Array(vec![]));
+    let mut block = normal::Block::new(header, body, decode(&[0x81, 0xa0]));
+    block.header.extra_data.extra_data_proof = block.compute_extra_data_proof();
+    block
+}
+
+/// a placeholder signature, for the blocks whose signature is not checked
+pub fn signature() -> normal::BlockSignature {
+    normal::BlockSignature::Signature(hdwallet::Signature::from_bytes([0;hdwallet::SIGNATURE_SIZE]))
+}
+
+/// decode the given genesis block
+pub fn decode_genesis(raw: &RawBlock) -> genesis::Block {
+    match raw.decode() {
+        Ok(Block::GenesisBlock(block)) => block,
+        block => panic!("expected a genesis block, got {:?}", block),
+    }
+}
+
@@ -5,10 +5,11 @@
 //! `Tx` : Input + Output
 //! `TxInWitness`: Witness providing for TxIn (e.g. cryptographic signature)
 //! `TxAux` : Signed Tx (Tx + Witness)
+//! `UtxoSet` : the unspent outputs, updated block after block
 //!
-use std::{fmt};
+use std::{fmt, collections::BTreeMap};
 
-use hash::{Blake2b256};
+use hash::{self, Blake2b256};
 
 use cbor_event::{self, de::RawCbor, se::{Serializer}};
 use config::{ProtocolMagic};
@@ -17,6 +18,9 @@ use redeem;
 use hdwallet::{Signature, XPub, XPrv, XPUB_SIZE, SIGNATURE_SIZE};
 use address::{ExtendedAddr, SpendingData};
 use coin::{self, Coin};
+use block::{Block};
+use fee;
+use txutils::{Input, output_sum};
 
 // TODO: this seems to be the hash of the serialisation CBOR of a given Tx.
 // if this is confirmed, we need to make a proper type, wrapping it around
@@ -41,10 +45,7 @@ impl TxOut {
 }
 impl cbor_event::de::Deserialize for TxOut {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid TxOut: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "TxOut")?;
         let addr = cbor_event::de::Deserialize::deserialize(raw)?;
         let val  = cbor_event::de::Deserialize::deserialize(raw)?;
         Ok(TxOut::new(addr, val))
@@ -92,16 +93,27 @@ impl TxInWitness {
         TxInWitness::PkWitness(XPub::from_bytes([0u8;XPUB_SIZE]), fakesig)
     }
 
+    /// the data signed by the `PkWitness`es of the transaction `TxId`
+    ///
+    /// this is what an external signer (e.g. a hardware wallet) needs to
+    /// sign to produce the witness of an input.
+    pub fn signing_data(protocol_magic: ProtocolMagic, txid: &TxId) -> Vec<u8> {
+        signing_data(1, protocol_magic, txid)
+    }
+
     /// create a TxInWitness from a given private key `XPrv` for the given transaction id `TxId`.
     pub fn new(protocol_magic: ProtocolMagic, key: &XPrv, txid: &TxId) -> Self {
-        let vec = Serializer::new_vec()
-            .write_unsigned_integer(1).expect("write byte 0x01")
-            .serialize(&protocol_magic).expect("serialize protocol magic")
-            .serialize(&txid).expect("serialize Tx's Id")
-            .finalize();
+        let vec = TxInWitness::signing_data(protocol_magic, txid);
         TxInWitness::PkWitness(key.public(), key.sign(&vec))
     }
 
+    /// create a TxInWitness from a given redeem private key (i.e. the AVVM key
+    /// of a redeem address) for the given transaction id `TxId`.
+    pub fn new_redeem(protocol_magic: ProtocolMagic, key: &redeem::PrivateKey, txid: &TxId) -> Self {
+        let vec = signing_data(2, protocol_magic, txid);
+        TxInWitness::RedeemWitness(key.public(), key.sign(&vec))
+    }
+
     /// verify a given extended address is associated to the witness.
     ///
     pub fn verify_address(&self, address: &ExtendedAddr) -> bool {
@@ -125,11 +137,12 @@ impl TxInWitness {
     /// verify the signature against the given transation `Tx`
     ///
     pub fn verify_tx(&self, protocol_magic: ProtocolMagic, tx: &Tx) -> bool {
-        let vec = Serializer::new_vec()
-            .write_unsigned_integer(1).expect("write byte 0x01")
-            .serialize(&protocol_magic).expect("serialize protocol magic")
-            .serialize(&tx.id()).expect("serialize Tx's Id")
-            .finalize();
+        // the signing tag differs between the standard and the redeem witnesses
+        let sign_tag = match self {
+            &TxInWitness::RedeemWitness(_, _) => 2,
+            _                                 => 1,
+        };
+        let vec = signing_data(sign_tag, protocol_magic, &tx.id());
         match self {
             &TxInWitness::PkWitness(ref pk, ref sig)     => pk.verify(&vec, sig),
             &TxInWitness::ScriptWitness(_, _)            => unimplemented!(),
@@ -142,6 +155,15 @@ impl TxInWitness {
         self.verify_address(address) && self.verify_tx(protocol_magic, tx)
     }
 }
+
+fn signing_data(sign_tag: u64, protocol_magic: ProtocolMagic, txid: &TxId) -> Vec<u8> {
+    Serializer::new_vec()
+        .write_unsigned_integer(sign_tag).expect("write signing tag")
+        .serialize(&protocol_magic).expect("serialize protocol magic")
+        .serialize(txid).expect("serialize Tx's Id")
+        .finalize()
+}
+
 impl cbor_event::se::Serialize for TxInWitness {
     fn serialize<W: ::std::io::Write>(&self, serializer: Serializer<W>) -> cbor_event::Result<Serializer<W>> {
         let mut serializer = serializer.write_array(cbor_event::Len::Len(2))?;
@@ -166,10 +188,7 @@ impl cbor_event::se::Serialize for TxInWitness {
 }
 impl cbor_event::de::Deserialize for TxInWitness {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid TxInWitness: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "TxInWitness")?;
         let sum_type_idx = raw.unsigned_integer()?;
         match sum_type_idx {
             0 => {
@@ -179,10 +198,7 @@ impl cbor_event::de::Deserialize for TxInWitness {
                 }
                 let bytes = raw.bytes()?;
                 let mut raw = RawCbor::from(&bytes);
-                let len = raw.array()?;
-                if len != cbor_event::Len::Len(2) {
-                    return Err(cbor_event::Error::CustomError(format!("Invalid TxInWitness::PkWitness: recieved array of {:?} elements", len)));
-                }
+                raw.tuple(2, "TxInWitness::PkWitness")?;
                 let pk  = cbor_event::de::Deserialize::deserialize(&mut raw)?;
                 let sig = cbor_event::de::Deserialize::deserialize(&mut raw)?;
                 Ok(TxInWitness::PkWitness(pk, sig))
@@ -194,10 +210,7 @@ impl cbor_event::de::Deserialize for TxInWitness {
                 }
                 let bytes = raw.bytes()?;
                 let mut raw = RawCbor::from(&bytes);
-                let len = raw.array()?;
-                if len != cbor_event::Len::Len(2) {
-                    return Err(cbor_event::Error::CustomError(format!("Invalid TxInWitness::PkRedeemWitness: recieved array of {:?} elements", len)));
-                }
+                raw.tuple(2, "TxInWitness::PkRedeemWitness")?;
                 let pk  = cbor_event::de::Deserialize::deserialize(&mut raw)?;
                 let sig = cbor_event::de::Deserialize::deserialize(&mut raw)?;
                 Ok(TxInWitness::RedeemWitness(pk, sig))
@@ -212,6 +225,16 @@ impl cbor_event::de::Deserialize for TxInWitness {
 /// Structure used for addressing a specific output of a transaction
 /// built from a TxId (hash of the tx) and the offset in the outputs of this
 /// transaction.
+///
+/// It is displayed and parsed in the `<txid>#<index>` notation:
+///
+/// ```
+/// # use cardano::tx::{TxIn, TxId};
+/// let txin = TxIn::new(TxId::new(&[]), 1);
+/// let s = "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8#1";
+/// assert_eq!(txin.to_string(), s);
+/// assert_eq!(s.parse::<TxIn>().unwrap(), txin);
+/// ```
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct TxIn {
     pub id: TxId,
@@ -219,12 +242,39 @@ pub struct TxIn {
 }
 impl fmt::Display for TxIn {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}@{}", self.id, self.index)
+        write!(f, "{}#{}", self.id, self.index)
     }
 }
 impl TxIn {
     pub fn new(id: TxId, index: u32) -> Self { TxIn { id: id, index: index } }
 }
+
+/// error parsing a `TxIn` from the `<txid>#<index>` notation
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TxInParseError {
+    /// there is no `#` between the transaction id and the index
+    MissingIndex,
+    InvalidTxId(hash::Error),
+    InvalidIndex(::std::num::ParseIntError),
+}
+impl fmt::Display for TxInParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TxInParseError::MissingIndex => write!(f, "expected <txid>#<index>"),
+            TxInParseError::InvalidTxId(err) => write!(f, "invalid transaction id: {}", err),
+            TxInParseError::InvalidIndex(err) => write!(f, "invalid output index: {}", err),
+        }
+    }
+}
+impl ::std::str::FromStr for TxIn {
+    type Err = TxInParseError;
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        let sep = s.find('#').ok_or(TxInParseError::MissingIndex)?;
+        let id = TxId::from_hex(&&s[..sep]).map_err(TxInParseError::InvalidTxId)?;
+        let index = s[sep + 1..].parse().map_err(TxInParseError::InvalidIndex)?;
+        Ok(TxIn::new(id, index))
+    }
+}
 impl cbor_event::se::Serialize for TxIn {
     fn serialize<W: ::std::io::Write>(&self, serializer: Serializer<W>) -> cbor_event::Result<Serializer<W>> {
         serializer.write_array(cbor_event::Len::Len(2))?
@@ -235,10 +285,7 @@ impl cbor_event::se::Serialize for TxIn {
 }
 impl cbor_event::de::Deserialize for TxIn {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid TxInWitness: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "TxIn")?;
         let sum_type_idx = raw.unsigned_integer()?;
         if sum_type_idx != 0 {
             return Err(cbor_event::Error::CustomError(format!("Unsupported TxIn: {}", sum_type_idx)));
@@ -249,10 +296,7 @@ impl cbor_event::de::Deserialize for TxIn {
         }
         let bytes = raw.bytes()?;
         let mut raw = RawCbor::from(&bytes);
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid TxInWitness::PkRedeemWitness: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "TxIn::Utxo")?;
         let id  = cbor_event::de::Deserialize::deserialize(&mut raw)?;
         let idx = raw.unsigned_integer()?;
         Ok(TxIn::new(id, idx as u32))
@@ -301,6 +345,16 @@ impl Tx {
         }
         Ok(total)
     }
+
+    /// the data to sign for each input, in the inputs order, see
+    /// `TxInWitness::signing_data`.
+    ///
+    /// Every input signs the same transaction id, the payloads are
+    /// given per input so they can be dispatched to different signers.
+    pub fn signing_payloads(&self, protocol_magic: ProtocolMagic) -> Vec<Vec<u8>> {
+        let data = TxInWitness::signing_data(protocol_magic, &self.id());
+        self.inputs.iter().map(|_| data.clone()).collect()
+    }
 }
 impl cbor_event::se::Serialize for Tx {
     fn serialize<W: ::std::io::Write>(&self, serializer: Serializer<W>) -> cbor_event::Result<Serializer<W>> {
@@ -312,10 +366,7 @@ impl cbor_event::se::Serialize for Tx {
 }
 impl cbor_event::de::Deserialize for Tx {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Tx: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "Tx")?;
 
         let num_inputs = raw.array()?;
         assert_eq!(num_inputs, cbor_event::Len::Indefinite);
@@ -354,29 +405,67 @@ impl cbor_event::de::Deserialize for Tx {
     }
 }
 
+pub use config::DEFAULT_MAX_TX_BYTES;
+
 /// Tx with the vector of witnesses
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct TxAux {
     pub tx: Tx,
     pub witnesses: Vec<TxInWitness>,
 }
+/// a single line summary: the transaction id, the number of inputs and
+/// outputs, the total value of the outputs and the number of witnesses.
 impl fmt::Display for TxAux {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "Tx:\n{}", self.tx)?;
-        writeln!(f, "witnesses: {:?}\n", self.witnesses)
+        write!(f, "{}: {} inputs, {} outputs", self.tx.id(), self.tx.inputs.len(), self.tx.outputs.len())?;
+        match self.tx.get_output_total() {
+            Ok(total) => write!(f, " of {}", total)?,
+            Err(_) => write!(f, " of more than {}", Coin::new(coin::MAX_COIN).unwrap())?,
+        }
+        write!(f, ", {} witnesses", self.witnesses.len())
     }
 }
 impl TxAux {
     pub fn new(tx: Tx, witnesses: Vec<TxInWitness>) -> Self {
         TxAux { tx: tx, witnesses: witnesses }
     }
+
+    /// assemble the transaction with witnesses produced elsewhere (see
+    /// `Tx::signing_payloads`), checking every witness against the
+    /// address of the output spent by its input and the transaction.
+    ///
+    /// `spent` are the outputs spent by the inputs of the transaction, in
+    /// the same order.
+    pub fn new_checked(protocol_magic: ProtocolMagic, tx: Tx, spent: &[TxOut], witnesses: Vec<TxInWitness>) -> fee::Result<Self> {
+        if spent.len() != tx.inputs.len() {
+            return Err(fee::Error::SpentCountMismatch(tx.inputs.len(), spent.len()));
+        }
+        if witnesses.len() != tx.inputs.len() {
+            return Err(fee::Error::WitnessCountMismatch(tx.inputs.len(), witnesses.len()));
+        }
+        for ((txin, txout), witness) in tx.inputs.iter().zip(spent.iter()).zip(witnesses.iter()) {
+            if ! witness.verify_address(&txout.address) {
+                return Err(fee::Error::AddressMismatch(txin.clone()));
+            }
+            if ! witness.verify_tx(protocol_magic, &tx) {
+                return Err(fee::Error::InvalidWitness(txin.clone()));
+            }
+        }
+        Ok(TxAux::new(tx, witnesses))
+    }
+
+    /// the bytes to send to a node to submit the transaction
+    ///
+    /// this is the CBOR encoding of the transaction and its witnesses,
+    /// as it will appear in the block's payload.
+    pub fn to_submit_bytes(&self) -> Vec<u8> {
+        // can only fail if there is no more memory
+        cbor!(self).expect("encode TxAux")
+    }
 }
 impl cbor_event::de::Deserialize for TxAux {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid TxAux: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "TxAux")?;
 
         let tx = cbor_event::de::Deserialize::deserialize(raw)?;
         let mut witnesses = Vec::new();
@@ -409,6 +498,141 @@ pub fn txaux_serialize<W>(tx: &Tx, witnesses: &Vec<TxInWitness>, serializer: Ser
     cbor_event::se::serialize_fixed_array(witnesses.iter(), serializer)
 }
 
+/// the set of the unspent transaction outputs (UTxO), indexed by the
+/// `TxIn` pointing to them.
+///
+/// The blocks need to be applied in the blockchain order.
+#[derive(Debug, Clone, Default)]
+pub struct UtxoSet(BTreeMap<TxIn, TxOut>);
+impl UtxoSet {
+    pub fn new() -> Self { UtxoSet(BTreeMap::new()) }
+
+    /// remove the outputs spent by the transaction and add its new outputs
+    pub fn apply_tx(&mut self, tx: &Tx) {
+        self.apply_tx_filtered(tx, |_, _| true)
+    }
+
+    /// same as `apply_tx` but only the new outputs for which `keep`
+    /// returns true are added, e.g. the ones paying to a wallet.
+    pub fn apply_tx_filtered<F>(&mut self, tx: &Tx, mut keep: F)
+        where F: FnMut(&TxIn, &TxOut) -> bool
+    {
+        for txin in tx.inputs.iter() {
+            self.0.remove(txin);
+        }
+        let id = tx.id();
+        for (index, txout) in tx.outputs.iter().enumerate() {
+            let txin = TxIn::new(id.clone(), index as u32);
+            if keep(&txin, txout) {
+                self.0.insert(txin, txout.clone());
+            }
+        }
+    }
+
+    /// apply all the transactions of the given block
+    ///
+    /// genesis blocks do not contain any transaction and leave the
+    /// set unchanged.
+    pub fn apply_block(&mut self, block: &Block) {
+        match block {
+            Block::GenesisBlock(_) => {},
+            Block::MainBlock(blk) => {
+                for txaux in blk.body.tx.iter() {
+                    self.apply_tx(&txaux.tx);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, txin: &TxIn) -> Option<&TxOut> { self.0.get(txin) }
+
+    pub fn len(&self) -> usize { self.0.len() }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    pub fn iter(&self) -> ::std::collections::btree_map::Iter<TxIn, TxOut> { self.0.iter() }
+
+    /// sum the value of the unspent outputs matching the given predicate
+    ///
+    /// ```
+    /// # use cardano::tx::UtxoSet;
+    /// # use cardano::coin::Coin;
+    /// let utxos = UtxoSet::new();
+    /// assert_eq!(utxos.balance_for(|_| true).unwrap(), Coin::zero());
+    /// ```
+    pub fn balance_for<F>(&self, predicate: F) -> coin::Result<Coin>
+        where F: Fn(&TxOut) -> bool
+    {
+        let mut total = Coin::zero();
+        for txout in self.0.values().filter(|txout| predicate(txout)) {
+            total = (total + txout.value)?;
+        }
+        Ok(total)
+    }
+
+    /// sum the value of all the unspent outputs, failing if it goes
+    /// over `coin::MAX_COIN`
+    pub fn total_value(&self) -> coin::Result<Coin> { self.balance_for(|_| true) }
+}
+
+/// unspent outputs selected from a `UtxoSet` to spend a given value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inputs(Vec<(TxIn, TxOut)>);
+impl Inputs {
+    /// select enough unspent outputs to pay `target` and the fee of a
+    /// transaction spending them, with an output for the target and one
+    /// for the change.
+    ///
+    /// the fee is estimated with the default `LinearFee`; the selection
+    /// fails with `fee::Error::NotEnoughInput` if the outputs of the set
+    /// cannot cover it.
+    pub fn select_from(utxo: &UtxoSet, target: Coin, policy: fee::SelectionPolicy) -> fee::Result<Self> {
+        let alg = fee::LinearFee::default();
+        let mut selected = Vec::new();
+        let mut total = Coin::zero();
+
+        let inputs : Vec<Input<()>> = utxo.iter().map(|(txin, txout)| Input::new(txin.clone(), txout.clone(), ())).collect();
+        for input in fee::SelectionStrategy::select(&policy, inputs.iter().collect(), target) {
+            let (txin, txout) = (&input.ptr, &input.value);
+            selected.push((txin.clone(), txout.clone()));
+            total = (total + txout.value)?;
+
+            // the outputs' addresses are not known yet, use the spent
+            // output's address to estimate their size.
+            let mut tx = Tx::new_with(selected.iter().map(|(txin, _)| txin.clone()).collect(), vec![]);
+            tx.add_output(TxOut::new(txout.address.clone(), target));
+            tx.add_output(TxOut::new(txout.address.clone(), total));
+            let fee = alg.estimate_for_tx(&tx)?;
+
+            if total >= (target + fee.to_coin())? {
+                return Ok(Inputs(selected));
+            }
+        }
+        Err(fee::Error::NotEnoughInput)
+    }
+
+    pub fn len(&self) -> usize { self.0.len() }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    pub fn iter(&self) -> ::std::slice::Iter<(TxIn, TxOut)> { self.0.iter() }
+
+    /// the total value of the selected outputs
+    pub fn total(&self) -> coin::Result<Coin> {
+        output_sum(self.0.iter().map(|(_, txout)| txout))
+    }
+
+    /// the selected outputs as the `Input`s of a wallet, `addressing`
+    /// giving the wallet's addressing of the outputs' addresses.
+    pub fn to_inputs<Addressing, F>(&self, mut addressing: F) -> Vec<Input<Addressing>>
+        where F: FnMut(&ExtendedAddr) -> Addressing
+    {
+        self.0.iter().map(|(txin, txout)| {
+            Input::new(txin.clone(), txout.clone(), addressing(&txout.address))
+        }).collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TxProof {
     pub number: u32,
@@ -439,10 +663,7 @@ impl cbor_event::se::Serialize for TxProof {
 }
 impl cbor_event::de::Deserialize for TxProof {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(3) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid TxProof: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(3, "TxProof")?;
         let number = raw.unsigned_integer()?;
         let root   = cbor_event::de::Deserialize::deserialize(raw)?;
         let witnesses = cbor_event::de::Deserialize::deserialize(raw)?;
@@ -485,6 +706,20 @@ mod tests {
         assert_eq!(txout.address.attributes.derivation_path, Some(hdap));
     }
 
+    #[test]
+    fn txout_decode_unparsed_values() {
+        // TX_OUT with two more values appended to its array
+        let mut bytes = TX_OUT.to_vec();
+        bytes[0] = 0x84;
+        bytes.extend_from_slice(&[0x01, 0x02]);
+
+        let mut raw = RawCbor::from(&bytes);
+        match <TxOut as cbor_event::de::Deserialize>::deserialize(&mut raw) {
+            Err(cbor_event::Error::UnparsedValues("TxOut", 2)) => {},
+            r => panic!("expected 2 unparsed values, got {:?}", r),
+        }
+    }
+
     #[test]
     fn txout_encode_decode() {
         let seed = hdwallet::Seed::from_bytes(SEED);
@@ -510,6 +745,30 @@ mod tests {
         assert!(txin.index == 666);
     }
 
+    #[test]
+    fn txin_parse_display() {
+        const ID : &'static str = "aad78a13b50a014a24633c7d44fd8f8d18f67bbb3fa9cbcedf834ac899759dcd";
+        let txin : TxIn = format!("{}#666", ID).parse().unwrap();
+        assert_eq!(txin, TxIn::new(TxId::from_hex(&ID).unwrap(), 666));
+        assert_eq!(txin.to_string(), format!("{}#666", ID));
+        assert_eq!(txin.to_string().parse::<TxIn>().unwrap(), txin);
+
+        assert_eq!(ID.parse::<TxIn>(), Err(TxInParseError::MissingIndex));
+        match format!("{}#one", ID).parse::<TxIn>() {
+            Err(TxInParseError::InvalidIndex(_)) => {},
+            r => panic!("expected an invalid index, got {:?}", r),
+        }
+        match format!("{}#-1", ID).parse::<TxIn>() {
+            Err(TxInParseError::InvalidIndex(_)) => {},
+            r => panic!("expected an invalid index, got {:?}", r),
+        }
+        assert_eq!(format!("{}#0", &ID[..56]).parse::<TxIn>(), Err(TxInParseError::InvalidTxId(hash::Error::InvalidHashSize(32, 28))));
+        match "zz#0".parse::<TxIn>() {
+            Err(TxInParseError::InvalidTxId(hash::Error::HexadecimalError(_))) => {},
+            r => panic!("expected an invalid transaction id, got {:?}", r),
+        }
+    }
+
     #[test]
     fn txin_encode_decode() {
         let txid = TxId::new(&[0;32]);
@@ -549,6 +808,7 @@ mod tests {
         tx.add_output(txout);
 
         assert!(cbor_event::test_encode_decode(&tx).expect("encode/decode Tx"));
+        cbor_event::assert_canonical(&cbor!(&tx).unwrap()).expect("canonical Tx encoding");
     }
 
     #[test]
@@ -574,6 +834,7 @@ mod tests {
         let txinwitness = TxInWitness::new(protocol_magic, &sk, &tx.id());
 
         assert!(cbor_event::test_encode_decode(&txinwitness).expect("encode/decode TxInWitness"));
+        cbor_event::assert_canonical(&cbor!(&txinwitness).unwrap()).expect("canonical TxInWitness encoding");
     }
 
     #[test]
@@ -613,6 +874,94 @@ mod tests {
         assert!(txinwitness.verify(protocol_magic, &ea, &tx));
     }
 
+    #[test]
+    fn txaux_with_external_witnesses() {
+        let protocol_magic = ProtocolMagic::default();
+        let keys : Vec<_> = (1..3).map(|i| hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([i;32]))).collect();
+        let spent : Vec<_> = keys.iter().map(|key| {
+            TxOut::new(ExtendedAddr::new_simple(key.public()), Coin::new(1_000_000).unwrap())
+        }).collect();
+
+        let mut tx = Tx::new();
+        tx.add_input(TxIn::new(TxId::new(&[0;32]), 0));
+        tx.add_input(TxIn::new(TxId::new(&[0;32]), 1));
+        tx.add_output(TxOut::new(spent[0].address.clone(), Coin::new(1_500_000).unwrap()));
+
+        // the keys are held by an external signer, only given the payloads
+        let payloads = tx.signing_payloads(protocol_magic);
+        assert_eq!(payloads.len(), 2);
+        let witnesses : Vec<_> = keys.iter().zip(payloads.iter()).map(|(key, payload)| {
+            TxInWitness::PkWitness(key.public(), key.sign(payload))
+        }).collect();
+        assert_eq!(witnesses[0], TxInWitness::new(protocol_magic, &keys[0], &tx.id()));
+
+        let txaux = TxAux::new_checked(protocol_magic, tx.clone(), &spent, witnesses.clone()).unwrap();
+        for (txout, witness) in spent.iter().zip(txaux.witnesses.iter()) {
+            assert!(witness.verify(protocol_magic, &txout.address, &txaux.tx));
+        }
+
+        let mut swapped = witnesses.clone();
+        swapped.swap(0, 1);
+        match TxAux::new_checked(protocol_magic, tx.clone(), &spent, swapped) {
+            Err(fee::Error::AddressMismatch(txin)) => assert_eq!(txin, tx.inputs[0]),
+            r => panic!("expected an address mismatch, got {:?}", r),
+        }
+
+        let mut other_tx = tx.clone();
+        other_tx.add_output(TxOut::new(spent[1].address.clone(), Coin::new(1).unwrap()));
+        match TxAux::new_checked(protocol_magic, other_tx, &spent, witnesses.clone()) {
+            Err(fee::Error::InvalidWitness(txin)) => assert_eq!(txin, tx.inputs[0]),
+            r => panic!("expected an invalid witness, got {:?}", r),
+        }
+
+        match TxAux::new_checked(protocol_magic, tx.clone(), &spent, witnesses[..1].to_vec()) {
+            Err(fee::Error::WitnessCountMismatch(2, 1)) => {},
+            r => panic!("expected a missing witness, got {:?}", r),
+        }
+
+        match TxAux::new_checked(protocol_magic, tx, &spent[..1], witnesses) {
+            Err(fee::Error::SpentCountMismatch(2, 1)) => {},
+            r => panic!("expected a missing spent output, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn txinwitness_redeem_sign_verify() {
+        let protocol_magic = ProtocolMagic::default();
+        let sk = redeem::PrivateKey::generate(&[0;32]);
+        let pk = sk.public();
+
+        let ea = address::ExtendedAddr::new(
+            address::AddrType::ATRedeem,
+            address::SpendingData::RedeemASD(pk.clone()),
+            address::Attributes::new_bootstrap_era(None)
+        );
+
+        let mut tx = Tx::new();
+        tx.add_input(TxIn::new(TxId::new(&[0;32]), 0));
+        tx.add_output(TxOut::new(ea.clone(), Coin::new(42).unwrap()));
+
+        let txinwitness = TxInWitness::new_redeem(protocol_magic, &sk, &tx.id());
+        assert!(txinwitness.verify_address(&ea));
+        assert!(txinwitness.verify_tx(protocol_magic, &tx));
+
+        // the redeem witness is the sum type 2, followed by the tagged
+        // (24) cbor encoded [public key, signature]
+        let bytes = cbor!(&txinwitness).unwrap();
+        assert_eq!(&bytes[..4], &[0x82, 0x02, 0xd8, 0x18][..]);
+        cbor_event::assert_canonical(&bytes).expect("canonical redeem TxInWitness encoding");
+        assert!(cbor_event::test_encode_decode(&txinwitness).expect("encode/decode redeem TxInWitness"));
+
+        let txaux = TxAux::new(tx, vec![txinwitness]);
+        assert!(cbor_event::test_encode_decode(&txaux).expect("encode/decode redeem TxAux"));
+    }
+
+    #[test]
+    fn txaux_display() {
+        let txaux : TxAux = RawCbor::from(TX_AUX).deserialize().expect("to decode a `TxAux`");
+        assert_eq!(format!("{}", txaux), "63d6c46119c0b695360315093c90dd2ec609575113b7ed1c1928a967698ea9c5: 1 inputs, 1 outputs of 0.000042, 1 witnesses");
+    }
+
     #[test]
     fn txaux_decode() {
         let _txaux : TxAux = RawCbor::from(TX_AUX).deserialize().expect("to decode a TxAux");
@@ -630,6 +979,147 @@ mod tests {
         let txaux = TxAux::new(tx, witnesses);
 
         assert!(cbor_event::test_encode_decode(&txaux).expect("encode/decode TxAux"));
+        let bytes = cbor!(&txaux).unwrap();
+        cbor_event::assert_canonical(&bytes).expect("canonical TxAux encoding");
+        assert_eq!(bytes.as_slice(), TX_AUX);
+    }
+
+    #[test]
+    fn txaux_to_submit_bytes() {
+        let txaux : TxAux = RawCbor::from(TX_AUX).deserialize().expect("to decode a `TxAux`");
+        let bytes = txaux.to_submit_bytes();
+        assert_eq!(bytes.as_slice(), TX_AUX);
+        let decoded : TxAux = RawCbor::from(&bytes).deserialize().expect("to decode the submitted `TxAux`");
+        assert_eq!(decoded, txaux);
+    }
+
+    fn genesis_block() -> Block {
+        ::testing::genesis_block(0, 0, &::block::HeaderHash::new(&[])).decode().unwrap()
+    }
+
+    fn main_block(txs: Vec<Tx>) -> Block {
+        use block::{normal, types::{SlotId, EpochId}, HeaderHash};
+
+        let leader = hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes(SEED)).public();
+        let slot_id = SlotId { epoch: EpochId::new(0), slotid: 1 };
+        let mut block = ::testing::normal_block(slot_id, &HeaderHash::new(&[]), leader, ::testing::signature());
+        block.header.body_proof.tx.number = txs.len() as u32;
+        block.body.tx = normal::TxPayload::new(txs.into_iter().map(|tx| TxAux::new(tx, vec![])).collect());
+        Block::MainBlock(block)
+    }
+
+    #[test]
+    fn utxo_set_apply_blocks() {
+        let alice = ExtendedAddr::new_simple(hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([1;32])).public());
+        let bob   = ExtendedAddr::new_simple(hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([2;32])).public());
+
+        let mut tx1 = Tx::new();
+        tx1.add_input(TxIn::new(TxId::new(&[0]), 0));
+        tx1.add_output(TxOut::new(alice.clone(), Coin::new(100).unwrap()));
+        tx1.add_output(TxOut::new(bob.clone(), Coin::new(50).unwrap()));
+
+        let mut tx2 = Tx::new();
+        tx2.add_input(TxIn::new(tx1.id(), 0));
+        tx2.add_output(TxOut::new(bob.clone(), Coin::new(70).unwrap()));
+        tx2.add_output(TxOut::new(alice.clone(), Coin::new(25).unwrap()));
+
+        let mut utxos = UtxoSet::new();
+        utxos.apply_block(&genesis_block());
+        assert!(utxos.is_empty());
+
+        utxos.apply_block(&main_block(vec![tx1.clone()]));
+        assert_eq!(utxos.len(), 2);
+
+        utxos.apply_block(&main_block(vec![tx2.clone()]));
+        assert_eq!(utxos.len(), 3);
+        assert_eq!(utxos.get(&TxIn::new(tx1.id(), 0)), None);
+        assert_eq!(utxos.get(&TxIn::new(tx1.id(), 1)), Some(&tx1.outputs[1]));
+        assert_eq!(utxos.get(&TxIn::new(tx2.id(), 0)), Some(&tx2.outputs[0]));
+        assert_eq!(utxos.get(&TxIn::new(tx2.id(), 1)), Some(&tx2.outputs[1]));
+
+        assert_eq!(utxos.balance_for(|txout| txout.address == alice).unwrap(), Coin::new(25).unwrap());
+        assert_eq!(utxos.balance_for(|txout| txout.address == bob).unwrap(), Coin::new(120).unwrap());
+        assert_eq!(utxos.balance_for(|_| true).unwrap(), Coin::new(145).unwrap());
+    }
+
+    fn utxo_set(values: &[u64]) -> (ExtendedAddr, UtxoSet) {
+        let alice = ExtendedAddr::new_simple(hdwallet::XPrv::generate_from_seed(&hdwallet::Seed::from_bytes([1;32])).public());
+        let mut tx = Tx::new();
+        tx.add_input(TxIn::new(TxId::new(&[0]), 0));
+        for value in values {
+            tx.add_output(TxOut::new(alice.clone(), Coin::new(*value).unwrap()));
+        }
+        let mut utxos = UtxoSet::new();
+        utxos.apply_tx(&tx);
+        (alice, utxos)
+    }
+
+    #[test]
+    fn utxo_set_total_value() {
+        let (_, utxos) = utxo_set(&[]);
+        assert_eq!(utxos.total_value().unwrap(), Coin::zero());
+
+        let (_, utxos) = utxo_set(&[400_000, 700_000, 5_000_000]);
+        assert_eq!(utxos.total_value().unwrap(), Coin::new(6_100_000).unwrap());
+    }
+
+    #[test]
+    fn utxo_set_total_value_overflow() {
+        let (_, utxos) = utxo_set(&[coin::MAX_COIN, 0]);
+        assert_eq!(utxos.total_value().unwrap(), Coin::new(coin::MAX_COIN).unwrap());
+
+        let (_, utxos) = utxo_set(&[coin::MAX_COIN, 1]);
+        assert_eq!(utxos.total_value(), Err(coin::Error::OutOfBound(coin::MAX_COIN + 1)));
+    }
+
+    #[test]
+    fn inputs_select_exact() {
+        let target = Coin::new(1_000_000).unwrap();
+        let (alice, _) = utxo_set(&[]);
+
+        // the fee of the transaction spending one input
+        let mut tx = Tx::new_with(vec![TxIn::new(TxId::new(&[1]), 0)], vec![]);
+        tx.add_output(TxOut::new(alice.clone(), target));
+        tx.add_output(TxOut::new(alice.clone(), target));
+        let fee = fee::LinearFee::default().estimate_for_tx(&tx).unwrap();
+        let exact = (target + fee.to_coin()).unwrap();
+
+        let (_, utxos) = utxo_set(&[u64::from(exact), 1_000_000]);
+        let inputs = Inputs::select_from(&utxos, target, fee::SelectionPolicy::FirstMatchFirst).unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs.total().unwrap(), exact);
+
+        // one lovelace short, the second output is needed
+        let (_, utxos) = utxo_set(&[u64::from(exact) - 1, 1_000_000]);
+        let inputs = Inputs::select_from(&utxos, target, fee::SelectionPolicy::FirstMatchFirst).unwrap();
+        assert_eq!(inputs.len(), 2);
+    }
+
+    #[test]
+    fn inputs_select_surplus() {
+        let (alice, utxos) = utxo_set(&[400_000, 700_000, 5_000_000, 10_000_000]);
+        let target = Coin::new(1_000_000).unwrap();
+        let inputs = Inputs::select_from(&utxos, target, fee::SelectionPolicy::FirstMatchFirst).unwrap();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs.total().unwrap(), Coin::new(6_100_000).unwrap());
+
+        let wallet_inputs = inputs.to_inputs(|address| address == &alice);
+        assert_eq!(wallet_inputs.len(), 3);
+        assert!(wallet_inputs.iter().all(|input| input.addressing));
+        assert_eq!(wallet_inputs[2].value(), Coin::new(5_000_000).unwrap());
+    }
+
+    #[test]
+    fn inputs_select_insufficient() {
+        let (_, utxos) = utxo_set(&[400_000, 700_000]);
+        match Inputs::select_from(&utxos, Coin::new(1_000_000).unwrap(), fee::SelectionPolicy::FirstMatchFirst) {
+            Err(fee::Error::NotEnoughInput) => {},
+            r => panic!("expected NotEnoughInput, got {:?}", r),
+        }
+        match Inputs::select_from(&UtxoSet::new(), Coin::new(1).unwrap(), fee::SelectionPolicy::FirstMatchFirst) {
+            Err(fee::Error::NotEnoughInput) => {},
+            r => panic!("expected NotEnoughInput, got {:?}", r),
+        }
     }
 }
 
@@ -0,0 +1,146 @@
+//! track the funds of a wallet while going through the blockchain.
+//!
+//! The transactions are given in the blockchain order: every output
+//! paying to one of the wallet's addresses is added to the wallet's
+//! unspent outputs, and every input spending one of them removes it.
+//!
+
+use std::collections::BTreeMap;
+
+use address::{ExtendedAddr};
+use coin::{self, Coin};
+use tx::{Tx, TxIn, UtxoSet};
+use txutils::{Input};
+
+/// the unspent outputs of a wallet: a `UtxoSet` of the outputs paying to
+/// the wallet's addresses, along with the wallet's addressing of each.
+#[derive(Debug, Clone)]
+pub struct Balance<Addressing> {
+    utxos: UtxoSet,
+    addressings: BTreeMap<TxIn, Addressing>,
+}
+impl<Addressing: Clone> Balance<Addressing> {
+    pub fn new() -> Self {
+        Balance { utxos: UtxoSet::new(), addressings: BTreeMap::new() }
+    }
+
+    /// update the balance with the given transaction.
+    ///
+    /// `is_ours` returns the wallet's addressing of the given address
+    /// if it belongs to the wallet.
+    ///
+    /// The inputs are processed first, so a transaction cannot spend
+    /// its own outputs; the transactions must be given in order.
+    pub fn apply_tx<F>(&mut self, tx: &Tx, mut is_ours: F)
+        where F: FnMut(&ExtendedAddr) -> Option<Addressing>
+    {
+        for txin in tx.inputs.iter() {
+            self.addressings.remove(txin);
+        }
+
+        let addressings = &mut self.addressings;
+        self.utxos.apply_tx_filtered(tx, |txin, txout| {
+            match is_ours(&txout.address) {
+                None => false,
+                Some(addressing) => {
+                    addressings.insert(txin.clone(), addressing);
+                    true
+                }
+            }
+        });
+    }
+
+    /// the unspent outputs of the wallet
+    pub fn utxos<'a>(&'a self) -> impl Iterator<Item = Input<Addressing>> + 'a {
+        let addressings = &self.addressings;
+        self.utxos.iter().map(move |(txin, txout)| {
+            Input::new(txin.clone(), txout.clone(), addressings[txin].clone())
+        })
+    }
+
+    /// the total value of the unspent outputs of the wallet
+    pub fn total(&self) -> coin::Result<Coin> {
+        self.utxos.total_value()
+    }
+}
+impl<Addressing: Clone> Default for Balance<Addressing> {
+    fn default() -> Self { Balance::new() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bip::bip39::{MnemonicString, dictionary::ENGLISH};
+    use hdwallet::{XPrv, XPub};
+    use tx::{TxId, TxOut};
+    use wallet::{bip44::{self, AddrType}, scheme::{Wallet as _Wallet}};
+
+    const MNEMONICS : &'static str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn account() -> bip44::Account<XPrv> {
+        let mnemonics = MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap();
+        let mut wallet = bip44::Wallet::from_bip39_mnemonics(&mnemonics, b"password", Default::default());
+        wallet.create_account("account 1", 0)
+    }
+
+    fn address(account: &bip44::Account<XPub>, addr_type: AddrType, index: u32) -> ExtendedAddr {
+        account.gen_addresses(addr_type, index, 1).unwrap().pop().unwrap()
+    }
+
+    fn tx(inputs: Vec<TxIn>, outputs: Vec<(ExtendedAddr, u64)>) -> Tx {
+        let mut tx = Tx::new();
+        for txin in inputs { tx.add_input(txin); }
+        for (address, value) in outputs { tx.add_output(TxOut::new(address, Coin::new(value).unwrap())); }
+        tx
+    }
+
+    #[test]
+    fn receive_and_spend() {
+        let account = account().public();
+        let other = bip44::Wallet::from_root_key(XPrv::generate_from_seed(&::hdwallet::Seed::from_bytes([1;32])), Default::default())
+            .create_account("other", 0).public();
+        let ours = address(&account, AddrType::External, 0);
+        let change = address(&account, AddrType::Internal, 1);
+        let theirs = address(&other, AddrType::External, 0);
+
+        // block 1: someone pays 1000 to our address
+        let tx1 = tx(vec![TxIn::new(TxId::new(&[0;32]), 0)], vec![(theirs.clone(), 5000), (ours.clone(), 1000)]);
+        // block 2: unrelated transaction
+        let tx2 = tx(vec![TxIn::new(TxId::new(&[1;32]), 0)], vec![(theirs.clone(), 42)]);
+        // block 3: we spend our output, paying 600 to someone and
+        // getting back 390 in a change address (10 of fees)
+        let tx3 = tx(vec![TxIn::new(tx1.id(), 1)], vec![(theirs.clone(), 600), (change.clone(), 390)]);
+
+        let mut lookup = bip44::AddressLookup::new(account, 2);
+        let mut balance = Balance::new();
+
+        balance.apply_tx(&tx1, |addr| lookup.is_ours(addr));
+        assert_eq!(balance.total().unwrap(), Coin::new(1000).unwrap());
+
+        balance.apply_tx(&tx2, |addr| lookup.is_ours(addr));
+        assert_eq!(balance.total().unwrap(), Coin::new(1000).unwrap());
+
+        balance.apply_tx(&tx3, |addr| lookup.is_ours(addr));
+        assert_eq!(balance.total().unwrap(), Coin::new(390).unwrap());
+
+        let utxos : Vec<_> = balance.utxos().collect();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].ptr, TxIn::new(tx3.id(), 1));
+        assert_eq!(utxos[0].addressing, (AddrType::Internal, 1));
+    }
+
+    #[test]
+    fn spending_unknown_inputs_does_not_change_the_balance() {
+        let account = account().public();
+        let ours = address(&account, AddrType::External, 0);
+
+        let tx1 = tx(vec![TxIn::new(TxId::new(&[0;32]), 0)], vec![(ours.clone(), 1000)]);
+        let tx2 = tx(vec![TxIn::new(tx1.id(), 1)], vec![]);
+
+        let mut lookup = bip44::AddressLookup::new(account, bip44::DEFAULT_GAP_LIMIT);
+        let mut balance = Balance::new();
+        balance.apply_tx(&tx1, |addr| lookup.is_ours(addr));
+        balance.apply_tx(&tx2, |addr| lookup.is_ours(addr));
+        assert_eq!(balance.total().unwrap(), Coin::new(1000).unwrap());
+    }
+}
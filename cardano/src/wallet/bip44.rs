@@ -1,12 +1,14 @@
 /// BIP44 derivation scheme and address model
 ///
 
-use hdwallet::{Result, XPRV_SIZE, XPrv, XPub, DerivationScheme, DerivationIndex};
+use hdwallet::{Result, Error, XPRV_SIZE, XPrv, XPub, DerivationScheme, DerivationIndex};
 use bip::bip44::{BIP44_PURPOSE, BIP44_COIN_TYPE, BIP44_SOFT_UPPER_BOUND};
 use bip::bip39;
-use tx::{TxId, TxInWitness};
+use tx::{self, TxId, TxInWitness};
 use address::{ExtendedAddr};
 use config::{ProtocolMagic};
+use hash::{Blake2b224};
+use util::hex;
 use std::{ops::Deref, collections::{BTreeMap}};
 
 use super::scheme::{self};
@@ -83,6 +85,19 @@ impl Wallet {
         Wallet::from_bip39_seed(&seed, derivation_scheme)
     }
 
+    /// create a wallet from an English BIP39 mnemonic phrase, checking
+    /// the words and the checksum of the phrase first.
+    ///
+    /// The wallet is the one `from_bip39_mnemonics` creates, a mistyped
+    /// phrase fails with `bip39::Error::InvalidChecksum` instead of
+    /// creating another wallet.
+    pub fn from_mnemonic_phrase(phrase: &str, password: &str, derivation_scheme: DerivationScheme) -> bip39::Result<Self> {
+        let mnemonics = bip39::Mnemonics::from_string(&bip39::dictionary::ENGLISH, phrase)?;
+        bip39::Entropy::from_mnemonics(&mnemonics)?;
+        let mnemonics = bip39::MnemonicString::new(&bip39::dictionary::ENGLISH, phrase.to_owned())?;
+        Ok(Wallet::from_bip39_mnemonics(&mnemonics, password.as_bytes(), derivation_scheme))
+    }
+
     /// Create a new wallet from a root entropy
     ///
     /// This is the recommended method to create a wallet from initial generated value.
@@ -97,6 +112,88 @@ impl Wallet {
     }
 
     pub fn derivation_scheme(&self) -> DerivationScheme { self.derivation_scheme }
+
+    /// short hexadecimal fingerprint of the wallet: the beginning of the
+    /// hash of the cached root public key.
+    ///
+    /// It does not reveal the keys and is the same for the wallets
+    /// restored from the same seed, so it can be displayed to check the
+    /// right wallet was restored.
+    pub fn root_fingerprint(&self) -> String {
+        let hash = Blake2b224::new(self.cached_root_key.public().as_ref());
+        hex::encode(&hash.as_ref()[..8])
+    }
+
+    /// the first `count` external and internal (change) addresses of the
+    /// given account.
+    pub fn gen_address_book(&self, account: u32, count: u32) -> Result<AddressBook> {
+        let account = Account::new(self.cached_root_key.account(self.derivation_scheme, account)?, self.derivation_scheme);
+        Ok(AddressBook {
+            external: account.gen_addresses(AddrType::External, 0, count),
+            internal: account.gen_addresses(AddrType::Internal, 0, count),
+        })
+    }
+
+    /// the index of the next unused internal (change) address of the
+    /// given account: one after the highest internal index owning one of
+    /// the unspent outputs, `0` if none does.
+    ///
+    /// The addresses are looked up within `gap_limit`, see `AddressLookup`
+    /// (`config::Config` has a default one).
+    pub fn next_change_index(&self, utxo: &tx::UtxoSet, account: u32, gap_limit: u32) -> Result<u32> {
+        let account = Account::new(self.cached_root_key.account(self.derivation_scheme, account)?, self.derivation_scheme);
+        let mut lookup = AddressLookup::new(account.public(), gap_limit);
+
+        // the outputs are not ordered by index, so an address may only be
+        // in the lookup window once a higher index has been found: repeat
+        // until no new index is found
+        let mut next = 0;
+        loop {
+            let mut found = false;
+            for (_, txout) in utxo.iter() {
+                if let Some((AddrType::Internal, index)) = lookup.is_ours(&txout.address) {
+                    if index >= next { next = index + 1; found = true; }
+                }
+            }
+            if ! found { return Ok(next); }
+        }
+    }
+
+    /// every address of the given account appearing in the given outputs,
+    /// with its addressing, sorted by change then index.
+    ///
+    /// To list the addresses used in the wallet's history, give the
+    /// outputs of the `UtxoSet` along with the spent ones.
+    ///
+    /// The addresses are looked up within `gap_limit`, as in
+    /// `next_change_index`.
+    pub fn used_addresses<'a, I>(&self, outputs: I, account: u32, gap_limit: u32) -> Result<Vec<(Addressing, ExtendedAddr)>>
+        where I: IntoIterator<Item = &'a tx::TxOut>
+    {
+        let outputs : Vec<_> = outputs.into_iter().collect();
+        let account_key = Account::new(self.cached_root_key.account(self.derivation_scheme, account)?, self.derivation_scheme);
+        let mut lookup = AddressLookup::new(account_key.public(), gap_limit);
+
+        // as in `next_change_index`, repeat until no new address is found
+        let mut used = BTreeMap::new();
+        loop {
+            let count = used.len();
+            for txout in outputs.iter() {
+                if let Some((addr_type, index)) = lookup.is_ours(&txout.address) {
+                    used.entry((addr_type == AddrType::Internal, index))
+                        .or_insert_with(|| txout.address.clone());
+                }
+            }
+            if used.len() == count { break; }
+        }
+
+        Ok(used.into_iter().map(|((internal, index), address)| {
+            let addr_type = if internal { AddrType::Internal } else { AddrType::External };
+            let addressing = Addressing::new(account, addr_type).and_then(|addressing| addressing.incr(index))
+                                .expect("cannot fail, the account and the index are soft");
+            (addressing, address)
+        }).collect())
+    }
 }
 impl Deref for Wallet {
     type Target = RootLevel<XPrv>;
@@ -108,7 +205,8 @@ impl scheme::Wallet for Wallet {
     type Addressing  = Addressing;
 
     fn create_account(&mut self, alias: &str, id: u32) -> Self::Account {
-        let account = self.cached_root_key.account(self.derivation_scheme, id);
+        let account = self.cached_root_key.account(self.derivation_scheme, id)
+                          .expect("the account number is in the hardened range");
         let account = Account { cached_root_key: account, derivation_scheme: self.derivation_scheme };
         self.accounts.insert(alias.to_owned(), account.clone());
         account
@@ -121,9 +219,10 @@ impl scheme::Wallet for Wallet {
 
         for addressing in addresses {
             let key = self.cached_root_key
-                          .account(self.derivation_scheme, addressing.account.get_scheme_value())
-                          .change(self.derivation_scheme, addressing.address_type())
-                          .index(self.derivation_scheme, addressing.index.get_scheme_value());
+                          .account(self.derivation_scheme, addressing.account.get_account_number())
+                          .and_then(|account| account.change(self.derivation_scheme, addressing.address_type())
+                                                     .index(self.derivation_scheme, addressing.index.get_scheme_value()))
+                          .expect("cannot fail, the addressing is soft");
 
             let tx_witness = TxInWitness::new(protocol_magic, &key, txid);
             witnesses.push(tx_witness);
@@ -189,6 +288,38 @@ impl Account<XPrv> {
             index: from
         }
     }
+
+    /// generate `count` addresses of the given `addr_type`, starting at
+    /// the index `from`.
+    ///
+    /// The change level key is derived (and made public) only once, then
+    /// every address is obtained with a single soft public derivation. The
+    /// cost is then O(n) derivations in a single pass, cheaper than
+    /// deriving the private key of every index.
+    ///
+    /// Like the [`AddressGenerator`](./struct.AddressGenerator.html), the
+    /// generation stops at the last soft derivation
+    /// ([`BIP44_SOFT_UPPER_BOUND`](../../bip/bip44/constant.BIP44_SOFT_UPPER_BOUND.html)).
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use cardano::wallet::{bip44::{self, AddrType}, scheme::{Wallet}};
+    /// # use cardano::bip::bip39::{MnemonicString, dictionary::ENGLISH};
+    ///
+    /// let mnemonics = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    /// let mnemonics = MnemonicString::new(&ENGLISH, mnemonics.to_owned()).unwrap();
+    ///
+    /// let mut wallet = bip44::Wallet::from_bip39_mnemonics(&mnemonics, b"password", Default::default());
+    /// let account = wallet.create_account("account 1", 0);
+    ///
+    /// let addresses = account.gen_addresses(AddrType::External, 0, 20);
+    /// assert_eq!(addresses.len(), 20);
+    /// ```
+    ///
+    pub fn gen_addresses(&self, addr_type: AddrType, from: u32, count: u32) -> Vec<ExtendedAddr> {
+        self.public().gen_addresses(addr_type, from, count).expect("cannot fail, only soft derivations")
+    }
 }
 impl Account<XPub> {
     /// create an [`AddressGenerator`](./struct.AddressGenerator.html) iterator.
@@ -229,6 +360,28 @@ impl Account<XPub> {
             index: from
         })
     }
+
+    /// generate `count` addresses of the given `addr_type`, starting at
+    /// the index `from`.
+    ///
+    /// The change level key is derived only once, every address then
+    /// costs one soft derivation: O(n) in a single pass.
+    ///
+    /// Like the [`AddressGenerator`](./struct.AddressGenerator.html), the
+    /// generation stops at the last soft derivation
+    /// ([`BIP44_SOFT_UPPER_BOUND`](../../bip/bip44/constant.BIP44_SOFT_UPPER_BOUND.html)).
+    ///
+    pub fn gen_addresses(&self, addr_type: AddrType, from: u32, count: u32) -> Result<Vec<ExtendedAddr>> {
+        let change = self.cached_root_key.change(self.derivation_scheme, addr_type)?;
+        let to = from.saturating_add(count).min(BIP44_SOFT_UPPER_BOUND);
+
+        let mut vec = Vec::with_capacity(to.saturating_sub(from) as usize);
+        for index in from..to {
+            let key = change.index(self.derivation_scheme, index)?;
+            vec.push(ExtendedAddr::new_simple(key.0));
+        }
+        Ok(vec)
+    }
 }
 impl Deref for Account<XPrv> {
     type Target = AccountLevel<XPrv>;
@@ -247,10 +400,12 @@ impl scheme::Account for Account<XPub> {
         let (hint_low, hint_max) = addresses.size_hint();
         let mut vec = Vec::with_capacity(hint_max.unwrap_or(hint_low));
 
+        // the change level keys are derived at most once each
+        let mut changes = ChangeCache::new();
+
         for addressing in addresses {
-            let key = self.cached_root_key
-                          .change(self.derivation_scheme, addressing.0).expect("cannot fail")
-                          .index(self.derivation_scheme, addressing.1).expect("cannot fail");
+            let key = changes.get(addressing.0, || self.cached_root_key.change(self.derivation_scheme, addressing.0).expect("cannot fail"))
+                             .index(self.derivation_scheme, addressing.1).expect("cannot fail");
             let addr = ExtendedAddr::new_simple(key.0);
             vec.push(addr);
         }
@@ -267,11 +422,22 @@ impl scheme::Account for Account<XPrv> {
         let (hint_low, hint_max) = addresses.size_hint();
         let mut vec = Vec::with_capacity(hint_max.unwrap_or(hint_low));
 
+        // the change level keys are derived at most once each, the public
+        // key of soft derived indices is then derived from the public change
+        // key, which is cheaper than deriving the private key.
+        let mut changes = ChangeCache::new();
+
         for addressing in addresses {
-            let key = self.cached_root_key
-                          .change(self.derivation_scheme, addressing.0)
-                          .index(self.derivation_scheme, addressing.1)
-                          .public();
+            let change = changes.get(addressing.0, || {
+                let change = self.cached_root_key.change(self.derivation_scheme, addressing.0);
+                let public = change.public();
+                (change, public)
+            });
+            let key = if addressing.1 < BIP44_SOFT_UPPER_BOUND {
+                change.1.index(self.derivation_scheme, addressing.1).expect("cannot fail, soft derivation")
+            } else {
+                IndexLevel::from(change.0.derive(self.derivation_scheme, addressing.1)).public()
+            };
             let addr = ExtendedAddr::new_simple(key.0);
             vec.push(addr);
         }
@@ -280,6 +446,115 @@ impl scheme::Account for Account<XPrv> {
     }
 }
 
+/// lazily computed change level keys, one for each `AddrType`
+struct ChangeCache<T> {
+    internal: Option<T>,
+    external: Option<T>,
+}
+impl<T> ChangeCache<T> {
+    fn new() -> Self { ChangeCache { internal: None, external: None } }
+
+    fn get<F>(&mut self, addr_type: AddrType, f: F) -> &T
+        where F: FnOnce() -> T
+    {
+        let slot = match addr_type {
+            AddrType::Internal => &mut self.internal,
+            AddrType::External => &mut self.external,
+        };
+        if slot.is_none() { *slot = Some(f()); }
+        slot.as_ref().unwrap()
+    }
+}
+
+/// the addresses of both chains of an account, see
+/// [`Wallet::gen_address_book`](./struct.Wallet.html#method.gen_address_book).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressBook {
+    /// the addresses given out to receive funds
+    pub external: Vec<ExtendedAddr>,
+    /// the change addresses
+    pub internal: Vec<ExtendedAddr>,
+}
+
+pub use config::DEFAULT_GAP_LIMIT;
+
+/// lookup structure to find out if an address belongs to a given account.
+///
+/// The first `gap_limit` addresses of both the internal and external
+/// chains are generated, then every time one of them is found the
+/// window is extended so there are always `gap_limit` addresses ahead
+/// of the last used one (see the BIP44 _address gap limit_).
+///
+/// # Example:
+///
+/// ```
+/// # use cardano::wallet::{bip44::{self, AddrType}, scheme::{Wallet}};
+/// # use cardano::bip::bip39::{MnemonicString, dictionary::ENGLISH};
+///
+/// let mnemonics = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let mnemonics = MnemonicString::new(&ENGLISH, mnemonics.to_owned()).unwrap();
+///
+/// let mut wallet = bip44::Wallet::from_bip39_mnemonics(&mnemonics, b"password", Default::default());
+/// let account = wallet.create_account("account 1", 0).public();
+///
+/// let address = account.gen_addresses(AddrType::Internal, 5, 1).unwrap().pop().unwrap();
+///
+/// let mut lookup = bip44::AddressLookup::new(account, bip44::DEFAULT_GAP_LIMIT);
+/// assert_eq!(lookup.is_ours(&address), Some((AddrType::Internal, 5)));
+/// ```
+///
+pub struct AddressLookup {
+    account: Account<XPub>,
+    gap_limit: u32,
+    expected: BTreeMap<ExtendedAddr, (AddrType, u32)>,
+    // next index to generate, for the internal and the external chains
+    next_internal: u32,
+    next_external: u32,
+}
+impl AddressLookup {
+    pub fn new(account: Account<XPub>, gap_limit: u32) -> Self {
+        let mut lookup = AddressLookup {
+            account,
+            gap_limit,
+            expected: BTreeMap::new(),
+            next_internal: 0,
+            next_external: 0,
+        };
+        lookup.extend(AddrType::Internal, gap_limit);
+        lookup.extend(AddrType::External, gap_limit);
+        lookup
+    }
+
+    /// generate the addresses of the given chain up to index `to` (excluded)
+    fn extend(&mut self, addr_type: AddrType, to: u32) {
+        let from = match addr_type {
+            AddrType::Internal => self.next_internal,
+            AddrType::External => self.next_external,
+        };
+        if to <= from { return; }
+        let addresses = self.account.gen_addresses(addr_type, from, to - from)
+                                    .expect("cannot fail, only soft derivations");
+        let next = from + addresses.len() as u32;
+        for (index, address) in (from..).zip(addresses) {
+            self.expected.insert(address, (addr_type, index));
+        }
+        match addr_type {
+            AddrType::Internal => self.next_internal = next,
+            AddrType::External => self.next_external = next,
+        }
+    }
+
+    /// return the addressing of the given address if it belongs to the account
+    pub fn is_ours(&mut self, address: &ExtendedAddr) -> Option<(AddrType, u32)> {
+        let found = self.expected.get(address).cloned();
+        if let Some((addr_type, index)) = found {
+            let gap_limit = self.gap_limit;
+            self.extend(addr_type, index.saturating_add(1).saturating_add(gap_limit));
+        }
+        found
+    }
+}
+
 /// create an `AddressGenerator`
 ///
 /// an address iterator starts from the given index, and stop when
@@ -303,7 +578,7 @@ impl Iterator for AddressGenerator<XPrv> {
         let index = self.index;
         self.index += 1;
 
-        let index = self.cached_root_key.index(self.derivation_scheme, index);
+        let index = self.cached_root_key.index(self.derivation_scheme, index).expect("cannot fail, soft derivation");
         Some(index)
     }
 }
@@ -323,9 +598,15 @@ impl Iterator for AddressGenerator<XPub> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RootLevel<T>(T);
 impl RootLevel<XPrv> {
-    pub fn account(&self, derivation_scheme: DerivationScheme, id: u32) -> AccountLevel<XPrv>
+    /// the account key of the given account number.
+    ///
+    /// The account number is the one given to `bip44::Account::new`, not
+    /// its scheme value: it fails with `Error::ExpectedSoftDerivation` if
+    /// `id` is already in the hardened range.
+    pub fn account(&self, derivation_scheme: DerivationScheme, id: u32) -> Result<AccountLevel<XPrv>>
     {
-        AccountLevel::from(self.0.derive(derivation_scheme, BIP44_SOFT_UPPER_BOUND | id))
+        if id >= BIP44_SOFT_UPPER_BOUND { return Err(Error::ExpectedSoftDerivation); }
+        Ok(AccountLevel::from(self.0.derive_hardened(derivation_scheme, BIP44_SOFT_UPPER_BOUND | id)?))
     }
 }
 impl<T> Deref for RootLevel<T> {
@@ -340,10 +621,10 @@ impl From<XPrv> for RootLevel<XPrv> {
 pub struct AccountLevel<T>(T);
 impl AccountLevel<XPrv> {
     pub fn external(&self, derivation_scheme: DerivationScheme) -> ChangeLevel<XPrv> {
-        ChangeLevel::from(self.0.derive(derivation_scheme, 0))
+        ChangeLevel::from(self.0.derive_soft(derivation_scheme, 0).unwrap())
     }
     pub fn internal(&self, derivation_scheme: DerivationScheme) -> ChangeLevel<XPrv> {
-        ChangeLevel::from(self.0.derive(derivation_scheme, 1))
+        ChangeLevel::from(self.0.derive_soft(derivation_scheme, 1).unwrap())
     }
     pub fn change(&self, derivation_scheme:DerivationScheme, addr_type: AddrType) -> ChangeLevel<XPrv> {
         match addr_type {
@@ -383,9 +664,14 @@ impl<T> Deref for AccountLevel<T> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ChangeLevel<T>(T);
 impl ChangeLevel<XPrv> {
-    pub fn index(&self, derivation_scheme: DerivationScheme, index: DerivationIndex) -> IndexLevel<XPrv>
+    /// the key of the given address index.
+    ///
+    /// It fails with `Error::ExpectedSoftDerivation` if `index` is in the
+    /// hardened range: the key would not match the one derived from the
+    /// public change key.
+    pub fn index(&self, derivation_scheme: DerivationScheme, index: DerivationIndex) -> Result<IndexLevel<XPrv>>
     {
-        IndexLevel::from(self.0.derive(derivation_scheme, index))
+        Ok(IndexLevel::from(self.0.derive_soft(derivation_scheme, index)?))
     }
     pub fn public(&self) -> ChangeLevel<XPub> {
         ChangeLevel::from(self.0.public())
@@ -425,3 +711,322 @@ impl<T> Deref for IndexLevel<T> {
     type Target = T;
     fn deref(&self) -> &T { &self.0 }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::scheme::{Wallet as _Wallet, Account as _Account};
+    use bip::bip39::{MnemonicString, dictionary::ENGLISH};
+
+    const MNEMONICS : &'static str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn account(derivation_scheme: DerivationScheme) -> Account<XPrv> {
+        let mnemonics = MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap();
+        let mut wallet = Wallet::from_bip39_mnemonics(&mnemonics, b"password", derivation_scheme);
+        wallet.create_account("account 1", 0)
+    }
+
+    fn gen_addresses_reference(account: &Account<XPrv>, addr_type: AddrType, from: u32, count: usize) -> Vec<ExtendedAddr> {
+        account.address_generator(addr_type, from)
+               .take(count)
+               .map(|xprv| ExtendedAddr::new_simple(xprv.public().0))
+               .collect()
+    }
+
+    fn wallet() -> Wallet {
+        let mnemonics = MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap();
+        Wallet::from_bip39_mnemonics(&mnemonics, b"password", DerivationScheme::V2)
+    }
+
+    #[test]
+    fn from_mnemonic_phrase() {
+        let restored = Wallet::from_mnemonic_phrase(MNEMONICS, "password", DerivationScheme::V2).unwrap();
+        assert_eq!(restored.root_fingerprint(), wallet().root_fingerprint());
+    }
+
+    #[test]
+    fn from_mnemonic_phrase_invalid() {
+        let invalid_checksum = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        match Wallet::from_mnemonic_phrase(invalid_checksum, "password", DerivationScheme::V2) {
+            Err(bip39::Error::InvalidChecksum(_, _)) => {},
+            Err(err) => panic!("expected an invalid checksum, got {}", err),
+            Ok(_) => panic!("the phrase has an invalid checksum"),
+        }
+        match Wallet::from_mnemonic_phrase("abandon abandon abandon", "password", DerivationScheme::V2) {
+            Err(bip39::Error::WrongNumberOfWords(3)) => {},
+            Err(err) => panic!("expected a wrong number of words, got {}", err),
+            Ok(_) => panic!("the phrase is too short"),
+        }
+        assert!(Wallet::from_mnemonic_phrase(&MNEMONICS.replace("about", "abut"), "password", DerivationScheme::V2).is_err());
+    }
+
+    #[test]
+    fn gen_address_book() {
+        let mut wallet = wallet();
+        let book = wallet.gen_address_book(0, 5).unwrap();
+        assert_eq!(book.external.len(), 5);
+        assert_eq!(book.internal.len(), 5);
+        assert!(book.external.iter().all(|address| !book.internal.contains(address)));
+
+        let account = wallet.create_account("account 1", 0);
+        assert_eq!(book.external, account.gen_addresses(AddrType::External, 0, 5));
+        assert_eq!(book.internal, account.gen_addresses(AddrType::Internal, 0, 5));
+        assert_ne!(wallet.gen_address_book(1, 5).unwrap(), book);
+    }
+
+    #[test]
+    fn root_fingerprint() {
+        let seed = bip39::Seed::from_mnemonic_string(&MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap(), b"password");
+        let fingerprint = Wallet::from_bip39_seed(&seed, DerivationScheme::V2).root_fingerprint();
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(wallet().root_fingerprint(), fingerprint);
+        assert_eq!(Wallet::from_bip39_seed(&seed, DerivationScheme::V2).root_fingerprint(), fingerprint);
+
+        let other = bip39::Seed::from_mnemonic_string(&MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap(), b"other password");
+        assert_ne!(Wallet::from_bip39_seed(&other, DerivationScheme::V2).root_fingerprint(), fingerprint);
+        assert_ne!(Wallet::from_bip39_seed(&seed, DerivationScheme::V1).root_fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn account_rejects_a_hardened_account_number() {
+        match wallet().account(DerivationScheme::V2, bip44::Account::new(0).unwrap().get_scheme_value()) {
+            Err(Error::ExpectedSoftDerivation) => {},
+            Err(err) => panic!("expected a soft derivation error, got {}", err),
+            Ok(_) => panic!("the account number is in the hardened range"),
+        }
+    }
+
+    #[test]
+    fn index_rejects_a_hardened_index() {
+        let change = wallet().account(DerivationScheme::V2, 0).unwrap().internal(DerivationScheme::V2);
+        match change.index(DerivationScheme::V2, 0x80000000) {
+            Err(Error::ExpectedSoftDerivation) => {},
+            Err(err) => panic!("expected a soft derivation error, got {}", err),
+            Ok(_) => panic!("the address index is in the hardened range"),
+        }
+        assert!(change.index(DerivationScheme::V2, 0x7fffffff).is_ok());
+    }
+
+    #[test]
+    fn gen_addresses_1000() {
+        for scheme in [DerivationScheme::V1, DerivationScheme::V2].iter() {
+            let account = account(*scheme);
+            let expected = gen_addresses_reference(&account, AddrType::External, 0, 1000);
+
+            assert_eq!(account.gen_addresses(AddrType::External, 0, 1000), expected);
+            assert_eq!(account.public().gen_addresses(AddrType::External, 0, 1000).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn gen_addresses_stop_at_soft_upper_bound() {
+        let account = account(DerivationScheme::V2);
+        let addresses = account.gen_addresses(AddrType::Internal, BIP44_SOFT_UPPER_BOUND - 2, 10);
+        assert_eq!(addresses, gen_addresses_reference(&account, AddrType::Internal, BIP44_SOFT_UPPER_BOUND - 2, 10));
+        assert_eq!(addresses.len(), 2);
+    }
+
+    #[test]
+    fn generate_addresses_mixed_types() {
+        let account = account(DerivationScheme::V2);
+        let addressing : Vec<_> = (0..50).map(|i| (if i % 3 == 0 { AddrType::Internal } else { AddrType::External }, i)).collect();
+
+        let expected : Vec<_> = addressing.iter().map(|&(addr_type, index)| {
+            let key = account.change(DerivationScheme::V2, addr_type).index(DerivationScheme::V2, index).unwrap().public();
+            ExtendedAddr::new_simple(key.0)
+        }).collect();
+
+        assert_eq!(account.generate_addresses(addressing.iter()), expected);
+        assert_eq!(account.public().generate_addresses(addressing.iter()), expected);
+    }
+
+    #[test]
+    fn address_lookup_extends_gap() {
+        let account = account(DerivationScheme::V2);
+        let addresses = account.gen_addresses(AddrType::External, 0, 10);
+        let mut lookup = AddressLookup::new(account.public(), 3);
+
+        // past the gap limit, not found yet
+        assert_eq!(lookup.is_ours(&addresses[5]), None);
+        // every address found pushes the window further
+        assert_eq!(lookup.is_ours(&addresses[2]), Some((AddrType::External, 2)));
+        assert_eq!(lookup.is_ours(&addresses[5]), Some((AddrType::External, 5)));
+        assert_eq!(lookup.is_ours(&addresses[8]), Some((AddrType::External, 8)));
+        // the internal chain did not move
+        let internal = account.gen_addresses(AddrType::Internal, 3, 1);
+        assert_eq!(lookup.is_ours(&internal[0]), None);
+    }
+
+    #[test]
+    fn next_change_index() {
+        use coin::Coin;
+        let mnemonics = MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap();
+        let wallet = Wallet::from_bip39_mnemonics(&mnemonics, b"password", DerivationScheme::V2);
+        let account = account(DerivationScheme::V2);
+        let address = |addr_type, index| account.gen_addresses(addr_type, index, 1).pop().unwrap();
+
+        let mut utxo = tx::UtxoSet::new();
+        assert_eq!(wallet.next_change_index(&utxo, 0, DEFAULT_GAP_LIMIT).unwrap(), 0);
+
+        let mut tx = tx::Tx::new();
+        tx.add_input(tx::TxIn::new(TxId::new(&[0;32]), 0));
+        // 22 is past the gap limit until 3 is found
+        for &(addr_type, index) in [(AddrType::Internal, 22), (AddrType::Internal, 3), (AddrType::External, 30)].iter() {
+            tx.add_output(tx::TxOut::new(address(addr_type, index), Coin::new(1000).unwrap()));
+        }
+        utxo.apply_tx(&tx);
+        assert_eq!(wallet.next_change_index(&utxo, 0, DEFAULT_GAP_LIMIT).unwrap(), 23);
+        // the addresses belong to the account 0 only
+        assert_eq!(wallet.next_change_index(&utxo, 1, DEFAULT_GAP_LIMIT).unwrap(), 0);
+    }
+
+    #[test]
+    fn used_addresses() {
+        use coin::Coin;
+        let account = account(DerivationScheme::V2);
+        let address = |addr_type, index| account.gen_addresses(addr_type, index, 1).pop().unwrap();
+        let output = |addr_type, index| tx::TxOut::new(address(addr_type, index), Coin::new(1000).unwrap());
+
+        // the external 4 was spent, the external 24 is past the gap limit
+        // until 4 is found
+        let spent = vec![output(AddrType::External, 4)];
+        let mut utxo = tx::UtxoSet::new();
+        let mut tx = tx::Tx::new();
+        tx.add_input(tx::TxIn::new(TxId::new(&[0;32]), 0));
+        tx.add_output(output(AddrType::External, 24));
+        tx.add_output(output(AddrType::Internal, 1));
+        tx.add_output(output(AddrType::External, 24));
+        utxo.apply_tx(&tx);
+
+        let used = wallet().used_addresses(utxo.iter().map(|(_, txout)| txout).chain(spent.iter()), 0, DEFAULT_GAP_LIMIT).unwrap();
+        let expected : Vec<_> = [(AddrType::External, 4), (AddrType::External, 24), (AddrType::Internal, 1)].iter().map(|&(addr_type, index)| {
+            (Addressing::new(0, addr_type).unwrap().incr(index).unwrap(), address(addr_type, index))
+        }).collect();
+        assert_eq!(used, expected);
+
+        // the addresses belong to the account 0 only
+        assert_eq!(wallet().used_addresses(spent.iter(), 1, DEFAULT_GAP_LIMIT).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn used_addresses_within_the_gap_limit() {
+        use coin::Coin;
+        let account = account(DerivationScheme::V2);
+        let output = |index| tx::TxOut::new(account.gen_addresses(AddrType::External, index, 1).pop().unwrap(), Coin::new(1000).unwrap());
+        let addressing = |index| Addressing::new(0, AddrType::External).unwrap().incr(index).unwrap();
+
+        // with a gap of 5, the addresses 0 to 4 are looked up
+        let used = wallet().used_addresses([output(4)].iter(), 0, 5).unwrap();
+        assert_eq!(used.iter().map(|(addressing, _)| *addressing).collect::<Vec<_>>(), vec![addressing(4)]);
+        assert_eq!(wallet().used_addresses([output(5)].iter(), 0, 5).unwrap(), vec![]);
+        // the default gap reaches it
+        assert_eq!(wallet().used_addresses([output(5)].iter(), 0, DEFAULT_GAP_LIMIT).unwrap().len(), 1);
+    }
+
+    // estimate the fee then create the transaction spending one input
+    fn spend_one_input(addressing: Addressing) -> (::fee::Result<::fee::Fee>, ::fee::Result<(tx::TxAux, ::fee::Fee)>) {
+        use coin::Coin;
+        use txutils::{Input, OutputPolicy};
+        let mnemonics = MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap();
+        let mut wallet = Wallet::from_bip39_mnemonics(&mnemonics, b"password", DerivationScheme::V2);
+        let account = wallet.create_account("account 1", 0);
+        let address = |addr_type, index| account.gen_addresses(addr_type, index, 1).pop().unwrap();
+
+        // the input is at the first external address
+        let input = Input::new( tx::TxIn::new(TxId::new(&[0;32]), 0)
+                              , tx::TxOut::new(address(AddrType::External, 0), Coin::new(10_000_000).unwrap())
+                              , addressing);
+        let inputs = vec![input];
+        let outputs = vec![tx::TxOut::new(address(AddrType::External, 1), Coin::new(1_000_000).unwrap())];
+        let change = OutputPolicy::One(address(AddrType::Internal, 0));
+
+        let estimate = wallet.estimate_fee(::fee::SelectionPolicy::default(), inputs.iter(), &outputs, &change);
+        let tx = wallet.new_transaction(&Default::default(), ::fee::SelectionPolicy::default(), inputs.iter(), outputs, &change);
+        (estimate, tx)
+    }
+
+    #[test]
+    fn estimate_fee_is_the_transaction_fee() {
+        use fee::{LinearFee, FeeAlgorithm};
+
+        let (estimate, built) = spend_one_input(Addressing::new(0, AddrType::External).unwrap());
+        let (txaux, fee) = built.unwrap();
+        assert_eq!(estimate.unwrap(), fee);
+        assert!(fee >= LinearFee::default().calculate_for_txaux(&txaux).unwrap());
+    }
+
+    #[test]
+    fn new_transaction_has_no_fee_output() {
+        use coin::Coin;
+        use txutils::output_sum;
+        let (txaux, fee) = spend_one_input(Addressing::new(0, AddrType::External).unwrap()).1.unwrap();
+
+        // only the requested output and the change, no output for the fee
+        assert_eq!(txaux.tx.outputs.len(), 2);
+        let total_out = output_sum(txaux.tx.outputs.iter()).unwrap();
+        assert_eq!((total_out + fee.to_coin()).unwrap(), Coin::new(10_000_000).unwrap());
+    }
+
+    #[test]
+    fn new_transaction_checks_the_addressing() {
+        let mut addressing = Addressing::new(0, AddrType::External).unwrap();
+        addressing.index = Index::new(1).unwrap();
+        match spend_one_input(addressing).1 {
+            Err(::fee::Error::AddressMismatch(txin)) => assert_eq!(txin, tx::TxIn::new(TxId::new(&[0;32]), 0)),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("the transaction should not have been signed"),
+        }
+    }
+
+    #[test]
+    fn new_transaction_too_large() {
+        use coin::Coin;
+        use txutils::{Input, OutputPolicy};
+        let mnemonics = MnemonicString::new(&ENGLISH, MNEMONICS.to_owned()).unwrap();
+        let mut wallet = Wallet::from_bip39_mnemonics(&mnemonics, b"password", DerivationScheme::V2);
+        let account = wallet.create_account("account 1", 0);
+        let address = account.gen_addresses(AddrType::External, 0, 1).pop().unwrap();
+
+        // many small inputs at the first external address
+        let inputs : Vec<_> = (0..100).map(|i| {
+            Input::new( tx::TxIn::new(TxId::new(&[0;32]), i)
+                      , tx::TxOut::new(address.clone(), Coin::new(1_000_000).unwrap())
+                      , Addressing::new(0, AddrType::External).unwrap())
+        }).collect();
+        let outputs = vec![tx::TxOut::new(address.clone(), Coin::new(50_000_000).unwrap())];
+        let change = OutputPolicy::One(address.clone());
+
+        let new_transaction = |max_tx_bytes| {
+            let config = ::config::Config { max_tx_bytes: max_tx_bytes, ..Default::default() };
+            wallet.new_transaction(&config, ::fee::SelectionPolicy::default(), inputs.iter(), outputs.clone(), &change)
+        };
+        let size = match new_transaction(::tx::DEFAULT_MAX_TX_BYTES) {
+            Err(::fee::Error::TxTooLarge(size)) => size,
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("the transaction should be too large"),
+        };
+        assert!(size > ::tx::DEFAULT_MAX_TX_BYTES);
+        let (txaux, _) = new_transaction(size).unwrap();
+        assert_eq!(txaux.to_submit_bytes().len(), size);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "with-bench")]
+mod bench {
+    use super::*;
+    use super::scheme::{Wallet as _Wallet};
+    use bip::bip39::{MnemonicString, dictionary::ENGLISH};
+    use test;
+
+    #[bench]
+    fn gen_addresses_1000(b: &mut test::Bencher) {
+        let mnemonics = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonics = MnemonicString::new(&ENGLISH, mnemonics.to_owned()).unwrap();
+        let mut wallet = Wallet::from_bip39_mnemonics(&mnemonics, b"password", Default::default());
+        let account = wallet.create_account("account 1", 0);
+        b.iter(|| {
+            let _ = account.gen_addresses(AddrType::External, 0, 1000);
+        })
+    }
+}
@@ -49,13 +49,21 @@ impl Wallet {
     /// There are many things that can go wrong when implementing this
     /// process, it is all done correctly by this function: prefer using
     /// this function.
-    pub fn from_daedalus_mnemonics<D>(derivation_scheme: DerivationScheme, dic: &D, mnemonics_phrase: String) -> Result<Self>
+    pub fn from_daedalus_mnemonics_with<D>(derivation_scheme: DerivationScheme, dic: &D, mnemonics_phrase: String) -> Result<Self>
         where D: bip39::dictionary::Language
     {
         let root_key = RootKey::from_daedalus_mnemonics(derivation_scheme, dic, mnemonics_phrase)?;
         Ok(Wallet::from_root_key(derivation_scheme, root_key))
     }
 
+    /// import a Daedalus wallet from its english mnemonic words.
+    ///
+    /// This is `from_daedalus_mnemonics_with` with the english dictionary
+    /// and the `DerivationScheme::V1` used by Daedalus.
+    pub fn from_daedalus_mnemonics(words: &str) -> Result<Self> {
+        Wallet::from_daedalus_mnemonics_with(DerivationScheme::V1, &bip39::dictionary::ENGLISH, words.to_owned())
+    }
+
     /// test that the given address belongs to the wallet.
     ///
     /// This only possible because addresses from this wallet contain
@@ -278,3 +286,22 @@ impl Deref for RootKey {
     type Target = XPrv;
     fn deref(&self) -> &Self::Target { &self.root_key }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::scheme::{Wallet as _Wallet, Account as _Account};
+
+    const MNEMONICS : &'static str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    // the address at the addressing (0', 0') of the wallet above
+    const FIRST_ADDRESS : &'static str = "DdzFFzCqrhsi45bifVfbvE2fpg1FhoWvouuw6U5owRcQ1BDEff8vk92cmhC2FuTgn88ZSiqGNZACYrns6TtZxWzGPYr4GTh9i3nUmuuJ";
+
+    #[test]
+    fn daedalus_first_address() {
+        let wallet = Wallet::from_daedalus_mnemonics(MNEMONICS).unwrap();
+        let address = wallet.list_accounts().generate_addresses([(0x80000000, 0x80000000)].iter()).pop().unwrap();
+
+        assert_eq!(format!("{}", address), FIRST_ADDRESS);
+        assert_eq!(wallet.check_address(&address), Some((0x80000000, 0x80000000)));
+    }
+}
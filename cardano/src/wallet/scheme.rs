@@ -3,10 +3,11 @@
 //!
 
 use tx::{self, TxId, TxOut, TxInWitness};
+use cbor_event::se::Serializer;
 use fee::{self, SelectionAlgorithm};
 use txutils::{Input, OutputPolicy};
 use coin::Coin;
-use config::{ProtocolMagic};
+use config::{self, ProtocolMagic};
 use address::{ExtendedAddr};
 
 /// main wallet scheme, provides all the details to manage a wallet:
@@ -47,15 +48,27 @@ pub trait Wallet {
     /// it select the needed inputs, compute the fee and possible change
     /// signes every TxIn as needed.
     ///
-    fn new_transaction<'a, I>( &self
-                             , protocol_magic: ProtocolMagic
-                             , selection_policy: fee::SelectionPolicy
-                             , inputs: I
-                             , outputs: Vec<TxOut>
-                             , output_policy: &OutputPolicy
-                             )
+    /// The fee is not an output of the transaction: it is the difference
+    /// between the selected inputs and the outputs (including the change)
+    /// and is collected by the node.
+    ///
+    /// Every witness is checked against the address of the input it
+    /// spends, a wrong addressing fails with `fee::Error::AddressMismatch`.
+    ///
+    /// A transaction larger than the configuration's `max_tx_bytes` fails
+    /// with `fee::Error::TxTooLarge`. The size is checked before signing,
+    /// with placeholder witnesses of the size of the real ones.
+    ///
+    fn new_transaction<'a, S, I>( &self
+                                , config: &config::Config
+                                , selection_policy: S
+                                , inputs: I
+                                , outputs: Vec<TxOut>
+                                , output_policy: &OutputPolicy
+                                )
             -> fee::Result<(tx::TxAux, fee::Fee)>
-        where I : 'a + Iterator<Item = &'a Input<Self::Addressing>> + ExactSizeIterator
+        where S : fee::SelectionStrategy
+            , I : 'a + Iterator<Item = &'a Input<Self::Addressing>> + ExactSizeIterator
             , Self::Addressing: 'a
     {
         let alg = fee::LinearFee::default();
@@ -78,10 +91,41 @@ pub trait Wallet {
             };
         }
 
-        let witnesses = self.sign_tx(protocol_magic, &tx.id(), addressings.iter());
+        let fake_witnesses = vec![TxInWitness::fake(); addressings.len()];
+        let size = tx::txaux_serialize(&tx, &fake_witnesses, Serializer::new_vec())?.finalize().len();
+        if size > config.max_tx_bytes {
+            return Err(fee::Error::TxTooLarge(size));
+        }
+
+        let witnesses = self.sign_tx(config.protocol_magic, &tx.id(), addressings.iter());
+
+        for (input, witness) in selected_inputs.iter().zip(witnesses.iter()) {
+            if ! witness.verify_address(&input.value.address) {
+                return Err(fee::Error::AddressMismatch(input.ptr.clone()));
+            }
+        }
 
         Ok((tx::TxAux::new(tx, witnesses), fee))
     }
+
+    /// the fee `new_transaction` would pay given the same arguments,
+    /// without signing anything.
+    fn estimate_fee<'a, S, I>( &self
+                             , selection_policy: S
+                             , inputs: I
+                             , outputs: &[TxOut]
+                             , output_policy: &OutputPolicy
+                             )
+            -> fee::Result<fee::Fee>
+        where S : fee::SelectionStrategy
+            , I : 'a + Iterator<Item = &'a Input<Self::Addressing>> + ExactSizeIterator
+            , Self::Addressing: 'a
+    {
+        let alg = fee::LinearFee::default();
+
+        let (fee, _, _) = alg.compute(selection_policy, inputs, outputs.iter(), output_policy)?;
+        Ok(fee)
+    }
 }
 
 /// account level scheme, provides all the details to manage an account:
@@ -0,0 +1,53 @@
+//! `BlockHeader::compute_hash_into` is there to hash many headers without
+//! allocating an encoding buffer for each of them. Counting allocations
+//! needs a global allocator, so this test lives in its own binary.
+
+extern crate cardano;
+extern crate cryptoxide;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use cardano::block::RawBlockHeader;
+use cryptoxide::blake2b::Blake2b;
+
+#[path = "../src/block/test_vectors.rs"]
+#[allow(dead_code)]
+mod test_vectors;
+
+/// count the allocations of the current thread, so the tests running
+/// in parallel do not interfere with each other's counts
+struct CountingAllocator;
+thread_local!(static ALLOCATIONS: Cell<usize> = const { Cell::new(0) });
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { System.dealloc(ptr, layout) }
+}
+#[global_allocator]
+static ALLOCATOR : CountingAllocator = CountingAllocator;
+
+fn count_allocations<F: FnMut()>(mut f: F) -> usize {
+    let before = ALLOCATIONS.with(|count| count.get());
+    f();
+    ALLOCATIONS.with(|count| count.get()) - before
+}
+
+#[test]
+fn compute_hash_into_allocations() {
+    const ITERATIONS : usize = 1000;
+    let header = RawBlockHeader::from_dat(test_vectors::MAINNET_BLOCK_HEADER.to_vec()).decode().unwrap();
+
+    let naive = count_allocations(|| {
+        for _ in 0..ITERATIONS { header.compute_hash(); }
+    });
+    let mut hasher = Blake2b::new(32);
+    let into = count_allocations(|| {
+        for _ in 0..ITERATIONS { header.compute_hash_into(&mut hasher); }
+    });
+
+    // the naive path allocates at least its encoding buffer every time
+    assert!(naive >= ITERATIONS, "compute_hash: {} allocations", naive);
+    assert!(into < naive, "compute_hash_into: {} allocations, compute_hash: {}", into, naive);
+}
@@ -405,7 +405,9 @@ impl<'a> RawCbor<'a> {
             Len::Indefinite => Err(Error::IndefiniteLenNotSupported(Type::Bytes)),
             Len::Len(len) => {
                 let start = 1 + len_sz;
-                let end   = start + len as usize;
+                // a huge declared length must not wrap around
+                let end   = start.checked_add(len as usize).ok_or(Error::NotEnough(self.len(), usize::MAX))?;
+                if end > self.len() { return Err(Error::NotEnough(self.len(), end)); }
                 let bytes = Bytes::from(&self.0[start..end as usize]);
                 self.advance(end)?;
                 Ok(bytes)
@@ -436,7 +438,9 @@ impl<'a> RawCbor<'a> {
             Len::Indefinite => Err(Error::IndefiniteLenNotSupported(Type::Text)),
             Len::Len(len) => {
                 let start = 1 + len_sz;
-                let end   = start + len as usize;
+                // a huge declared length must not wrap around
+                let end   = start.checked_add(len as usize).ok_or(Error::NotEnough(self.len(), usize::MAX))?;
+                if end > self.len() { return Err(Error::NotEnough(self.len(), end)); }
                 let bytes = &self.0[start..end as usize];
                 let text = String::from_utf8(Vec::from(bytes))?;
                 self.advance(end)?;
@@ -470,6 +474,35 @@ impl<'a> RawCbor<'a> {
         Ok(len)
     }
 
+    /// cbor array of the given number of elements, i.e. the encoding of
+    /// a struct or a tuple. `name` is the name of the object being
+    /// decoded, used in the error messages.
+    ///
+    /// Fails with `Error::UnparsedValues` if the array has more elements
+    /// than expected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cbor_event::{de::{*}, Error};
+    ///
+    /// let vec = vec![0x83, 0,1,2];
+    ///
+    /// assert!(RawCbor::from(&vec).tuple(3, "Triple").is_ok());
+    /// match RawCbor::from(&vec).tuple(2, "Pair") {
+    ///     Err(Error::UnparsedValues("Pair", 1)) => {},
+    ///     _ => panic!("expected one unparsed value"),
+    /// }
+    /// ```
+    ///
+    pub fn tuple(&mut self, expected: u64, name: &'static str) -> Result<()> {
+        match self.array()? {
+            Len::Len(len) if len == expected => Ok(()),
+            Len::Len(len) if len > expected => Err(Error::UnparsedValues(name, (len - expected) as usize)),
+            len => Err(Error::CustomError(format!("Invalid {}: recieved array of {:?} elements", name, len))),
+        }
+    }
+
     /// cbor map
     ///
     /// The function fails if the type of the given RawCbor is not `Type::Map`.
@@ -569,6 +602,20 @@ impl<'a> Deref for RawCbor<'a> {
 mod test {
     use super::*;
 
+    #[test]
+    fn tuple_with_unparsed_values() {
+        let vec = vec![0x85, 0,1,2,3,4];
+
+        match RawCbor::from(&vec).tuple(2, "Pair") {
+            Err(Error::UnparsedValues("Pair", 3)) => {},
+            r => panic!("expected 3 unparsed values, got {:?}", r),
+        }
+        match RawCbor::from(&vec).tuple(6, "Sextuple") {
+            Err(Error::CustomError(_)) => {},
+            r => panic!("expected a length error, got {:?}", r),
+        }
+    }
+
     #[test]
     fn negative_integer() {
         let vec = vec![0x38, 0x29];
@@ -595,6 +642,29 @@ mod test {
         let bytes = raw.bytes().unwrap();
         assert!(bytes.is_empty());
     }
+    #[test]
+    fn bytes_truncated() {
+        let vec = vec![0x52, 0x73, 0x6F, 0x6D];
+        let mut raw = RawCbor::from(&vec);
+
+        assert!(raw.bytes().is_err());
+        assert!(RawCbor::from(&[0x64, 0x74][..]).text().is_err());
+    }
+    #[test]
+    fn bytes_huge_length() {
+        // the declared length is `u64::MAX`
+        let mut vec = vec![0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        vec.extend_from_slice(&[0x00, 0x01]);
+        match RawCbor::from(&vec).bytes() {
+            Err(Error::NotEnough(11, _)) => {},
+            r => panic!("expected not enough bytes, got {:?}", r),
+        }
+        vec[0] = 0x7b;
+        match RawCbor::from(&vec).text() {
+            Err(Error::NotEnough(11, _)) => {},
+            r => panic!("expected not enough bytes, got {:?}", r),
+        }
+    }
 
     #[test]
     fn text() {
@@ -25,6 +25,14 @@ pub enum Error {
     InvalidTextError(::std::string::FromUtf8Error),
     CannotParse(Type, Vec<u8>),
     IoError(::std::io::Error),
+    /// the object starting at the given byte offset is not encoded with
+    /// the smallest possible representation.
+    NonCanonical(usize),
+    /// the array has more elements than the decoded object, the parameters
+    /// are the name of the object and the number of elements left unparsed.
+    UnparsedValues(&'static str, usize),
+    /// the object nests more arrays, maps and tags than the given maximum.
+    TooDeep(usize),
 
     CustomError(String)
 }
@@ -54,6 +62,9 @@ impl fmt::Display for Error {
             InvalidTextError(utf8_error) => write!(f, "Invalid cbor: expected a valid utf8 string text. {:?}", utf8_error),
             CannotParse(t, bytes) => write!(f, "Invalid cbor: cannot parse the cbor object `{:?}' with the following bytes {:?}", t, bytes),
             IoError(io_error) => write!(f, "Invalid cbor: I/O error: {:?}.", io_error),
+            NonCanonical(offset) => write!(f, "Invalid cbor: non canonical encoding of the object at byte offset {}.", offset),
+            UnparsedValues(name, count) => write!(f, "Invalid cbor: {} unparsed values left in the array of {}.", count, name),
+            TooDeep(max) => write!(f, "Invalid cbor: the object is nested more than {} levels deep.", max),
             CustomError(err) => write!(f, "Invalid cbor: {}", err)
         }
     }
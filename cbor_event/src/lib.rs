@@ -66,6 +66,7 @@ pub mod de;
 pub mod se;
 mod value;
 mod macros;
+mod reader;
 
 pub use len::{*};
 pub use types::{*};
@@ -74,6 +75,7 @@ pub use error::{Error};
 pub use de::{Deserialize};
 pub use se::{Serialize};
 pub use value::{ObjectKey, Value};
+pub use reader::{Reader};
 
 const MAX_INLINE_ENCODING : u64 = 23;
 
@@ -94,3 +96,162 @@ pub fn test_encode_decode<V: Sized+PartialEq+Serialize+Deserialize>(v: &V) -> Re
 
     Ok(v == &v_)
 }
+
+/// check the given bytes are the canonical CBOR encoding of one object.
+///
+/// Re-encoding an object needs to give the same bytes as the original
+/// encoding or the hashes (and the proofs) computed on these bytes won't
+/// match. This function checks every integer, length and tag is encoded
+/// with the smallest possible representation and that there are no
+/// trailing bytes after the object.
+///
+/// Definite and indefinite length arrays and maps are both accepted: the
+/// choice between the two is specific to every type (some types are
+/// always encoded with indefinite length).
+///
+/// # Example
+///
+/// ```
+/// use cbor_event::{assert_canonical};
+///
+/// assert!(assert_canonical(&[0x18, 0x40]).is_ok());
+/// // `0x05` encoded on 2 bytes while it fits in the type byte
+/// assert!(assert_canonical(&[0x18, 0x05]).is_err());
+/// ```
+///
+pub fn assert_canonical(bytes: &[u8]) -> Result<()> {
+    let mut raw = de::RawCbor::from(bytes);
+    assert_canonical_object(bytes.len(), &mut raw)?;
+    if raw.len() > 0 {
+        return Err(Error::CustomError(format!("{} trailing bytes after the cbor object", raw.len())));
+    }
+    Ok(())
+}
+
+fn first_byte(raw: &de::RawCbor) -> Result<u8> {
+    raw.as_ref().first().cloned().ok_or(Error::NotEnough(0, 1))
+}
+
+fn assert_canonical_object<'a>(total: usize, raw: &mut de::RawCbor<'a>) -> Result<()> {
+    let offset = total - raw.len();
+    let cbor_type = raw.cbor_type()?;
+
+    if cbor_type == Type::Special {
+        let b = first_byte(raw)? & 0b0001_1111;
+        return match b {
+            0x00..=0x17 => raw.advance(1),
+            // simple values below 32 must be encoded in the type byte
+            0x18 => if raw.as_ref().get(1).map(|v| *v < 32).unwrap_or(false) {
+                Err(Error::NonCanonical(offset))
+            } else {
+                raw.advance(2)
+            },
+            0x19 => raw.advance(3),
+            0x1a => raw.advance(5),
+            0x1b => raw.advance(9),
+            _    => Err(Error::NonCanonical(offset)),
+        };
+    }
+
+    let (len, len_sz) = raw.cbor_len()?;
+    let len = match len {
+        Len::Indefinite => {
+            raw.advance(1)?;
+            match cbor_type {
+                Type::Array | Type::Map => {},
+                t => return Err(Error::IndefiniteLenNotSupported(t)),
+            }
+            loop {
+                if raw.cbor_type()? == Type::Special && first_byte(raw)? == 0xff {
+                    return raw.advance(1);
+                }
+                assert_canonical_object(total, raw)?;
+                if cbor_type == Type::Map { assert_canonical_object(total, raw)?; }
+            }
+        },
+        Len::Len(len) => len,
+    };
+
+    let minimal_sz = match len {
+        0..=MAX_INLINE_ENCODING => 0,
+        0x18..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x10000..=0xffff_ffff => 4,
+        _ => 8,
+    };
+    if len_sz != minimal_sz { return Err(Error::NonCanonical(offset)); }
+    raw.advance(1 + len_sz)?;
+
+    match cbor_type {
+        Type::UnsignedInteger | Type::NegativeInteger => Ok(()),
+        Type::Bytes | Type::Text => raw.advance(len as usize),
+        Type::Array => {
+            for _ in 0..len { assert_canonical_object(total, raw)?; }
+            Ok(())
+        },
+        Type::Map => {
+            for _ in 0..(len * 2) { assert_canonical_object(total, raw)?; }
+            Ok(())
+        },
+        Type::Tag => assert_canonical_object(total, raw),
+        Type::Special => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn canonical_integers() {
+        assert!(assert_canonical(&[0x17]).is_ok());
+        assert!(assert_canonical(&[0x18, 0x18]).is_ok());
+        assert!(assert_canonical(&[0x19, 0x01, 0x00]).is_ok());
+        assert!(assert_canonical(&[0x1a, 0x00, 0x01, 0x00, 0x00]).is_ok());
+        assert!(assert_canonical(&[0x3b, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]).is_ok());
+    }
+
+    #[test]
+    fn non_minimal_integers_are_flagged() {
+        match assert_canonical(&[0x18, 0x17]) {
+            Err(Error::NonCanonical(0)) => {},
+            r => panic!("expected non canonical error, got {:?}", r),
+        }
+        assert!(assert_canonical(&[0x19, 0x00, 0xff]).is_err());
+        assert!(assert_canonical(&[0x1a, 0x00, 0x00, 0xff, 0xff]).is_err());
+        assert!(assert_canonical(&[0x1b, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn non_minimal_nested_length_is_flagged() {
+        // array of 1 element: a bytes of length 1 encoded with a 1 byte length
+        match assert_canonical(&[0x81, 0x58, 0x01, 0x00]) {
+            Err(Error::NonCanonical(1)) => {},
+            r => panic!("expected non canonical error, got {:?}", r),
+        }
+        assert!(assert_canonical(&[0x81, 0x41, 0x00]).is_ok());
+    }
+
+    #[test]
+    fn indefinite_collections() {
+        assert!(assert_canonical(&[0x9f, 0x01, 0x02, 0xff]).is_ok());
+        assert!(assert_canonical(&[0xbf, 0x01, 0x02, 0xff]).is_ok());
+        assert!(assert_canonical(&[0x9f, 0x18, 0x01, 0xff]).is_err());
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        assert!(assert_canonical(&[0x01, 0x02]).is_err());
+    }
+
+    #[test]
+    fn encoder_is_canonical() {
+        let bytes = se::Serializer::new_vec()
+            .write_array(Len::Len(3)).unwrap()
+            .write_unsigned_integer(24).unwrap()
+            .write_negative_integer(-300).unwrap()
+            .write_bytes(&[0u8;300]).unwrap()
+            .finalize();
+        assert!(assert_canonical(&bytes).is_ok());
+    }
+}
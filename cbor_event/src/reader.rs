@@ -0,0 +1,213 @@
+//! Incremental reading of CBOR objects from a [`std::io::Read`]
+//!
+//! [`std::io::Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+
+use std::io::{self, Read};
+
+use de::{Deserialize, RawCbor};
+use error::Error;
+use result::Result;
+use types::Type;
+
+const BREAK : u8 = 0xff;
+
+/// maximum nesting of the arrays, maps and tags of an object, so a
+/// malicious stream cannot exhaust the stack
+const MAX_DEPTH : usize = 128;
+
+/// read CBOR objects one by one from a stream (a socket, a file...),
+/// without having to buffer the whole stream first.
+///
+/// Only the bytes of the object being read are kept in memory.
+///
+/// ```
+/// use cbor_event::{Reader};
+///
+/// let stream : &[u8] = &[0x01, 0x82, 0x02, 0x03];
+/// let mut reader = Reader::new(stream);
+///
+/// assert_eq!(reader.next_object::<u32>().unwrap(), Some(1));
+/// assert_eq!(reader.next_object::<Vec<u32>>().unwrap(), Some(vec![2, 3]));
+/// assert_eq!(reader.next_object::<u32>().unwrap(), None);
+/// ```
+pub struct Reader<R> {
+    inner: R,
+}
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R) -> Self { Reader { inner } }
+
+    pub fn get_ref(&self) -> &R { &self.inner }
+    pub fn get_mut(&mut self) -> &mut R { &mut self.inner }
+    pub fn into_inner(self) -> R { self.inner }
+
+    /// read the encoded bytes of the next CBOR object of the stream.
+    ///
+    /// Returns `None` if the stream ended before the object started, an
+    /// error if it ended in the middle of the object or if the object is
+    /// nested more than `MAX_DEPTH` levels deep.
+    pub fn next_raw(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut byte = [0u8;1];
+        loop {
+            match self.inner.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(Error::from(err)),
+            }
+        }
+        let mut bytes = Vec::new();
+        self.object(byte[0], &mut bytes, 0)?;
+        Ok(Some(bytes))
+    }
+
+    /// read and deserialise the next CBOR object of the stream, see
+    /// [`next_raw`](#method.next_raw).
+    pub fn next_object<T: Deserialize>(&mut self) -> Result<Option<T>> {
+        match self.next_raw()? {
+            None => Ok(None),
+            Some(bytes) => RawCbor::from(&bytes).deserialize().map(Some),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let mut byte = [0u8;1];
+        self.inner.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn bytes(&mut self, len: u64, out: &mut Vec<u8>) -> Result<()> {
+        let start = out.len();
+        (&mut self.inner).take(len).read_to_end(out)?;
+        let read = out.len() - start;
+        if (read as u64) < len {
+            return Err(Error::NotEnough(read, len as usize));
+        }
+        Ok(())
+    }
+
+    // the length argument of the object starting with the given byte,
+    // `None` for an indefinite length
+    fn len(&mut self, first: u8, out: &mut Vec<u8>) -> Result<Option<u64>> {
+        let sz = match first & 0b0001_1111 {
+            b @ 0x00..=0x17 => return Ok(Some(b as u64)),
+            0x18 => 1,
+            0x19 => 2,
+            0x1a => 4,
+            0x1b => 8,
+            0x1f => return Ok(None),
+            b    => return Err(Error::UnknownLenType(b)),
+        };
+        let start = out.len();
+        self.bytes(sz, out)?;
+        Ok(Some(out[start..].iter().fold(0, |acc, b| acc << 8 | *b as u64)))
+    }
+
+    // copy the object starting with the given byte, nested in `depth`
+    // arrays, maps or tags, into `out`
+    fn object(&mut self, first: u8, out: &mut Vec<u8>, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH { return Err(Error::TooDeep(MAX_DEPTH)); }
+        out.push(first);
+        let t = Type::from(first);
+        match (t, self.len(first, out)?) {
+            (Type::UnsignedInteger, Some(_)) | (Type::NegativeInteger, Some(_)) | (Type::Special, Some(_)) => Ok(()),
+            (Type::Bytes, Some(len)) | (Type::Text, Some(len)) => self.bytes(len, out),
+            (Type::Array, Some(len)) => self.objects(len, out, depth + 1),
+            (Type::Map, Some(len)) => self.objects(len * 2, out, depth + 1),
+            (Type::Tag, Some(_)) => self.objects(1, out, depth + 1),
+            // indefinite bytes and text are a sequence of chunks, as the
+            // indefinite collections their elements
+            (Type::Bytes, None) | (Type::Text, None) | (Type::Array, None) | (Type::Map, None) => {
+                loop {
+                    let byte = self.byte()?;
+                    if byte == BREAK {
+                        out.push(byte);
+                        return Ok(());
+                    }
+                    self.object(byte, out, depth + 1)?;
+                }
+            },
+            (t, None) => Err(Error::IndefiniteLenNotSupported(t)),
+        }
+    }
+
+    fn objects(&mut self, count: u64, out: &mut Vec<u8>, depth: usize) -> Result<()> {
+        for _ in 0..count {
+            let byte = self.byte()?;
+            self.object(byte, out, depth)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+    use se::Serializer;
+    use len::Len;
+
+    // a block-like object: `[0, [header hash, [indefinite transactions], {attributes}]]`
+    fn block(n: u8) -> Vec<u8> {
+        Serializer::new_vec()
+            .write_array(Len::Len(2)).unwrap()
+            .write_unsigned_integer(0).unwrap()
+            .write_array(Len::Len(3)).unwrap()
+            .write_bytes(&[n;32]).unwrap()
+            .write_array(Len::Indefinite).unwrap()
+                .write_tag(24).unwrap().write_bytes(&vec![n;300]).unwrap()
+                .write_negative_integer(-(n as i64) - 1).unwrap()
+                .write_text("tx").unwrap()
+            .write_special(::Special::Break).unwrap()
+            .write_map(Len::Len(1)).unwrap()
+                .write_unsigned_integer(n as u64 * 1000).unwrap()
+                .write_special(::Special::Bool(true)).unwrap()
+            .finalize()
+    }
+
+    #[test]
+    fn read_concatenated_blocks() {
+        let blocks : Vec<Vec<u8>> = (0..5).map(block).collect();
+        let mut reader = Reader::new(Cursor::new(blocks.concat()));
+
+        for expected in blocks.iter() {
+            assert_eq!(reader.next_raw().unwrap().as_ref(), Some(expected));
+        }
+        assert_eq!(reader.next_raw().unwrap(), None);
+    }
+
+    #[test]
+    fn read_truncated_block() {
+        let mut bytes = block(1);
+        bytes.extend_from_slice(&block(2)[..100]);
+        let mut reader = Reader::new(Cursor::new(bytes));
+
+        assert_eq!(reader.next_raw().unwrap(), Some(block(1)));
+        assert!(reader.next_raw().is_err());
+    }
+
+    #[test]
+    fn read_objects() {
+        let bytes = [0x9f, 0x01, 0x02, 0xff, 0x5f, 0x41, 0x01, 0x42, 0x02, 0x03, 0xff];
+        let mut reader = Reader::new(Cursor::new(&bytes[..]));
+
+        assert_eq!(reader.next_object::<Vec<u32>>().unwrap(), Some(vec![1, 2]));
+        assert_eq!(reader.next_raw().unwrap(), Some(bytes[4..].to_vec()));
+        assert!(reader.next_object::<u32>().unwrap().is_none());
+    }
+
+    #[test]
+    fn read_deeply_nested_object() {
+        // `[[...[0]...]]` nested up to the maximum depth is read
+        let mut bytes = vec![0x81; MAX_DEPTH];
+        bytes.push(0x00);
+        let mut reader = Reader::new(Cursor::new(bytes.clone()));
+        assert_eq!(reader.next_raw().unwrap(), Some(bytes));
+
+        // one level more is an error, whatever follows
+        let mut reader = Reader::new(Cursor::new(vec![0x81; 100_000]));
+        match reader.next_raw() {
+            Err(Error::TooDeep(MAX_DEPTH)) => {},
+            r => panic!("expected a too deep object, got {:?}", r),
+        }
+    }
+}
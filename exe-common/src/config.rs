@@ -1,12 +1,42 @@
 pub mod net {
     use cardano::block::{HeaderHash,EpochId};
     use cardano::config::{ProtocolMagic};
-    use std::{path::{Path}, fs::{self, File}, fmt, ops::{Deref, DerefMut}};
+    use std::{path::{Path}, fs::{self, File}, fmt, io, result, ops::{Deref, DerefMut}, time::{Duration}};
     use storage::tmpfile::{TmpFile};
     use serde_yaml;
     use serde;
 
     const DEFAULT_EPOCH_STABILITY_DEPTH : usize = 2160;
+    const DEFAULT_CONNECT_TIMEOUT_SECONDS : u64 = 30;
+    const DEFAULT_READ_TIMEOUT_SECONDS : u64 = 60;
+
+    /// error while loading a network configuration file
+    #[derive(Debug)]
+    pub enum Error {
+        IoError(io::Error),
+        /// the file is not valid YAML or does not have the expected structure
+        YamlError(serde_yaml::Error),
+        /// a mandatory field is not present in the configuration file
+        MissingField(&'static str),
+        /// the given field has an invalid value, with the reason why
+        InvalidField(&'static str, String),
+    }
+    impl From<io::Error> for Error {
+        fn from(e: io::Error) -> Self { Error::IoError(e) }
+    }
+    impl From<serde_yaml::Error> for Error {
+        fn from(e: serde_yaml::Error) -> Self { Error::YamlError(e) }
+    }
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Error::IoError(err) => write!(f, "I/O error: {}", err),
+                Error::YamlError(err) => write!(f, "invalid configuration file: {}", err),
+                Error::MissingField(field) => write!(f, "missing field `{}'", field),
+                Error::InvalidField(field, reason) => write!(f, "invalid field `{}': {}", field, reason),
+            }
+        }
+    }
 
 
     /// A blockchain may have multiple Peer of different kind. Here we define the list
@@ -190,6 +220,43 @@ pub mod net {
         { Peers(::std::iter::FromIterator::from_iter(iter)) }
     }
 
+    /// timeouts applied to the connections to the native peers
+    ///
+    /// Without a read timeout a dead peer would keep us waiting for
+    /// its answer indefinitely.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use exe_common::config::net::{Timeouts};
+    /// use std::time::Duration;
+    ///
+    /// let timeouts = Timeouts::default();
+    /// assert_eq!(timeouts.connect_duration(), Duration::from_secs(30));
+    /// assert_eq!(timeouts.read_duration(), Some(Duration::from_secs(60)));
+    /// ```
+    ///
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Timeouts {
+        /// number of seconds to wait for the connection to be established
+        pub connect: u64,
+        /// number of seconds to wait for data from the peer,
+        /// `None` means waiting indefinitely.
+        pub read: Option<u64>,
+    }
+    impl Timeouts {
+        pub fn connect_duration(&self) -> Duration { Duration::from_secs(self.connect) }
+        pub fn read_duration(&self) -> Option<Duration> { self.read.map(Duration::from_secs) }
+    }
+    impl Default for Timeouts {
+        fn default() -> Self {
+            Timeouts {
+                connect: DEFAULT_CONNECT_TIMEOUT_SECONDS,
+                read: Some(DEFAULT_READ_TIMEOUT_SECONDS),
+            }
+        }
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Config {
         pub genesis: HeaderHash,
@@ -197,7 +264,11 @@ pub mod net {
         pub epoch_stability_depth: usize,
         pub protocol_magic: ProtocolMagic,
         pub epoch_start: EpochId,
-        pub peers: Peers
+        pub peers: Peers,
+        // the field is optional so configuration files written before
+        // it was introduced are still loading
+        #[serde(default)]
+        pub timeouts: Timeouts,
     }
     impl Config {
         pub fn mainnet() -> Self {
@@ -209,8 +280,9 @@ pub mod net {
                 genesis_prev: HeaderHash::from_hex(&"5f20df933584822601f9e3f8c024eb5eb252fe8cefb24d1317dc3d432e940ebb").unwrap(),
                 epoch_stability_depth: DEFAULT_EPOCH_STABILITY_DEPTH,
                 protocol_magic: ProtocolMagic::default(),
-                epoch_start: 0,
-                peers: peers
+                epoch_start: EpochId::new(0),
+                peers: peers,
+                timeouts: Timeouts::default(),
             }
         }
 
@@ -223,19 +295,66 @@ pub mod net {
                 genesis_prev: HeaderHash::from_hex(&"c6a004d3d178f600cd8caa10abbebe1549bef878f0665aea2903472d5abf7323").unwrap(),
                 epoch_stability_depth: DEFAULT_EPOCH_STABILITY_DEPTH,
                 protocol_magic: ProtocolMagic::new(633343913),
-                epoch_start: 0,
-                peers: peers
+                epoch_start: EpochId::new(0),
+                peers: peers,
+                timeouts: Timeouts::default(),
             }
         }
 
-        pub fn from_file<P: AsRef<Path>>(p: P) -> Option<Self> {
-            let path = p.as_ref();
-            if ! path.is_file() {
-                return None;
+        /// configuration of a custom network, for example a private
+        /// testnet or a local cluster.
+        ///
+        /// There is no default peer for such network, they need to be
+        /// added to the configuration afterward.
+        ///
+        pub fn custom(genesis: HeaderHash, genesis_prev: HeaderHash, protocol_magic: ProtocolMagic, epoch_start: EpochId) -> Self {
+            Config {
+                genesis: genesis,
+                genesis_prev: genesis_prev,
+                epoch_stability_depth: DEFAULT_EPOCH_STABILITY_DEPTH,
+                protocol_magic: protocol_magic,
+                epoch_start: epoch_start,
+                peers: Peers::new(),
+                timeouts: Timeouts::default(),
             }
+        }
 
-            let mut file = File::open(path).unwrap();
-            serde_yaml::from_reader(&mut file).unwrap()
+        /// start building a custom configuration field by field, see
+        /// `ConfigBuilder`.
+        pub fn builder() -> ConfigBuilder { ConfigBuilder::default() }
+
+        /// load the configuration from the given file
+        ///
+        /// the hashes and the protocol magic are checked so a corrupted
+        /// file is reported here rather than failing later on.
+        pub fn from_file<P: AsRef<Path>>(p: P) -> result::Result<Self, Error> {
+            let mut file = File::open(p.as_ref())?;
+            let value : serde_yaml::Value = serde_yaml::from_reader(&mut file)?;
+            Self::check_fields(&value)?;
+            Ok(serde_yaml::from_value(value)?)
+        }
+
+        fn check_fields(value: &serde_yaml::Value) -> result::Result<(), Error> {
+            for field in ["genesis", "genesis_prev"].iter() {
+                match value.get(field) {
+                    None => return Err(Error::MissingField(field)),
+                    Some(v) => match v.as_str() {
+                        None => return Err(Error::InvalidField(field, "expected an hexadecimal string".to_owned())),
+                        Some(hex) => {
+                            if let Err(err) = HeaderHash::from_hex(&hex) {
+                                return Err(Error::InvalidField(field, format!("{}", err)));
+                            }
+                        }
+                    }
+                }
+            }
+            match value.get("protocol_magic") {
+                None => Err(Error::MissingField("protocol_magic")),
+                Some(v) => match v.as_u64() {
+                    Some(magic) if magic <= ::std::u32::MAX as u64 => Ok(()),
+                    _ => Err(Error::InvalidField("protocol_magic", "expected a 32 bits unsigned integer".to_owned())),
+                }
+            }
         }
         pub fn to_file<P: AsRef<Path>>(&self, p: P) {
             let dir = p.as_ref().parent().unwrap().to_path_buf();
@@ -245,4 +364,213 @@ pub mod net {
             file.render_permanent(&p.as_ref().to_path_buf()).unwrap();
         }
     }
+
+    /// builder of a custom network configuration
+    ///
+    /// The genesis hashes and the protocol magic have to be set, the
+    /// other fields default to the same values as `Config::custom`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exe_common;
+    /// # extern crate cardano;
+    /// use exe_common::config::net::{Config, Peer};
+    /// use cardano::{block::{HeaderHash, EpochId}, config::{ProtocolMagic}};
+    ///
+    /// let config = Config::builder()
+    ///     .genesis(HeaderHash::new(b"genesis"))
+    ///     .genesis_prev(HeaderHash::new(b"genesis prev"))
+    ///     .protocol_magic(ProtocolMagic::new(42))
+    ///     .epoch_start(EpochId::new(1))
+    ///     .peer("local", Peer::new("127.0.0.1:3000".to_string()))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.peers.natives(), vec!["127.0.0.1:3000"]);
+    ///
+    /// assert!(Config::builder().protocol_magic(ProtocolMagic::new(42)).build().is_err());
+    /// ```
+    ///
+    #[derive(Debug, Clone, Default)]
+    pub struct ConfigBuilder {
+        genesis: Option<HeaderHash>,
+        genesis_prev: Option<HeaderHash>,
+        epoch_stability_depth: Option<usize>,
+        protocol_magic: Option<ProtocolMagic>,
+        epoch_start: Option<EpochId>,
+        peers: Vec<NamedPeer>,
+        timeouts: Option<Timeouts>,
+    }
+    impl ConfigBuilder {
+        pub fn genesis(mut self, genesis: HeaderHash) -> Self { self.genesis = Some(genesis); self }
+        pub fn genesis_prev(mut self, genesis_prev: HeaderHash) -> Self { self.genesis_prev = Some(genesis_prev); self }
+        pub fn epoch_stability_depth(mut self, depth: usize) -> Self { self.epoch_stability_depth = Some(depth); self }
+        pub fn protocol_magic(mut self, protocol_magic: ProtocolMagic) -> Self { self.protocol_magic = Some(protocol_magic); self }
+        pub fn epoch_start(mut self, epoch_start: EpochId) -> Self { self.epoch_start = Some(epoch_start); self }
+        pub fn timeouts(mut self, timeouts: Timeouts) -> Self { self.timeouts = Some(timeouts); self }
+        /// add a peer to connect to, the peers are kept in the given order
+        pub fn peer(mut self, name: &str, peer: Peer) -> Self { self.peers.push(NamedPeer::new(name.to_owned(), peer)); self }
+
+        /// check the fields and build the configuration
+        pub fn build(self) -> result::Result<Config, Error> {
+            let genesis = self.genesis.ok_or(Error::MissingField("genesis"))?;
+            let genesis_prev = self.genesis_prev.ok_or(Error::MissingField("genesis_prev"))?;
+            let protocol_magic = self.protocol_magic.ok_or(Error::MissingField("protocol_magic"))?;
+            if genesis == genesis_prev {
+                return Err(Error::InvalidField("genesis_prev", "the genesis cannot be its own parent".to_owned()));
+            }
+            let epoch_stability_depth = self.epoch_stability_depth.unwrap_or(DEFAULT_EPOCH_STABILITY_DEPTH);
+            if epoch_stability_depth == 0 {
+                return Err(Error::InvalidField("epoch_stability_depth", "expected a non zero depth".to_owned()));
+            }
+            let mut names = ::std::collections::BTreeSet::new();
+            for peer in self.peers.iter() {
+                if ! names.insert(peer.name()) {
+                    return Err(Error::InvalidField("peers", format!("peer `{}' is declared more than once", peer.name())));
+                }
+            }
+            Ok(Config {
+                genesis: genesis,
+                genesis_prev: genesis_prev,
+                epoch_stability_depth: epoch_stability_depth,
+                protocol_magic: protocol_magic,
+                epoch_start: self.epoch_start.unwrap_or(EpochId::new(0)),
+                peers: self.peers.into_iter().collect(),
+                timeouts: self.timeouts.unwrap_or_default(),
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use std::env;
+        use rand;
+
+        #[test]
+        fn custom_config_to_file_from_file() {
+            let genesis = HeaderHash::from_hex(&"0b1c5ff1d8e3e24ff2e94b3cc9c0f55ec0e9cc4735fa63bdcb1c7ac81e4a1f02").unwrap();
+            let genesis_prev = HeaderHash::from_hex(&"c4e0fc3a4ffb3191f88b26a9834453cbac0e6b9c8d8f7ae810696bee575d1d22").unwrap();
+            let config = Config::custom(genesis.clone(), genesis_prev.clone(), ProtocolMagic::new(42), EpochId::new(3));
+
+            let dir = env::temp_dir().join(format!("exe-common-test-{}", rand::random::<u64>()));
+            let file = dir.join("config.yml");
+            config.to_file(&file);
+            let loaded = Config::from_file(&file).expect("config file written");
+            fs::remove_dir_all(&dir).unwrap();
+
+            assert_eq!(loaded.genesis, genesis);
+            assert_eq!(loaded.genesis_prev, genesis_prev);
+            assert_eq!(loaded.protocol_magic, ProtocolMagic::new(42));
+            assert_eq!(loaded.epoch_start, EpochId::new(3));
+            assert_eq!(loaded.epoch_stability_depth, DEFAULT_EPOCH_STABILITY_DEPTH);
+            assert_eq!(loaded.timeouts, Timeouts::default());
+            assert!(loaded.peers.is_empty());
+        }
+
+        fn write_config(content: &str) -> ::std::path::PathBuf {
+            let dir = env::temp_dir().join(format!("exe-common-test-{}", rand::random::<u64>()));
+            fs::create_dir_all(&dir).unwrap();
+            let file = dir.join("config.yml");
+            fs::write(&file, content).unwrap();
+            file
+        }
+
+        fn load(content: &str) -> result::Result<Config, Error> {
+            let file = write_config(content);
+            let result = Config::from_file(&file);
+            fs::remove_dir_all(file.parent().unwrap()).unwrap();
+            result
+        }
+
+        const GENESIS : &'static str = "89d9b5a5b8ddc8d7e5a6795e9774d97faf1efea59b2caf7eaf9f8c5b32059df4";
+        const GENESIS_PREV : &'static str = "5f20df933584822601f9e3f8c024eb5eb252fe8cefb24d1317dc3d432e940ebb";
+
+        #[test]
+        fn config_with_truncated_genesis() {
+            let content = format!("genesis: {}\ngenesis_prev: {}\nepoch_stability_depth: 2160\nprotocol_magic: 764824073\nepoch_start: 0\npeers: []\n", &GENESIS[..40], GENESIS_PREV);
+            match load(&content) {
+                Err(Error::InvalidField("genesis", _)) => {},
+                result => panic!("expected an invalid genesis, got {:?}", result),
+            }
+        }
+
+        #[test]
+        fn config_with_missing_magic() {
+            let content = format!("genesis: {}\ngenesis_prev: {}\nepoch_stability_depth: 2160\nepoch_start: 0\npeers: []\n", GENESIS, GENESIS_PREV);
+            match load(&content) {
+                Err(Error::MissingField("protocol_magic")) => {},
+                result => panic!("expected a missing protocol magic, got {:?}", result),
+            }
+        }
+
+        #[test]
+        fn config_mainnet_from_file() {
+            let mainnet = Config::mainnet();
+            let file = write_config(&serde_yaml::to_string(&mainnet).unwrap());
+            let loaded = Config::from_file(&file).unwrap();
+            fs::remove_dir_all(file.parent().unwrap()).unwrap();
+            assert_eq!(loaded.genesis, mainnet.genesis);
+            assert_eq!(loaded.protocol_magic, mainnet.protocol_magic);
+        }
+
+        #[test]
+        fn builder_all_fields() {
+            let timeouts = Timeouts { connect: 5, read: None };
+            let config = Config::builder()
+                .genesis(HeaderHash::from_hex(&GENESIS).unwrap())
+                .genesis_prev(HeaderHash::from_hex(&GENESIS_PREV).unwrap())
+                .epoch_stability_depth(10)
+                .protocol_magic(ProtocolMagic::new(1234))
+                .epoch_start(EpochId::new(2))
+                .peer("node-0", Peer::new("127.0.0.1:3000".to_string()))
+                .peer("hermes", Peer::new("http://127.0.0.1:8080/local".to_string()))
+                .timeouts(timeouts)
+                .build()
+                .unwrap();
+
+            let file = write_config(&serde_yaml::to_string(&config).unwrap());
+            let loaded = Config::from_file(&file).unwrap();
+            fs::remove_dir_all(file.parent().unwrap()).unwrap();
+
+            assert_eq!(loaded.genesis, HeaderHash::from_hex(&GENESIS).unwrap());
+            assert_eq!(loaded.genesis_prev, HeaderHash::from_hex(&GENESIS_PREV).unwrap());
+            assert_eq!(loaded.epoch_stability_depth, 10);
+            assert_eq!(loaded.protocol_magic, ProtocolMagic::new(1234));
+            assert_eq!(loaded.epoch_start, EpochId::new(2));
+            assert_eq!(loaded.peers.iter().map(|p| (p.name(), p.peer().clone())).collect::<Vec<_>>(),
+                       vec![ ("node-0", Peer::native("127.0.0.1:3000".to_string()))
+                           , ("hermes", Peer::http("http://127.0.0.1:8080/local".to_string()))
+                           ]);
+            assert_eq!(loaded.timeouts, timeouts);
+        }
+
+        #[test]
+        fn builder_checks_the_fields() {
+            let builder = Config::builder()
+                .genesis(HeaderHash::from_hex(&GENESIS).unwrap())
+                .protocol_magic(ProtocolMagic::new(1234));
+            match builder.clone().build() {
+                Err(Error::MissingField("genesis_prev")) => {},
+                result => panic!("expected a missing genesis_prev, got {:?}", result),
+            }
+            match builder.clone().genesis_prev(HeaderHash::from_hex(&GENESIS).unwrap()).build() {
+                Err(Error::InvalidField("genesis_prev", _)) => {},
+                result => panic!("expected an invalid genesis_prev, got {:?}", result),
+            }
+            let builder = builder.genesis_prev(HeaderHash::from_hex(&GENESIS_PREV).unwrap());
+            match builder.clone().epoch_stability_depth(0).build() {
+                Err(Error::InvalidField("epoch_stability_depth", _)) => {},
+                result => panic!("expected an invalid epoch_stability_depth, got {:?}", result),
+            }
+            let peer = Peer::new("127.0.0.1:3000".to_string());
+            match builder.clone().peer("node", peer.clone()).peer("node", peer).build() {
+                Err(Error::InvalidField("peers", _)) => {},
+                result => panic!("expected invalid peers, got {:?}", result),
+            }
+            let config = builder.build().unwrap();
+            assert_eq!(config.epoch_start, EpochId::new(0));
+            assert_eq!(config.epoch_stability_depth, DEFAULT_EPOCH_STABILITY_DEPTH);
+        }
+    }
 }
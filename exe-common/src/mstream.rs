@@ -5,6 +5,7 @@ use std::io;
 use std::fmt;
 
 use network::{Result, Error};
+use config::net::{Timeouts};
 
 pub struct MetricStart {
     bytes_start: u64,
@@ -56,13 +57,9 @@ pub struct MStream {
     write_sz: u64,
 }
 
-const TIMEOUT_SECONDS      : u64 = 30;
-const TIMEOUT_NANO_SECONDS : u32 = 0;
-
 impl MStream {
-    pub fn init(dest: &SocketAddr) -> Result<Self> {
-        let timeout = Duration::new(TIMEOUT_SECONDS, TIMEOUT_NANO_SECONDS);
-        let stream = match TcpStream::connect_timeout(dest, timeout) {
+    pub fn init(dest: &SocketAddr, timeouts: &Timeouts) -> Result<Self> {
+        let stream = match TcpStream::connect_timeout(dest, timeouts.connect_duration()) {
             Ok(stream) => stream,
             Err(ioerr) => {
                 return if ioerr.kind() == io::ErrorKind::TimedOut {
@@ -72,6 +69,7 @@ impl MStream {
                 }
             }
         };
+        stream.set_read_timeout(timeouts.read_duration())?;
         stream.set_nodelay(true)?;
         //let lock = RwLock::new(5);
         Ok(MStream {
@@ -93,7 +91,15 @@ impl MStream {
 
 impl Read for MStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let sz = self.stream.read(buf)?;
+        let sz = match self.stream.read(buf) {
+            Ok(sz) => sz,
+            // depending on the platform, reaching the read timeout is
+            // reported as `WouldBlock` or `TimedOut`.
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "read timed out"))
+            },
+            Err(err) => return Err(err),
+        };
         self.read_sz += sz as u64;
         Ok(sz)
     }
@@ -19,13 +19,24 @@ pub trait Api {
     /// inclusive = false) or [from, to] (if inclusive = true). FIXME:
     /// the inclusive = true case is only needed because the native
     /// protocol doesn't support fetching from the genesis_prev hash.
+    ///
+    /// No more blocks are passed to `got_block` once it returned
+    /// `Fetch::Stop`.
     fn get_blocks<F>( &mut self
                     , from: &BlockRef
                     , inclusive: bool
                     , to: &BlockRef
                     , got_block: &mut F
                     ) -> Result<()>
-        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> ();
+        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch;
+}
+
+/// returned by the `get_blocks` callback, whether the next blocks
+/// are wanted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fetch {
+    Continue,
+    Stop,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -2,6 +2,8 @@ use std::{io};
 use protocol::{self, ntt};
 use hyper;
 use cbor_event;
+use storage;
+use cardano::block::{HeaderHash, EpochId};
 
 #[derive(Debug)]
 pub enum Error {
@@ -11,20 +13,59 @@ pub enum Error {
     CborError(cbor_event::Error),
     HyperError(hyper::Error),
     ConnectionTimedOut,
+    /// the peer did not answer within the configured read timeout
+    Timeout,
     HttpError(String, hyper::StatusCode),
+    /// the peer does not have the requested block
+    BlockNotFound(HeaderHash),
+    /// the peer sent a block which is not the child of the previous one
+    UnexpectedBlock(HeaderHash),
+    /// the first epoch of the requested range is after the last one
+    InvalidEpochRange(EpochId, EpochId),
+    /// the hash the epoch starts from is not known: it is not the first
+    /// epoch of the chain and the previous epoch is not packed
+    UnknownEpochStart(EpochId),
+    /// the epoch is not stable yet, it cannot be packed
+    UnstableEpoch(EpochId),
+    /// the peer stopped sending blocks before the end of the epoch
+    IncompleteEpoch(EpochId),
+    /// the address of the native peer is not of the form `host[:port]`
+    InvalidAddress(String),
+    /// a received block could not be written to the storage
+    StorageError(storage::Error),
 }
+
+fn is_timeout(e: &io::Error) -> bool { e.kind() == io::ErrorKind::TimedOut }
+
 impl From<io::Error> for Error {
-    fn from(e: io::Error) -> Self { Error::IoError(e) }
+    fn from(e: io::Error) -> Self {
+        if is_timeout(&e) { Error::Timeout } else { Error::IoError(e) }
+    }
 }
 impl From<protocol::Error> for Error {
-    fn from(e: protocol::Error) -> Self { Error::ProtocolError(e) }
+    fn from(e: protocol::Error) -> Self {
+        match e {
+            protocol::Error::IOError(ref ioe) if is_timeout(ioe) => Error::Timeout,
+            protocol::Error::NttError(ntt::Error::IOError(ref ioe)) if is_timeout(ioe) => Error::Timeout,
+            protocol::Error::BlockNotFound(hash) => Error::BlockNotFound(hash),
+            e => Error::ProtocolError(e),
+        }
+    }
 }
 impl From<hyper::Error> for Error {
     fn from(e: hyper::Error) -> Self { Error::HyperError(e) }
 }
 impl From<ntt::Error> for Error {
-    fn from(e: ntt::Error) -> Self { Error::NttError(e) }
+    fn from(e: ntt::Error) -> Self {
+        match e {
+            ntt::Error::IOError(ref ioe) if is_timeout(ioe) => Error::Timeout,
+            e => Error::NttError(e),
+        }
+    }
 }
 impl From<cbor_event::Error> for Error {
     fn from(e: cbor_event::Error) -> Self { Error::CborError(e) }
 }
+impl From<storage::Error> for Error {
+    fn from(e: storage::Error) -> Self { Error::StorageError(e) }
+}
@@ -6,25 +6,27 @@ use std::time::{SystemTime, Duration};
 use std::thread;
 
 use futures::{Future, Stream};
-use hyper::Client;
+use hyper::{Client, StatusCode};
 use tokio_core::reactor::Core;
 
 use network::{Result, Error};
-use network::api::{Api, BlockRef};
+use network::api::{Api, BlockRef, Fetch};
 
 // Time between get_tip calls. FIXME: make configurable?
 static NETWORK_REFRESH_FREQUENCY: Duration = Duration::from_secs(60 * 10);
 
 /// hermes end point
+///
+/// a new event loop is created for every request, as it cannot be sent
+/// to another thread along the end point.
 pub struct HermesEndPoint {
     pub url: String,
     pub blockchain: String,
-    core: Core
 }
 
 impl HermesEndPoint {
     pub fn new(url: String, blockchain: String) -> Self {
-        HermesEndPoint { url, blockchain, core: Core::new().unwrap() }
+        HermesEndPoint { url, blockchain }
     }
 
     pub fn uri(& mut self, path: &str) -> String {
@@ -41,7 +43,8 @@ impl Api for HermesEndPoint {
 
         let mut bh_bytes = Vec::with_capacity(4096);
         {
-            let client = Client::new(&self.core.handle());
+            let mut core = Core::new()?;
+            let client = Client::new(&core.handle());
             let work = client.get(uri.parse().unwrap()).from_err::<Error>()
                 .and_then(|res| {
                 if !res.status().is_success() {
@@ -52,7 +55,7 @@ impl Api for HermesEndPoint {
                 })
             });
             let now = SystemTime::now();
-            self.core.run(work)?;
+            core.run(work)?;
             let time_elapsed = now.elapsed().unwrap();
             info!("Downloaded TIP in {}sec", time_elapsed.as_secs());
         }
@@ -76,12 +79,15 @@ impl Api for HermesEndPoint {
     fn get_block(&mut self, hash: &HeaderHash) -> Result<RawBlock> {
         let uri = self.uri(&format!("block/{}", hash));
         info!("querying uri: {}", uri);
-        let client = Client::new(&self.core.handle());
+        let mut core = Core::new()?;
+        let client = Client::new(&core.handle());
         let mut block_raw = vec!();
         let mut err = None;
         {
             let work = client.get(uri.parse().unwrap()).and_then(|res| {
-                if !res.status().is_success() {
+                if res.status() == StatusCode::NotFound {
+                    err = Some(Error::BlockNotFound(hash.clone()));
+                } else if !res.status().is_success() {
                     err = Some(Error::HttpError(uri, res.status().clone()));
                 };
                 res.body().for_each(|chunk| {
@@ -90,7 +96,7 @@ impl Api for HermesEndPoint {
                 })
             });
             let now = SystemTime::now();
-            self.core.run(work)?;
+            core.run(work)?;
             let time_elapsed = now.elapsed().unwrap();
             info!("Downloaded block in {}sec", time_elapsed.as_secs());
         }
@@ -104,7 +110,7 @@ impl Api for HermesEndPoint {
                     , to: &BlockRef
                     , got_block: &mut F
                     ) -> Result<()>
-        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> ()
+        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch
     {
         let mut inclusive = inclusive;
         let mut from = from.clone();
@@ -137,7 +143,8 @@ impl Api for HermesEndPoint {
                 {
                     let uri = self.uri(&format!("epoch/{}", epoch));
                     info!("querying uri: {}", uri);
-                    let client = Client::new(&self.core.handle());
+                    let mut core = Core::new()?;
+                    let client = Client::new(&core.handle());
                     let work = client.get(uri.parse().unwrap()).and_then(|res| {
                         if !res.status().is_success() {
                             err = Some(Error::HttpError(uri, res.status().clone()));
@@ -148,7 +155,7 @@ impl Api for HermesEndPoint {
                         })
                     });
                     let now = SystemTime::now();
-                    self.core.run(work)?;
+                    core.run(work)?;
                     let time_elapsed = now.elapsed().unwrap();
                     info!("Downloaded EPOCH in {}sec", time_elapsed.as_secs());
                 }
@@ -161,11 +168,13 @@ impl Api for HermesEndPoint {
                     let block = block_raw.decode()?;
                     let hdr = block.get_header();
 
-                    assert!(hdr.get_blockdate().get_epochid() == epoch);
+                    assert!(hdr.epoch() == epoch);
                     //assert!(from.date != hdr.get_blockdate() || from.hash == hdr.compute_hash());
 
                     if from.date <= hdr.get_blockdate() {
-                        got_block(&hdr.compute_hash(), &block, &block_raw);
+                        if got_block(&hdr.compute_hash(), &block, &block_raw) == Fetch::Stop {
+                            return Ok(());
+                        }
                     }
 
                     from = BlockRef {
@@ -200,7 +209,7 @@ impl Api for HermesEndPoint {
                 }
 
                 while let Some((hash, block, block_raw)) = blocks.pop() {
-                    got_block(&hash, &block, &block_raw);
+                    if got_block(&hash, &block, &block_raw) == Fetch::Stop { break }
                 }
 
                 break;
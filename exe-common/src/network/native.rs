@@ -1,13 +1,55 @@
 use protocol;
 use mstream::{MStream, MetricStart, MetricStats};
 use cardano::{config::{ProtocolMagic}};
+use config::net::{Timeouts};
 use rand;
-use std::{net::{SocketAddr, ToSocketAddrs}, ops::{Deref, DerefMut}};
+use std::{io::{self, Read, Write}, net::{SocketAddr, ToSocketAddrs}, ops::{Deref, DerefMut}, time::{Duration, Instant}};
 use cardano::block::{Block, BlockHeader, RawBlock, HeaderHash};
 use protocol::command::*;
+use protocol::packet::{BlockHeaderResponse};
+use storage::{self, StorageConfig};
 
 use network::{Error, Result};
-use network::api::{Api, BlockRef};
+use network::api::{Api, BlockRef, Fetch};
+
+/// maximum number of headers requested at once, the next ones are
+/// requested once the blocks of these are received
+const MAX_HEADER_BATCH : usize = 2000;
+
+/// port of the native peers whose address does not have one
+pub const DEFAULT_PORT : u16 = 3000;
+
+/// split the `host[:port]` address of a native peer, the port being
+/// `DEFAULT_PORT` if omitted.
+///
+/// IPv6 hosts followed by a port are written between brackets: `[::1]:3000`.
+pub fn parse_address(address: &str) -> Result<(String, u16)> {
+    let invalid = || Error::InvalidAddress(address.to_owned());
+    let (host, port) = if address.starts_with('[') {
+        let end = address.find(']').ok_or_else(invalid)?;
+        match &address[end + 1..] {
+            "" => (&address[1..end], None),
+            rest if rest.starts_with(':') => (&address[1..end], Some(&rest[1..])),
+            _ => return Err(invalid()),
+        }
+    } else if address.matches(':').count() > 1 {
+        (address, None)
+    } else {
+        let mut parts = address.splitn(2, ':');
+        (parts.next().unwrap(), parts.next())
+    };
+    if host.is_empty() { return Err(invalid()) }
+    let port = match port {
+        None => DEFAULT_PORT,
+        Some(port) => port.parse().map_err(|_| invalid())?,
+    };
+    Ok((host.to_owned(), port))
+}
+
+fn socket_addrs(address: &str) -> Result<Vec<SocketAddr>> {
+    let (host, port) = parse_address(address)?;
+    Ok((host.as_str(), port).to_socket_addrs()?.collect())
+}
 
 /// native peer
 pub struct PeerPool {
@@ -23,10 +65,10 @@ pub struct PeerPool {
     pub connections: Vec<Connection>
 }
 impl PeerPool {
-    pub fn new(name: String, address: String, protocol_magic: ProtocolMagic) -> Result<Self> {
+    pub fn new(name: String, address: String, protocol_magic: ProtocolMagic, timeouts: &Timeouts) -> Result<Self> {
         let mut connections = Vec::new();
-        for sockaddr in address.to_socket_addrs()? {
-            match Connection::new(sockaddr, protocol_magic) {
+        for sockaddr in socket_addrs(&address)? {
+            match Connection::new(sockaddr, protocol_magic, timeouts) {
                 Ok(connection) => {
                     connections.push(connection);
                     break
@@ -42,6 +84,13 @@ impl PeerPool {
         }
         Ok(PeerPool { name, address, connections })
     }
+
+    /// see `OpenPeer::set_header_cache`
+    pub fn set_header_cache(&mut self, config: &StorageConfig) {
+        for connection in self.connections.iter_mut() {
+            connection.set_header_cache(config.clone())
+        }
+    }
 }
 
 // TODO: this is not necessarily what we want to do here,
@@ -76,7 +125,7 @@ impl Api for PeerPool {
                     , to: &BlockRef
                     , got_block: &mut F
                     ) -> Result<()>
-        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> ()
+        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch
     {
         match self.connections.get_mut(0) {
             None => panic!("We expect at lease one connection on any native peer"),
@@ -87,8 +136,8 @@ impl Api for PeerPool {
 
 pub struct Connection(pub SocketAddr, pub OpenPeer);
 impl Connection {
-    pub fn new(sockaddr: SocketAddr, protocol_magic: ProtocolMagic) -> Result<Self> {
-        let network = OpenPeer::new(protocol_magic, &sockaddr)?;
+    pub fn new(sockaddr: SocketAddr, protocol_magic: ProtocolMagic, timeouts: &Timeouts) -> Result<Self> {
+        let network = OpenPeer::new(protocol_magic, &sockaddr, timeouts)?;
         Ok(Connection (sockaddr, network))
     }
 }
@@ -100,25 +149,59 @@ impl DerefMut for Connection {
     fn deref_mut(&mut self) -> &mut Self::Target { & mut self.1 }
 }
 
-pub struct OpenPeer(pub protocol::Connection<MStream>);
+/// perform the handshakes with the peer on the other side of `stream`
+/// and subscribe to its tip updates.
+fn open<T: Read+Write>(protocol_magic: ProtocolMagic, stream: T) -> Result<protocol::Connection<T>> {
+    let drg_seed = rand::random();
+    let mut hs = protocol::packet::Handshake::default();
+    hs.protocol_magic = protocol_magic;
 
-impl OpenPeer {
-    pub fn new(protocol_magic: ProtocolMagic, host: &SocketAddr) -> Result<Self> {
-        let drg_seed = rand::random();
-        let mut hs = protocol::packet::Handshake::default();
-        hs.protocol_magic = protocol_magic;
+    let conn = protocol::ntt::Connection::handshake(drg_seed, stream)?;
+    let mut conne = protocol::Connection::new(conn);
+    conne.handshake(&hs)?;
 
-        let stream = MStream::init(host)?;
+    // FIXME: make it configurable whether we want to subscribe to
+    // receive tip updates.
+    conne.subscribe()?;
 
-        let conn = protocol::ntt::Connection::handshake(drg_seed, stream)?;
-        let mut conne = protocol::Connection::new(conn);
-        conne.handshake(&hs)?;
+    Ok(conne)
+}
 
-        // FIXME: make it configurable whether we want to subscribe to
-        // receive tip updates.
-        conne.subscribe()?;
+/// check the peer at the given address is reachable and accepts our
+/// handshake, without requesting anything else.
+///
+/// returns the time taken to connect, perform the handshake and subscribe.
+/// Every address the name resolves to is tried until one succeeds.
+pub fn ping(protocol_magic: ProtocolMagic, address: &str, timeouts: &Timeouts) -> Result<Duration> {
+    let mut last_error = None;
+    for sockaddr in socket_addrs(address)? {
+        let start = Instant::now();
+        match MStream::init(&sockaddr, timeouts).and_then(|stream| open(protocol_magic, stream)) {
+            Ok(_) => return Ok(start.elapsed()),
+            Err(err) => {
+                warn!("ping of {} ({}) failed: {:?}", address, sockaddr, err);
+                last_error = Some(err)
+            },
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        Error::from(io::Error::new(io::ErrorKind::AddrNotAvailable, format!("no address found for `{}'", address)))
+    }))
+}
+
+pub struct OpenPeer(pub protocol::Connection<MStream>, Option<StorageConfig>);
 
-        Ok(OpenPeer(conne))
+impl OpenPeer {
+    pub fn new(protocol_magic: ProtocolMagic, host: &SocketAddr, timeouts: &Timeouts) -> Result<Self> {
+        let stream = MStream::init(host, timeouts)?;
+        Ok(OpenPeer(open(protocol_magic, stream)?, None))
+    }
+
+    /// keep the stable headers received by `get_blocks` in the given
+    /// storage, so a download going through them again (after a restart
+    /// for example) does not request them again.
+    pub fn set_header_cache(&mut self, config: StorageConfig) {
+        self.1 = Some(config)
     }
 
     pub fn read_start(&self) -> MetricStart {
@@ -134,7 +217,7 @@ impl Api for OpenPeer {
 
         if let Some(prev_tip) = self.0.get_latest_tip() { return Ok(prev_tip) }
 
-        let block_headers_raw = GetBlockHeader::tip().execute(&mut self.0).expect("to get one header at least");
+        let block_headers_raw = GetBlockHeader::tip().execute(&mut self.0)?;
 
         let block_headers = block_headers_raw.decode()?;
 
@@ -157,8 +240,7 @@ impl Api for OpenPeer {
     }
 
     fn get_block(&mut self, hash: &HeaderHash) -> Result<RawBlock> {
-        let b = GetBlock::only(&hash).execute(&mut self.0)
-            .expect("to get one block at least");
+        let b = GetBlock::only(&hash).execute(&mut self.0)?;
 
         Ok(RawBlock::from_dat(b[0].as_ref().to_vec()))
     }
@@ -169,7 +251,7 @@ impl Api for OpenPeer {
                     , to: &BlockRef
                     , got_block: &mut F
                     ) -> Result<()>
-        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> ()
+        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch
     {
         let mut inclusive = inclusive;
         let mut from = from.clone();
@@ -185,71 +267,407 @@ impl Api for OpenPeer {
 
             if inclusive {
                 if from.date > to.date { break }
-                info!("  ### get headers [{}..{}]", from.hash, to.hash);
+                info!("  ### get headers [{}..{}]", from.hash.to_short_hex(), to.hash.to_short_hex());
             } else {
                 if from.date >= to.date { break }
-                info!("  ### get headers ({}..{}]", from.hash, to.hash);
+                info!("  ### get headers ({}..{}]", from.hash.to_short_hex(), to.hash.to_short_hex());
             }
             let metrics = self.read_start();
-            let block_headers_raw = GetBlockHeader::range(
-                &vec![from.hash.clone()], to.hash.clone())
-                .execute(&mut self.0).expect("to get one header at least");
+            let get_headers = GetBlockHeader::range(&vec![from.hash.clone()], to.hash.clone(), MAX_HEADER_BATCH);
+            let block_headers = {
+                let connection = &mut self.0;
+                get_headers_cached(self.1.as_ref(), &from, to, || {
+                    let headers = get_headers.cap(get_headers.execute(connection)?.decode()?);
+                    Ok(BlockHeaderResponse::Ok(headers).into_oldest_first().map_err(protocol::Error::ServerError)?)
+                })?
+            };
             let hdr_metrics = self.read_elapsed(&metrics);
-            let block_headers = block_headers_raw.decode()?;
             info!("  got {} headers  ( {} )", block_headers.len(), hdr_metrics);
 
-            assert!(!block_headers.is_empty());
+            // the peer does not know any block after `from`
+            if block_headers.is_empty() { return Err(Error::BlockNotFound(to.hash.clone())) }
 
             let start = 0;
             let end = block_headers.len() - 1;
 
-            info!("  start {} {} <- {}", block_headers[start].compute_hash(), block_headers[start].get_blockdate(), block_headers[start].get_previous_header());
-            info!("  end   {} {} <- {}", block_headers[end].compute_hash(), block_headers[end].get_blockdate(), block_headers[end].get_previous_header());
+            info!("  start {} {} <- {}", block_headers[start].compute_hash().to_short_hex(), block_headers[start].get_blockdate(), block_headers[start].get_previous_header().to_short_hex());
+            info!("  end   {} {} <- {}", block_headers[end].compute_hash().to_short_hex(), block_headers[end].get_blockdate(), block_headers[end].get_previous_header().to_short_hex());
 
-            // The server will return the oldest ~2000 blocks starting at
-            // 'from'. However, they're in reverse order. Thus the last
-            // element of 'block_headers' should have 'from' as its
-            // parent.
-            assert!(block_headers[end].get_previous_header() == from.hash);
+            // The server returns the oldest ~2000 blocks starting at
+            // 'from', oldest first once ordered. Thus the first element
+            // of 'block_headers' should have 'from' as its parent.
+            if block_headers[start].get_previous_header() != from.hash {
+                return Err(Error::UnexpectedBlock(block_headers[start].compute_hash()))
+            }
 
-            let start_hash = if inclusive { block_headers[end].get_previous_header() } else { block_headers[end].compute_hash() };
-            let end_hash = block_headers[start].compute_hash();
+            let start_hash = if inclusive { block_headers[start].get_previous_header() } else { block_headers[start].compute_hash() };
+            let end_hash = block_headers[end].compute_hash();
 
-            info!("  get blocks [{}..{}]", start_hash, end_hash);
+            info!("  get blocks [{}..{}]", start_hash.to_short_hex(), end_hash.to_short_hex());
 
             let metrics = self.read_start();
-            let blocks_raw = GetBlock::from(&start_hash, &end_hash)
-                .execute(&mut self.0)
-                .expect("to get one block at least");
+            let (count, fetch) = {
+                let blocks_raw = GetBlock::from(&start_hash, &end_hash).stream(&mut self.0)?;
+                stream_blocks(blocks_raw, &mut from, &mut inclusive, got_block)?
+            };
             let blocks_metrics = self.read_elapsed(&metrics);
-            info!("  got {} blocks  ( {} )", blocks_raw.len(), blocks_metrics);
+            info!("  got {} blocks  ( {} )", count, blocks_metrics);
+            if fetch == Fetch::Stop { break }
+        }
 
-            assert!(!blocks_raw.is_empty());
+        Ok(())
+    }
+}
+
+/// get the first headers of the range `(from..to]`, oldest first: from the
+/// cache if the header following `from` is in it, otherwise with `fetch`.
+///
+/// Only the headers of the epochs before the one preceding the epoch of
+/// `to` are added to the cache: the stability depth is less than an epoch,
+/// so they cannot be rolled back anymore.
+fn get_headers_cached<F>( cache: Option<&StorageConfig>
+                        , from: &BlockRef
+                        , to: &BlockRef
+                        , fetch: F
+                        ) -> Result<Vec<BlockHeader>>
+    where F: FnOnce() -> Result<Vec<BlockHeader>>
+{
+    let cache = match cache {
+        None => return fetch(),
+        Some(cache) => cache,
+    };
+    let headers = storage::cached_headers_after(cache, &from.hash, from.date.get_epochid(), &to.hash, MAX_HEADER_BATCH);
+    if ! headers.is_empty() {
+        debug!("{} headers after {} found in the cache", headers.len(), from.hash.to_short_hex());
+        return Ok(headers);
+    }
+    let headers = fetch()?;
+    for header in headers.iter().filter(|header| header.epoch() + 2 <= to.date.get_epochid()) {
+        if let Err(err) = storage::cache_header(cache, header) {
+            warn!("unable to cache the header {}: {:?}", header.compute_hash().to_short_hex(), err);
+        }
+    }
+    Ok(headers)
+}
+
+/// pass the blocks to `got_block` as they are received, checking each
+/// of them is the child of the previous one, and return the number of
+/// blocks passed to `got_block` and whether it asked to stop.
+///
+/// Only one block is held in memory at a time. `from` is updated to the
+/// last block passed to `got_block`. Once it asked to stop, the rest of
+/// the response is still read, so the connection can be used again.
+fn stream_blocks<I, F>( blocks_raw: I
+                      , from: &mut BlockRef
+                      , inclusive: &mut bool
+                      , got_block: &mut F
+                      ) -> Result<(usize, Fetch)>
+    where I: Iterator<Item = protocol::Result<RawBlock>>
+        , F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch
+{
+    let mut count = 0;
+    let mut blocks_raw = blocks_raw;
+    while let Some(block_raw) = blocks_raw.next() {
+        let block_raw = block_raw?;
+        let block = block_raw.decode()?;
+        let hdr = block.get_header();
+        let date = hdr.get_blockdate();
+        let blockhash = hdr.compute_hash();
+
+        //info!("  got block {} {} prev {}", blockhash, date, hdr.get_previous_header());
+
+        if !*inclusive && hdr.get_previous_header() != from.hash {
+            error!("previous header doesn't match: hash {} date {} got {} expected {}",
+                   blockhash, date, hdr.get_previous_header(), from.hash);
+            return Err(Error::UnexpectedBlock(blockhash))
+        }
 
-            for block_raw in blocks_raw.iter() {
-                let block = block_raw.decode()?;
-                let hdr = block.get_header();
-                let date = hdr.get_blockdate();
-                let blockhash = hdr.compute_hash();
+        let fetch = got_block(&blockhash, &block, &block_raw);
 
-                //info!("  got block {} {} prev {}", blockhash, date, hdr.get_previous_header());
+        *from = BlockRef {
+            hash: blockhash,
+            parent: hdr.get_previous_header(),
+            date: date
+        };
+        *inclusive = false;
+        count += 1;
 
-                if !inclusive && hdr.get_previous_header() != from.hash {
-                    panic!("previous header doesn't match: hash {} date {} got {} expected {}",
-                           blockhash, date, hdr.get_previous_header(), from.hash)
-                }
+        if fetch == Fetch::Stop {
+            for block_raw in blocks_raw { block_raw?; }
+            return Ok((count, Fetch::Stop));
+        }
+    }
+    Ok((count, Fetch::Continue))
+}
 
-                got_block(&hdr.compute_hash(), &block, &block_raw);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{net::TcpListener, thread, sync::mpsc, cell::Cell, cmp};
+    use cardano::block::{BlockDate, EpochId};
+    use cardano::testing::genesis_block;
+
+    #[test]
+    fn parse_address_default_port() {
+        assert_eq!(parse_address("relays.cardano-mainnet.iohk.io").unwrap(), ("relays.cardano-mainnet.iohk.io".to_owned(), DEFAULT_PORT));
+        assert_eq!(parse_address("::1").unwrap(), ("::1".to_owned(), DEFAULT_PORT));
+        assert_eq!(parse_address("[::1]").unwrap(), ("::1".to_owned(), DEFAULT_PORT));
+    }
 
-                from = BlockRef {
-                    hash: blockhash,
-                    parent: hdr.get_previous_header(),
-                    date: date
-                };
-                inclusive = false;
+    #[test]
+    fn parse_address_with_port() {
+        assert_eq!(parse_address("relays.cardano-mainnet.iohk.io:1234").unwrap(), ("relays.cardano-mainnet.iohk.io".to_owned(), 1234));
+        assert_eq!(parse_address("127.0.0.1:1234").unwrap(), ("127.0.0.1".to_owned(), 1234));
+        assert_eq!(parse_address("[::1]:1234").unwrap(), ("::1".to_owned(), 1234));
+    }
+
+    #[test]
+    fn parse_address_invalid() {
+        for address in &["host:abc", "host:", "host:70000", ":1234", "", "[::1", "[::1]1234"] {
+            match parse_address(address) {
+                Err(Error::InvalidAddress(ref invalid)) => assert_eq!(invalid, address),
+                other => panic!("expected an invalid address error for `{}', got {:?}", address, other),
             }
         }
+    }
 
-        Ok(())
+    #[test]
+    fn open_peer_times_out_on_silent_peer() {
+        // a peer accepting the connection but never answering
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (done, wait_done) = mpsc::channel::<()>();
+        let server = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let _ = wait_done.recv();
+            drop(socket)
+        });
+
+        let timeouts = Timeouts { connect: 1, read: Some(1) };
+        let start = Instant::now();
+        match OpenPeer::new(ProtocolMagic::default(), &addr, &timeouts) {
+            Err(Error::Timeout) => {},
+            Err(err) => panic!("expected a timeout error, got: {:?}", err),
+            Ok(_) => panic!("expected a timeout error"),
+        }
+        assert!(start.elapsed() < Duration::from_secs(3));
+
+        done.send(()).unwrap();
+        server.join().unwrap();
+    }
+
+    /// mock transport replaying the given answers of the peer and
+    /// recording what was sent to it.
+    struct MockTransport {
+        answers: io::Cursor<Vec<u8>>,
+        sent: Vec<u8>,
+    }
+    impl MockTransport {
+        fn new(answers: Vec<u8>) -> Self {
+            MockTransport { answers: io::Cursor::new(answers), sent: Vec::new() }
+        }
+    }
+    impl Read for MockTransport {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.answers.read(buf) }
+    }
+    impl Write for MockTransport {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.sent.write(buf) }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    fn push_u32(v: u32, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&[(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]);
+    }
+
+    // the peer accepts the transport handshake, opens its light
+    // connection and sends its handshake and node id on it
+    fn accepting_peer() -> Vec<u8> {
+        let light_id = protocol::ntt::LIGHT_ID_MIN;
+        let handshake = protocol::packet::send_handshake(&protocol::packet::Handshake::default());
+        let nodeid = [0x41, 0, 0, 0, 0, 0, 0, 0, 1];
+
+        let mut answers = Vec::new();
+        push_u32(0, &mut answers);
+        push_u32(protocol::ntt::protocol::ControlHeader::CreateNewConnection as u32, &mut answers);
+        push_u32(light_id, &mut answers);
+        push_u32(light_id, &mut answers);
+        push_u32(handshake.len() as u32, &mut answers);
+        answers.extend_from_slice(&handshake);
+        push_u32(light_id, &mut answers);
+        push_u32(nodeid.len() as u32, &mut answers);
+        answers.extend_from_slice(&nodeid);
+        answers
+    }
+
+    #[test]
+    fn open_accepted_handshake() {
+        let connection = open(ProtocolMagic::default(), MockTransport::new(accepting_peer())).unwrap();
+        let sent = &connection.get_backend().sent;
+        // the subscription is the last message sent
+        let (_, subscribe) = protocol::packet::send_msg_subscribe(false);
+        assert!(sent.ends_with(&subscribe));
+    }
+
+    #[test]
+    fn open_rejected_handshake() {
+        let mut answers = Vec::new();
+        push_u32(0xffffffff, &mut answers);
+        match open(ProtocolMagic::default(), MockTransport::new(answers)) {
+            Err(Error::NttError(protocol::ntt::Error::UnsupportedVersion)) => {},
+            Err(err) => panic!("expected an unsupported version error, got: {:?}", err),
+            Ok(_) => panic!("the handshake should have been rejected"),
+        }
+    }
+
+    #[test]
+    fn open_unexpected_handshake_answer() {
+        // the peer accepts the transport handshake but closes the socket
+        // instead of opening its light connection
+        let mut answers = Vec::new();
+        push_u32(0, &mut answers);
+        push_u32(protocol::ntt::protocol::ControlHeader::CloseSocket as u32, &mut answers);
+        push_u32(0, &mut answers);
+        match open(ProtocolMagic::default(), MockTransport::new(answers)) {
+            Err(Error::ProtocolError(protocol::Error::UnexpectedResponse)) => {},
+            Err(err) => panic!("expected an unexpected response error, got: {:?}", err),
+            Ok(_) => panic!("the handshake should have failed"),
+        }
+    }
+
+    /// mock transport receiving the blocks one at a time, counting how many
+    /// were received but not yet processed.
+    struct MockBlocks<'a> {
+        blocks: ::std::vec::IntoIter<RawBlock>,
+        buffered: &'a Cell<usize>,
+        peak_buffered: &'a Cell<usize>,
+    }
+    impl<'a> Iterator for MockBlocks<'a> {
+        type Item = protocol::Result<RawBlock>;
+        fn next(&mut self) -> Option<Self::Item> {
+            let block = self.blocks.next()?;
+            self.buffered.set(self.buffered.get() + 1);
+            self.peak_buffered.set(cmp::max(self.peak_buffered.get(), self.buffered.get()));
+            Some(Ok(block))
+        }
+    }
+
+    #[test]
+    fn stream_blocks_one_at_a_time() {
+        let mut blocks = vec![];
+        let mut prev = HeaderHash::new(b"genesis prev");
+        for epoch in 0..10 {
+            let block = genesis_block(epoch, 0, &prev);
+            prev = block.decode().unwrap().get_header().compute_hash();
+            blocks.push(block);
+        }
+        let first = blocks[0].decode().unwrap().get_header().get_previous_header();
+
+        let buffered = Cell::new(0);
+        let peak_buffered = Cell::new(0);
+        let transport = MockBlocks { blocks: blocks.into_iter(), buffered: &buffered, peak_buffered: &peak_buffered };
+
+        let mut from = BlockRef { hash: first, parent: HeaderHash::new(&[]), date: BlockDate::Genesis(EpochId::new(0)) };
+        let mut inclusive = false;
+        let mut written = vec![];
+        let (count, fetch) = stream_blocks(transport, &mut from, &mut inclusive, &mut |hash, _, _| {
+            // the block is written as soon as it is received
+            buffered.set(buffered.get() - 1);
+            written.push(hash.clone());
+            Fetch::Continue
+        }).unwrap();
+
+        assert_eq!(count, 10);
+        assert_eq!(fetch, Fetch::Continue);
+        assert_eq!(written.len(), 10);
+        assert_eq!(peak_buffered.get(), 1);
+        assert_eq!(from.hash, prev);
+        assert_eq!(from.date, BlockDate::Genesis(EpochId::new(9)));
+    }
+
+    #[test]
+    fn stream_blocks_checks_the_chain() {
+        let block0 = genesis_block(0, 0, &HeaderHash::new(b"genesis prev"));
+        let block1 = genesis_block(1, 0, &HeaderHash::new(b"not block 0"));
+        let hash0 = block0.decode().unwrap().get_header().compute_hash();
+        let hash1 = block1.decode().unwrap().get_header().compute_hash();
+        let mut from = BlockRef { hash: HeaderHash::new(b"genesis prev"), parent: HeaderHash::new(&[]), date: BlockDate::Genesis(EpochId::new(0)) };
+        let mut inclusive = false;
+        match stream_blocks(vec![Ok(block0), Ok(block1)].into_iter(), &mut from, &mut inclusive, &mut |_, _, _| Fetch::Continue) {
+            Err(Error::UnexpectedBlock(hash)) => assert_eq!(hash, hash1),
+            r => panic!("expected an unexpected block error, got {:?}", r),
+        }
+        // the blocks before the unexpected one were passed
+        assert_eq!(from.hash, hash0);
+    }
+
+    #[test]
+    fn stream_blocks_stops_and_reads_the_rest() {
+        let mut blocks = vec![];
+        let mut prev = HeaderHash::new(b"genesis prev");
+        for epoch in 0..5 {
+            let block = genesis_block(epoch, 0, &prev);
+            prev = block.decode().unwrap().get_header().compute_hash();
+            blocks.push(block);
+        }
+        let first = blocks[0].decode().unwrap().get_header().get_previous_header();
+        let second = blocks[1].decode().unwrap().get_header().compute_hash();
+
+        let buffered = Cell::new(0);
+        let peak_buffered = Cell::new(0);
+        let mut transport = MockBlocks { blocks: blocks.into_iter(), buffered: &buffered, peak_buffered: &peak_buffered };
+
+        let mut from = BlockRef { hash: first, parent: HeaderHash::new(&[]), date: BlockDate::Genesis(EpochId::new(0)) };
+        let mut inclusive = false;
+        let (count, fetch) = stream_blocks(&mut transport, &mut from, &mut inclusive, &mut |_, block, _| {
+            if block.get_header().get_blockdate() == BlockDate::Genesis(EpochId::new(1)) { Fetch::Stop } else { Fetch::Continue }
+        }).unwrap();
+
+        assert_eq!((count, fetch), (2, Fetch::Stop));
+        assert_eq!(from.hash, second);
+        // the whole response has been read
+        assert!(transport.next().is_none());
+        assert_eq!(buffered.get(), 5);
+    }
+
+    #[test]
+    fn get_headers_from_the_cache() {
+        use std::fs;
+
+        let config = storage::testing::temporary_storage().config;
+        let mut headers = vec![];
+        let mut prev = HeaderHash::new(b"genesis prev");
+        for epoch in 0..6 {
+            let header = genesis_block(epoch, 0, &prev).decode().unwrap().get_header();
+            prev = header.compute_hash();
+            headers.push(header);
+        }
+        let block_ref = |header: &BlockHeader| BlockRef {
+            hash: header.compute_hash(),
+            parent: header.get_previous_header(),
+            date: header.get_blockdate(),
+        };
+        let hashes = |headers: &[BlockHeader]| -> Vec<HeaderHash> {
+            headers.iter().map(|header| header.compute_hash()).collect()
+        };
+        let genesis_prev = BlockRef { hash: HeaderHash::new(b"genesis prev"), parent: HeaderHash::new(&[]), date: BlockDate::Genesis(EpochId::new(0)) };
+        let tip = block_ref(&headers[5]);
+
+        let got = get_headers_cached(Some(&config), &genesis_prev, &tip, || Ok(headers.clone())).unwrap();
+        assert_eq!(hashes(&got), hashes(&headers));
+
+        // after a restart the tip has moved, and the download resumes from
+        // a block of the epoch 1: the stable headers are in the cache
+        let new_tip = BlockRef { hash: HeaderHash::new(b"new tip"), parent: tip.hash.clone(), date: BlockDate::Genesis(EpochId::new(6)) };
+        let got = get_headers_cached(Some(&config), &block_ref(&headers[1]), &new_tip, || panic!("network should not be used")).unwrap();
+        assert_eq!(hashes(&got), hashes(&headers[2..4]));
+
+        // the headers of the epochs 4 and 5 could still be rolled back
+        // when they were received, they are not in the cache
+        match get_headers_cached(Some(&config), &block_ref(&headers[3]), &new_tip, || Err(Error::ConnectionTimedOut)) {
+            Err(Error::ConnectionTimedOut) => {},
+            r => panic!("expected a connection error, got {:?}", r),
+        }
+
+        fs::remove_dir_all(config.get_path()).unwrap();
     }
 }
@@ -3,6 +3,7 @@ use network::{native, Result, hermes};
 use network::api::{*, BlockRef};
 use cardano::config::{ProtocolMagic};
 use cardano::block::{Block, BlockHeader, RawBlock, HeaderHash};
+use storage::{StorageConfig};
 
 /// network object to handle a peer connection and redirect to constructing
 /// the appropriate network protocol object (native, http...)
@@ -12,15 +13,30 @@ pub enum Peer {
 }
 impl Peer {
     pub fn new(network: String, name: String, cfg: config::net::Peer, protocol_magic: ProtocolMagic) -> Result<Self> {
+        Peer::new_with_timeouts(network, name, cfg, protocol_magic, &config::net::Timeouts::default())
+    }
+
+    /// same as `new` but with the given timeouts applied to the native connections
+    pub fn new_with_timeouts(network: String, name: String, cfg: config::net::Peer, protocol_magic: ProtocolMagic, timeouts: &config::net::Timeouts) -> Result<Self> {
         match cfg {
             config::net::Peer::Native(addr) => {
-                Ok(Peer::Native(native::PeerPool::new(name, addr, protocol_magic)?))
+                Ok(Peer::Native(native::PeerPool::new(name, addr, protocol_magic, timeouts)?))
             },
             config::net::Peer::Http(addr) => {
                 Ok(Peer::Http(hermes::HermesEndPoint::new(addr, network)))
             }
         }
     }
+
+    /// keep the headers received from a native peer in the given storage,
+    /// see `native::OpenPeer::set_header_cache`. The HTTP peers serve
+    /// whole epochs and do not request headers.
+    pub fn set_header_cache(&mut self, config: &StorageConfig) {
+        match self {
+            Peer::Native(peer) => peer.set_header_cache(config),
+            Peer::Http(_)      => {},
+        }
+    }
 }
 impl Api for Peer {
     fn get_tip(&mut self) -> Result<BlockHeader> {
@@ -50,7 +66,7 @@ impl Api for Peer {
                     , to: &BlockRef
                     , got_block: &mut F
                     ) -> Result<()>
-        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> ()
+        where F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch
     {
         match self {
             Peer::Native(peer)   => peer.get_blocks(from, inclusive, to, got_block),
@@ -58,3 +74,18 @@ impl Api for Peer {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_send<T: Send>() {}
+
+    // the peers are moved to the threads downloading in parallel
+    #[test]
+    fn peers_are_send() {
+        assert_send::<native::PeerPool>();
+        assert_send::<hermes::HermesEndPoint>();
+        assert_send::<Peer>();
+    }
+}
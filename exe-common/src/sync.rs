@@ -1,9 +1,10 @@
 use config::net;
-use network::{Peer, api::Api, api::BlockRef, Result};
+use network::{self, Peer, api::Api, api::BlockRef, api::Fetch, Result};
 use storage::{self, tag, Error, block_read};
-use cardano::block::{BlockDate, EpochId, HeaderHash, BlockHeader};
+use cardano::block::{BlockDate, EpochId, SlotId, HeaderHash, BlockHeader, RawBlock};
 use cardano::util::{hex};
 use std::time::{SystemTime, Duration};
+use std::{mem, result};
 
 fn duration_print(d: Duration) -> String {
     format!("{}.{:03} seconds", d.as_secs(), d.subsec_millis())
@@ -16,12 +17,17 @@ struct EpochWriterState {
     blobs_to_delete: Vec<HeaderHash>,
 }
 
-fn net_sync_to<A: Api>(
-    net: &mut A,
+// the network's tip, the genesis of the chain and which epochs are stable
+struct SyncStart {
+    tip: BlockRef,
+    genesis_ref: (BlockRef, bool),
+    first_unstable_epoch: EpochId,
+}
+
+fn sync_start(
     net_cfg: &net::Config,
-    storage: &storage::Storage,
     tip_header: &BlockHeader)
-    -> Result<()>
+    -> SyncStart
 {
     let tip = BlockRef {
         hash: tip_header.compute_hash(),
@@ -31,17 +37,36 @@ fn net_sync_to<A: Api>(
 
     debug!("Configured genesis   : {}", net_cfg.genesis);
     debug!("Configured genesis-1 : {}", net_cfg.genesis_prev);
-    info!( "Network TIP is       : {} ({}) <- {}", tip.hash, tip.date, tip_header.get_previous_header());
+    info!( "Network TIP is       : {} ({}) <- {}", tip.hash.to_short_hex(), tip.date, tip_header.get_previous_header().to_short_hex());
 
-    // Start fetching at the current HEAD tag, or the genesis block if
-    // it doesn't exist.
     let genesis_ref = (BlockRef {
         hash: net_cfg.genesis.clone(),
         parent: net_cfg.genesis_prev.clone(),
         date: BlockDate::Genesis(net_cfg.epoch_start)
     }, true);
 
-    let our_tip = match storage.get_block_from_tag(&tag::HEAD) {
+    // Determine whether the previous epoch is stable yet. Note: This
+    // assumes that k is smaller than the number of blocks in an
+    // epoch.
+    let first_unstable_epoch = tip.date.get_epochid() -
+        match tip.date {
+            BlockDate::Genesis(_) => 1,
+            BlockDate::Normal(d) => if d.slotid as usize <= net_cfg.epoch_stability_depth { 1 } else { 0 }
+        };
+    info!("First unstable epoch : {}", first_unstable_epoch);
+
+    SyncStart { tip, genesis_ref, first_unstable_epoch }
+}
+
+// Start fetching at the `HEAD` tag of the network, or the genesis block
+// if it doesn't exist.
+fn our_tip(
+    network: &str,
+    storage: &storage::Storage,
+    genesis_ref: &(BlockRef, bool))
+    -> (BlockRef, bool)
+{
+    let our_tip = match storage.get_block_from_tag(&tag::get_network_head_tag(network)) {
         Err(Error::NoSuchTag) => genesis_ref.clone(),
         Err(err) => panic!(err),
         Ok(block) => {
@@ -58,21 +83,24 @@ fn net_sync_to<A: Api>(
     // ancestor of tip. In that case we should start from the last
     // stable epoch before our_tip.
 
-    info!("Fetching from        : {} ({})", our_tip.0.hash, our_tip.0.date);
+    info!("Fetching from        : {} ({})", our_tip.0.hash.to_short_hex(), our_tip.0.date);
 
-    // Determine whether the previous epoch is stable yet. Note: This
-    // assumes that k is smaller than the number of blocks in an
-    // epoch.
-    let first_unstable_epoch = tip.date.get_epochid() -
-        match tip.date {
-            BlockDate::Genesis(_) => 1,
-            BlockDate::Normal(d) => if d.slotid as usize <= net_cfg.epoch_stability_depth { 1 } else { 0 }
-        };
-    info!("First unstable epoch : {}", first_unstable_epoch);
+    our_tip
+}
 
-    let mut epoch_writer_state : Option<EpochWriterState> = None;
+fn net_sync_to<A: Api, W: SyncWriter>(
+    net: &mut A,
+    net_cfg: &net::Config,
+    network: &str,
+    storage: &storage::Storage,
+    writer: &mut W,
+    tip_header: &BlockHeader)
+    -> Result<usize>
+{
+    let SyncStart { tip, genesis_ref, first_unstable_epoch } = sync_start(net_cfg, tip_header);
+    let our_tip = our_tip(network, storage, &genesis_ref);
 
-    let mut last_block : Option<HeaderHash> = None;
+    let mut last_block : Option<(HeaderHash, BlockHeader)> = None;
 
     // If our tip is in an epoch that has become stable, we now need
     // to pack it. So read the previously fetched blocks in this epoch
@@ -82,21 +110,20 @@ fn net_sync_to<A: Api>(
     {
         let epoch_id = our_tip.0.date.get_epochid();
 
-        epoch_writer_state = Some(EpochWriterState {
-            epoch_id,
-            writer: storage::pack::PackWriter::init(&storage.config),
-            write_start_time: SystemTime::now(),
-            blobs_to_delete: vec![]
-        });
-        last_block = Some(our_tip.0.hash.clone());
+        let our_tip_header = block_read(&storage, our_tip.0.hash.bytes()).unwrap().decode().unwrap().get_header();
+        last_block = Some((our_tip.0.hash.clone(), our_tip_header));
 
-        let prev_block = append_blocks_to_epoch_reverse(
-            storage, epoch_writer_state.as_mut().unwrap(), &our_tip.0.hash);
+        let (blocks, prev_block) = read_epoch_blobs(storage, epoch_id, &our_tip.0.hash);
 
         // If tip.slotid < w, the previous epoch won't have been
         // created yet either, so do that now.
         if epoch_id > net_cfg.epoch_start {
-            maybe_create_epoch(storage, epoch_id - 1, &prev_block);
+            maybe_create_epoch(storage, writer, epoch_id - 1, &prev_block);
+        }
+
+        writer.start_epoch(epoch_id);
+        for (hash, block_raw) in blocks {
+            writer.append_block(&hash, &block_raw, true);
         }
     }
 
@@ -107,75 +134,184 @@ fn net_sync_to<A: Api>(
         && !epoch_exists(storage, first_unstable_epoch - 1)
     {
         // Iterate to the last block in the previous epoch.
-        let mut cur_hash = our_tip.0.hash.clone();
-        loop {
-            let block_raw = block_read(&storage, cur_hash.bytes()).unwrap();
-            let block = block_raw.decode().unwrap();
-            let hdr = block.get_header();
-            assert!(hdr.get_blockdate().get_epochid() == first_unstable_epoch);
-            cur_hash = hdr.get_previous_header();
-            if hdr.get_blockdate().is_genesis() { break }
-        }
+        let (_, prev_block) = read_epoch_blobs(storage, first_unstable_epoch, &our_tip.0.hash);
 
-        maybe_create_epoch(storage, first_unstable_epoch - 1, &cur_hash);
+        maybe_create_epoch(storage, writer, first_unstable_epoch - 1, &prev_block);
     }
 
+    let mut received = 0;
+    let mut expected_parent = if our_tip.1 { our_tip.0.parent.clone() } else { our_tip.0.hash.clone() };
+    let mut unexpected_block = None;
+    let mut last_slot : Option<SlotId> = None;
     net.get_blocks(&our_tip.0, our_tip.1, &tip, &mut |block_hash, block, block_raw| {
-        let date = block.get_header().get_blockdate();
+        let header = block.get_header();
+        if header.get_previous_header() != expected_parent {
+            unexpected_block = Some(block_hash.clone());
+            return Fetch::Stop;
+        }
+        expected_parent = block_hash.clone();
+        received += 1;
 
-        // Flush the previous epoch (if any).
-        if date.is_genesis() {
-            if let Some(epoch_writer_state) = epoch_writer_state.as_mut() {
-                finish_epoch(storage, epoch_writer_state);
+        let date = header.get_blockdate();
 
-                // Checkpoint the tip so we don't have to refetch
-                // everything if we get interrupted.
-                storage::tag::write(storage, &tag::HEAD, &last_block.as_ref().unwrap().bytes()[..]);
+        // Slots without a block are expected (the slot leader may be
+        // offline), only report them.
+        if let BlockDate::Normal(ref slot) = date {
+            if let Some(ref last) = last_slot {
+                if !last.is_adjacent(slot) {
+                    debug!("no block between the slots {} and {}", last, slot);
+                }
             }
+            last_slot = Some(slot.clone());
+        }
+
+        // Flush the previous epoch (if any).
+        if date.is_genesis() && writer.finish_epoch() {
+            // Checkpoint the tip so we don't have to refetch
+            // everything if we get interrupted.
+            let (hash, header) = last_block.as_ref().unwrap();
+            advance_head(storage, writer, network, hash, header);
         }
 
         if date.get_epochid() >= first_unstable_epoch {
             // This block is not part of a stable epoch yet and could
             // be rolled back. Therefore we can't pack this epoch
             // yet. Instead we write this block to disk separately.
-            let block_hash = storage::types::header_to_blockhash(&block_hash);
-            storage::blob::write(storage, &block_hash, block_raw.as_ref()).unwrap();
+            writer.write_blob(block_hash, block_raw);
         } else {
 
             // If this is the epoch genesis block, start writing a new epoch pack.
             if date.is_genesis() {
-                epoch_writer_state = Some(EpochWriterState {
-                    epoch_id: date.get_epochid(),
-                    writer: storage::pack::PackWriter::init(&storage.config),
-                    write_start_time: SystemTime::now(),
-                    blobs_to_delete: vec![]
-                });
+                writer.start_epoch(date.get_epochid());
             }
 
             // And append the block to the epoch pack.
-            if let Some(epoch_writer_state) = epoch_writer_state.as_mut() {
-                epoch_writer_state.writer.append(
-                    &storage::types::header_to_blockhash(&block_hash), block_raw.as_ref());
-            }
+            writer.append_block(block_hash, block_raw, false);
         }
 
-        last_block = Some(block_hash.clone());
+        last_block = Some((block_hash.clone(), header));
+        Fetch::Continue
     })?;
 
     // Update the tip tag to point to the most recent block.
-    if let Some(block_hash) = last_block {
-        storage::tag::write(&storage, &tag::HEAD,
-                            &storage::types::header_to_blockhash(&block_hash));
+    if let Some((block_hash, header)) = last_block {
+        advance_head(storage, writer, network, &block_hash, &header);
     }
 
-    Ok(())
+    match unexpected_block {
+        None => Ok(received),
+        Some(hash) => Err(network::Error::UnexpectedBlock(hash)),
+    }
+}
+
+// where `net_sync_to` commits the synchronization: `StorageWriter` writes
+// to the storage, `DryRunWriter` only reports what would be written
+trait SyncWriter {
+    // start the pack of the given epoch
+    fn start_epoch(&mut self, epoch_id: EpochId);
+    // append a block to the pack of the current epoch, if any. `stored`
+    // if the block is a blob to remove once the epoch is packed
+    fn append_block(&mut self, hash: &HeaderHash, block_raw: &RawBlock, stored: bool);
+    // finish the pack of the current epoch, returns whether there was one
+    fn finish_epoch(&mut self) -> bool;
+    // store a block of an unstable epoch
+    fn write_blob(&mut self, hash: &HeaderHash, block_raw: &RawBlock);
+    // point the given tag to the given block
+    fn write_head(&mut self, tag: &str, hash: &HeaderHash);
+}
+
+struct StorageWriter<'a> {
+    storage: &'a storage::Storage,
+    epoch: Option<EpochWriterState>,
+}
+impl<'a> StorageWriter<'a> {
+    fn new(storage: &'a storage::Storage) -> Self {
+        StorageWriter { storage, epoch: None }
+    }
+}
+impl<'a> SyncWriter for StorageWriter<'a> {
+    fn start_epoch(&mut self, epoch_id: EpochId) {
+        self.epoch = Some(EpochWriterState {
+            epoch_id,
+            writer: storage::pack::PackWriter::init(&self.storage.config),
+            write_start_time: SystemTime::now(),
+            blobs_to_delete: vec![]
+        });
+    }
+    fn append_block(&mut self, hash: &HeaderHash, block_raw: &RawBlock, stored: bool) {
+        if let Some(epoch_writer_state) = self.epoch.as_mut() {
+            epoch_writer_state.writer.append(&storage::types::header_to_blockhash(hash), block_raw.as_ref());
+            if stored { epoch_writer_state.blobs_to_delete.push(hash.clone()); }
+        }
+    }
+    fn finish_epoch(&mut self) -> bool {
+        match self.epoch.take() {
+            None => false,
+            Some(mut epoch_writer_state) => {
+                finish_epoch(self.storage, &mut epoch_writer_state);
+                true
+            }
+        }
+    }
+    fn write_blob(&mut self, hash: &HeaderHash, block_raw: &RawBlock) {
+        storage::blob::write(self.storage, hash.bytes(), block_raw.as_ref()).unwrap();
+    }
+    fn write_head(&mut self, tag: &str, hash: &HeaderHash) {
+        storage::tag::write_hash(self.storage, &tag, hash);
+    }
+}
+
+struct DryRunWriter {
+    report: SyncReport,
+    packing: bool,
+}
+impl SyncWriter for DryRunWriter {
+    fn start_epoch(&mut self, epoch_id: EpochId) {
+        self.report.epochs.push(epoch_id);
+        self.packing = true;
+    }
+    fn append_block(&mut self, _: &HeaderHash, _: &RawBlock, _: bool) {}
+    fn finish_epoch(&mut self) -> bool { mem::replace(&mut self.packing, false) }
+    fn write_blob(&mut self, _: &HeaderHash, _: &RawBlock) { self.report.blobs += 1; }
+    fn write_head(&mut self, _: &str, hash: &HeaderHash) { self.report.head = Some(hash.clone()); }
+}
+
+/// Move the `HEAD` tag of the network to the given block, only if it is
+/// further in the chain than the block currently pointed by `HEAD`: it must have a higher
+/// chain difficulty, or the same difficulty and a later date (a genesis
+/// block has the difficulty of the last main block of the previous epoch).
+/// This way a stale relay cannot move `HEAD` backward.
+///
+/// Returns whether `HEAD` was updated.
+fn advance_head<W: SyncWriter>(storage: &storage::Storage, writer: &mut W, network: &str, hash: &HeaderHash, header: &BlockHeader) -> bool
+{
+    let head = tag::get_network_head_tag(network);
+    match storage.get_block_from_tag(&head) {
+        Err(Error::NoSuchTag) => {},
+        Err(err) => panic!(err),
+        Ok(block) => {
+            let head = block.get_header();
+            let current = (head.get_chain_difficulty(), head.get_blockdate());
+            let new = (header.get_chain_difficulty(), header.get_blockdate());
+            if new <= current {
+                warn!("not moving HEAD backward from {} ({}) to {} ({})",
+                      head.compute_hash().to_short_hex(), current.1,
+                      hash.to_short_hex(), new.1);
+                return false;
+            }
+        }
+    }
+    writer.write_head(&head, hash);
+    true
 }
 
 /// Synchronize the local blockchain stored in `storage` with the
 /// network `net`. That is, fetch all blocks between the most recent
-/// block we received (as denoted by the `HEAD` tag) and the network's
+/// block we received (as denoted by the `HEAD` tag of the `network`,
+/// see `storage::tag::get_network_head_tag`) and the network's
 /// current tip. Blocks will be packed into epochs on disk as soon
-/// they're stable.
+/// they're stable. The synchronization stops with `Error::UnexpectedBlock`
+/// if a block received is not the child of the previous one.
 ///
 /// If `sync_once` is set to `true`, then this function will
 /// synchronize once and then return. If it's set to `false`, then
@@ -187,6 +323,7 @@ fn net_sync_to<A: Api>(
 pub fn net_sync<A: Api>(
     net: &mut A,
     net_cfg: &net::Config,
+    network: &str,
     storage: &storage::Storage,
     sync_once: bool)
     -> Result<()>
@@ -196,7 +333,7 @@ pub fn net_sync<A: Api>(
 
     loop {
 
-        net_sync_to(net, net_cfg, storage, &tip_header)?;
+        net_sync_to(net, net_cfg, network, storage, &mut StorageWriter::new(storage), &tip_header)?;
 
         if sync_once { break }
 
@@ -206,24 +343,222 @@ pub fn net_sync<A: Api>(
     Ok(())
 }
 
+/// what a synchronization would commit to the storage, see
+/// [`net_sync_dry_run`](./fn.net_sync_dry_run.html).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SyncReport {
+    /// number of blocks downloaded from the network
+    pub blocks: usize,
+    /// the epochs which would be packed
+    pub epochs: Vec<EpochId>,
+    /// number of blocks of the unstable epochs which would be stored
+    /// as blobs
+    pub blobs: usize,
+    /// the block the `HEAD` tag would point to
+    pub head: Option<HeaderHash>,
+}
+
+/// Same as `net_sync` synchronizing once, but nothing is written to
+/// `storage`: the blocks are downloaded and checked to form a chain
+/// starting at our `HEAD`, and the returned report tells what would
+/// have been committed.
+pub fn net_sync_dry_run<A: Api>(
+    net: &mut A,
+    net_cfg: &net::Config,
+    network: &str,
+    storage: &storage::Storage)
+    -> Result<SyncReport>
+{
+    let tip_header = net.get_tip()?;
+    let mut writer = DryRunWriter { report: SyncReport::default(), packing: false };
+    let blocks = net_sync_to(net, net_cfg, network, storage, &mut writer, &tip_header)?;
+    Ok(SyncReport { blocks, ..writer.report })
+}
+
+/// Download and pack the epochs `from` to `to` (inclusive) only, i.e. to
+/// fetch some epochs again without synchronizing the whole chain. The
+/// download starts at the genesis block of `from` and stops at the
+/// genesis block following `to`.
+///
+/// `from` must be the first epoch of the chain or follow an epoch already
+/// packed in `storage`, and `to` must be stable. The epochs of the range
+/// are packed again if they already were, `HEAD` is not modified.
+pub fn net_sync_epochs<A: Api>(
+    net: &mut A,
+    net_cfg: &net::Config,
+    storage: &storage::Storage,
+    from: EpochId,
+    to: EpochId)
+    -> Result<()>
+{
+    if from > to { return Err(network::Error::InvalidEpochRange(from, to)) }
+
+    let tip_header = net.get_tip()?;
+    let SyncStart { tip, genesis_ref, first_unstable_epoch } = sync_start(net_cfg, &tip_header);
+    if to >= first_unstable_epoch { return Err(network::Error::UnstableEpoch(to)) }
+
+    let start = epoch_start(net_cfg, storage, from, genesis_ref)?;
+
+    info!("Fetching epochs      : {} to {}", from, to);
+
+    let mut epoch_writer_state : Option<EpochWriterState> = None;
+
+    net.get_blocks(&start.0, start.1, &tip, &mut |block_hash, block, block_raw| {
+        let date = block.get_header().get_blockdate();
+
+        if date.is_genesis() {
+            if let Some(mut epoch_writer_state) = epoch_writer_state.take() {
+                finish_epoch(storage, &mut epoch_writer_state);
+            }
+            if date.get_epochid() > to { return Fetch::Stop }
+
+            epoch_writer_state = Some(EpochWriterState {
+                epoch_id: date.get_epochid(),
+                writer: storage::pack::PackWriter::init(&storage.config),
+                write_start_time: SystemTime::now(),
+                blobs_to_delete: vec![]
+            });
+        }
+
+        if let Some(epoch_writer_state) = epoch_writer_state.as_mut() {
+            epoch_writer_state.writer.append(
+                &storage::types::header_to_blockhash(&block_hash), block_raw.as_ref());
+        }
+        Fetch::Continue
+    })?;
+
+    if let Some(epoch_writer_state) = epoch_writer_state {
+        warn!("epoch {} was not received entirely, it is not packed", epoch_writer_state.epoch_id);
+    }
+
+    Ok(())
+}
+
+/// the progress of an interrupted [`download_epoch`](./fn.download_epoch.html).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PartialProgress {
+    /// the last block of the epoch received and stored, `None` if no
+    /// block was
+    pub last_block: Option<HeaderHash>,
+}
+
+/// fetch the stable epoch `epoch` and pack it, returning the hash of its
+/// last block. The pack starts with the epoch boundary block of the
+/// epoch, so `storage::iter_back_from` walks across the epochs.
+///
+/// The blocks are stored as they are received, so if the download fails
+/// the error comes with the progress made so far: give it back to
+/// `download_epoch` to resume after the last stored block. Use
+/// `PartialProgress::default()` to start from the beginning of the epoch,
+/// which must be the first epoch of the chain or follow an epoch already
+/// packed in `storage`.
+pub fn download_epoch<A: Api>(
+    net: &mut A,
+    net_cfg: &net::Config,
+    storage: &storage::Storage,
+    epoch: EpochId,
+    progress: PartialProgress)
+    -> result::Result<HeaderHash, (PartialProgress, network::Error)>
+{
+    let mut last_block = progress.last_block;
+
+    let tip_header = match net.get_tip() {
+        Ok(header) => header,
+        Err(err) => return Err((PartialProgress { last_block }, err)),
+    };
+    let SyncStart { tip, genesis_ref, first_unstable_epoch } = sync_start(net_cfg, &tip_header);
+    if epoch >= first_unstable_epoch {
+        return Err((PartialProgress { last_block }, network::Error::UnstableEpoch(epoch)))
+    }
+
+    let start = match last_block {
+        Some(ref hash) => block_read(storage, hash.bytes()).and_then(|block| block.decode().ok()).map(|block| {
+            let header = block.get_header();
+            (BlockRef {
+                hash: hash.clone(),
+                parent: header.get_previous_header(),
+                date: header.get_blockdate()
+            }, false)
+        }).ok_or(network::Error::UnknownEpochStart(epoch)),
+        None => epoch_start(net_cfg, storage, epoch, genesis_ref),
+    };
+    let start = match start {
+        Ok(start) => start,
+        Err(err) => return Err((PartialProgress { last_block }, err)),
+    };
+
+    info!("Fetching epoch       : {} from {}", epoch, start.0.hash.to_short_hex());
+
+    let mut complete = false;
+    let mut write_error = None;
+    let fetched = net.get_blocks(&start.0, start.1, &tip, &mut |block_hash, block, block_raw| {
+        // the genesis block of the next epoch tells this one is complete
+        if block.get_header().epoch() > epoch {
+            complete = true;
+            return Fetch::Stop
+        }
+        if let Err(err) = storage::blob::write(storage, block_hash.bytes(), block_raw.as_ref()) {
+            write_error = Some(err);
+            return Fetch::Stop
+        }
+        last_block = Some(block_hash.clone());
+        Fetch::Continue
+    });
+    if let Err(err) = fetched {
+        return Err((PartialProgress { last_block }, err))
+    }
+    if let Some(err) = write_error {
+        return Err((PartialProgress { last_block }, err.into()))
+    }
+
+    match last_block {
+        Some(hash) if complete => {
+            maybe_create_epoch(storage, &mut StorageWriter::new(storage), epoch, &hash);
+            Ok(hash)
+        },
+        last_block => Err((PartialProgress { last_block }, network::Error::IncompleteEpoch(epoch))),
+    }
+}
+
+// where to fetch the given epoch from: its genesis block is the child of
+// the last block of the previous epoch, `UnknownEpochStart` if the
+// previous epoch is not packed
+fn epoch_start(
+    net_cfg: &net::Config,
+    storage: &storage::Storage,
+    epoch: EpochId,
+    genesis_ref: (BlockRef, bool))
+    -> Result<(BlockRef, bool)>
+{
+    if epoch == net_cfg.epoch_start {
+        Ok(genesis_ref)
+    } else if epoch > net_cfg.epoch_start && epoch_exists(storage, epoch - 1) {
+        let last = storage::epoch_last_block(storage, epoch - 1)?.decode()?.get_header();
+        Ok((BlockRef {
+            hash: last.compute_hash(),
+            parent: last.get_previous_header(),
+            date: last.get_blockdate()
+        }, false))
+    } else {
+        Err(network::Error::UnknownEpochStart(epoch))
+    }
+}
+
 // Create an epoch from a complete set of previously fetched blocks on
 // disk.
-fn maybe_create_epoch(storage: &storage::Storage, epoch_id: EpochId, last_block: &HeaderHash)
+fn maybe_create_epoch<W: SyncWriter>(storage: &storage::Storage, writer: &mut W, epoch_id: EpochId, last_block: &HeaderHash)
 {
     if epoch_exists(&storage, epoch_id) { return }
 
     info!("Packing epoch {}", epoch_id);
 
-    let mut epoch_writer_state = EpochWriterState {
-        epoch_id,
-        writer: storage::pack::PackWriter::init(&storage.config),
-        write_start_time: SystemTime::now(),
-        blobs_to_delete: vec![]
-    };
-
-    append_blocks_to_epoch_reverse(&storage, &mut epoch_writer_state, last_block);
+    let (blocks, _) = read_epoch_blobs(storage, epoch_id, last_block);
 
-    finish_epoch(storage, &mut epoch_writer_state);
+    writer.start_epoch(epoch_id);
+    for (hash, block_raw) in blocks {
+        writer.append_block(&hash, &block_raw, true);
+    }
+    writer.finish_epoch();
 }
 
 // Check whether an epoch pack exists on disk.
@@ -234,30 +569,29 @@ fn epoch_exists(storage: &storage::Storage, epoch_id: EpochId) -> bool
     storage::epoch::epoch_read(&storage.config, epoch_id).is_ok()
 }
 
-fn append_blocks_to_epoch_reverse(
+// Read the previously fetched blocks of the epoch, from its genesis block
+// to `last_block`, returns them with the hash of the last block of the
+// previous epoch.
+fn read_epoch_blobs(
     storage: &storage::Storage,
-    epoch_writer_state: &mut EpochWriterState,
+    epoch_id: EpochId,
     last_block: &HeaderHash)
-    -> HeaderHash
+    -> (Vec<(HeaderHash, RawBlock)>, HeaderHash)
 {
     let mut cur_hash = last_block.clone();
     let mut blocks = vec![];
     loop {
         let block_raw = block_read(&storage, cur_hash.bytes()).unwrap();
-        epoch_writer_state.blobs_to_delete.push(cur_hash.clone());
         let block = block_raw.decode().unwrap();
         let hdr = block.get_header();
-        assert!(hdr.get_blockdate().get_epochid() == epoch_writer_state.epoch_id);
-        blocks.push((storage::types::header_to_blockhash(&cur_hash), block_raw));
+        assert!(hdr.epoch() == epoch_id);
+        blocks.push((cur_hash.clone(), block_raw));
         cur_hash = hdr.get_previous_header();
         if hdr.get_blockdate().is_genesis() { break }
     }
+    blocks.reverse();
 
-    while let Some((hash, block_raw)) = blocks.pop() {
-        epoch_writer_state.writer.append(&hash, block_raw.as_ref());
-    }
-
-    cur_hash
+    (blocks, cur_hash)
 }
 
 fn finish_epoch(storage: &storage::Storage, epoch_writer_state: &mut EpochWriterState) {
@@ -267,7 +601,7 @@ fn finish_epoch(storage: &storage::Storage, epoch_writer_state: &mut EpochWriter
     tmpfile.render_permanent(&storage.config.get_index_filepath(&packhash)).unwrap();
     let epoch_time_elapsed = epoch_writer_state.write_start_time.elapsed().unwrap();
 
-    if epoch_id > 0 {
+    if epoch_id > EpochId::new(0) {
         assert!(
             epoch_exists(storage, epoch_id - 1),
             "Attempted finish_epoch() with non-existent previous epoch (ID {}, previous' ID {})",
@@ -277,6 +611,7 @@ fn finish_epoch(storage: &storage::Storage, epoch_writer_state: &mut EpochWriter
     }
 
     storage::epoch::epoch_create(&storage.config, &packhash, epoch_id);
+    storage::remove_cached_headers(&storage.config, epoch_id);
 
     info!("=> pack {} written for epoch {} in {}", hex::encode(&packhash[..]),
           epoch_id, duration_print(epoch_time_elapsed));
@@ -287,17 +622,408 @@ fn finish_epoch(storage: &storage::Storage, epoch_writer_state: &mut EpochWriter
     }
 }
 
+/// get the block of the given hash, looking in the local storage first.
+///
+/// The network is only contacted (using `get_net` to open the connection)
+/// if the block is not already stored. The block is then stored as a blob
+/// so the next lookups will not need the network.
+pub fn get_block<A, F>(storage: &storage::Storage, hash: &HeaderHash, get_net: F) -> Result<RawBlock>
+    where A: Api, F: FnOnce() -> A
+{
+    if let Some(block) = block_read(storage, hash.bytes()) {
+        debug!("block {} found in the local storage", hash);
+        return Ok(block);
+    }
+
+    let block = get_net().get_block(hash)?;
+    if let Err(err) = storage::blob::write(storage, hash.bytes(), block.as_ref()) {
+        warn!("unable to store the block {}: {:?}", hash, err);
+    }
+    Ok(block)
+}
+
 pub fn get_peer(blockchain: &str, cfg: &net::Config, native: bool) -> Peer {
     for peer in cfg.peers.iter() {
         if (native && peer.is_native()) || (!native && peer.is_http()) {
-            return Peer::new(
+            return Peer::new_with_timeouts(
                 String::from(blockchain),
                 peer.name().to_owned(),
                 peer.peer().clone(),
                 cfg.protocol_magic,
+                &cfg.timeouts,
             ).unwrap();
         }
     }
 
     panic!("no peer to connect to")
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cardano::block::{Block};
+    use network::{Error};
+    use cardano::testing::{genesis_block, main_block};
+    use storage::testing::temporary_storage;
+    use std::{fs, io};
+
+    const BLOCK_HASH : &'static str = "8e7b11a8a4c2ba8fca2b2e6e8a88dd8a5e4d7ba4d3c76cd2c8c3ab05ee84e1a5";
+    const BLOCK_DATA : &'static [u8] = &[0x82, 0x00, 0x80];
+
+    // the error of the requests the mocks do not serve
+    fn unsupported<T>() -> Result<T> {
+        Err(Error::IoError(io::Error::new(io::ErrorKind::Other, "request not supported by the mock")))
+    }
+
+    struct MockNet(Option<RawBlock>);
+    impl Api for MockNet {
+        fn get_tip(&mut self) -> Result<BlockHeader> { unsupported() }
+        fn wait_for_new_tip(&mut self, _: &HeaderHash) -> Result<BlockHeader> { unsupported() }
+        fn get_block(&mut self, _: &HeaderHash) -> Result<RawBlock> {
+            self.0.take().ok_or(Error::ConnectionTimedOut)
+        }
+        fn get_blocks<F>(&mut self, _: &BlockRef, _: bool, _: &BlockRef, _: &mut F) -> Result<()>
+            where F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch
+        { unsupported() }
+    }
+
+    /// mock transport serving a whole chain, from its first block,
+    /// recording the blocks passed to `get_blocks`' callback. It fails
+    /// after having passed the given number of blocks, if any.
+    struct MockChain(Vec<RawBlock>, Vec<HeaderHash>, Option<usize>);
+    impl MockChain {
+        fn new(blocks: Vec<RawBlock>) -> Self { MockChain(blocks, vec![], None) }
+        fn failing_after(blocks: Vec<RawBlock>, count: usize) -> Self { MockChain(blocks, vec![], Some(count)) }
+    }
+    impl Api for MockChain {
+        fn get_tip(&mut self) -> Result<BlockHeader> {
+            Ok(self.0.last().unwrap().decode().unwrap().get_header())
+        }
+        fn wait_for_new_tip(&mut self, _: &HeaderHash) -> Result<BlockHeader> { unsupported() }
+        fn get_block(&mut self, _: &HeaderHash) -> Result<RawBlock> { unsupported() }
+        fn get_blocks<F>(&mut self, from: &BlockRef, inclusive: bool, _: &BlockRef, got_block: &mut F) -> Result<()>
+            where F: FnMut(&HeaderHash, &Block, &RawBlock) -> Fetch
+        {
+            let mut started = false;
+            for block_raw in self.0.iter() {
+                let block = block_raw.decode().unwrap();
+                let hash = block.get_header().compute_hash();
+                if !started {
+                    started = if inclusive { hash == from.hash } else { block.get_header().get_previous_header() == from.hash };
+                    if !started { continue }
+                }
+                if self.2 == Some(self.1.len()) { return Err(Error::ConnectionTimedOut) }
+                self.1.push(hash.clone());
+                if got_block(&hash, &block, block_raw) == Fetch::Stop { break }
+            }
+            Ok(())
+        }
+    }
+
+    // a chain of genesis blocks of the epochs 0 to 3, and its configuration
+    fn chain() -> (net::Config, Vec<RawBlock>) { chain_of(4) }
+
+    // a chain of genesis blocks of the given number of epochs
+    fn chain_of(epochs: u32) -> (net::Config, Vec<RawBlock>) {
+        let mut blocks = vec![];
+        let mut prev = HeaderHash::new(b"genesis prev");
+        for epoch in 0..epochs {
+            let block = genesis_block(epoch, 0, &prev);
+            prev = block.decode().unwrap().get_header().compute_hash();
+            blocks.push(block);
+        }
+        (config(&blocks), blocks)
+    }
+
+    // the configuration of the chain starting with the given blocks
+    fn config(blocks: &[RawBlock]) -> net::Config {
+        let genesis = blocks[0].decode().unwrap().get_header();
+        net::Config::builder()
+            .genesis(genesis.compute_hash())
+            .genesis_prev(genesis.get_previous_header())
+            .protocol_magic(Default::default())
+            .build().unwrap()
+    }
+
+    const NETWORK : &str = "test";
+
+    #[test]
+    fn get_block_from_storage_does_not_use_network() {
+        let storage = temporary_storage();
+        let hash = HeaderHash::from_hex(&BLOCK_HASH).unwrap();
+        storage::blob::write(&storage, hash.bytes(), BLOCK_DATA).unwrap();
+
+        let block = get_block(&storage, &hash, || -> MockNet { panic!("network should not be used") }).unwrap();
+        assert_eq!(block.as_ref(), BLOCK_DATA);
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn get_block_from_network_is_stored() {
+        let storage = temporary_storage();
+        let hash = HeaderHash::from_hex(&BLOCK_HASH).unwrap();
+
+        let block = get_block(&storage, &hash, || MockNet(Some(RawBlock::from_dat(BLOCK_DATA.to_vec())))).unwrap();
+        assert_eq!(block.as_ref(), BLOCK_DATA);
+        assert!(storage::blob::exist(&storage, hash.bytes()));
+
+        let block = get_block(&storage, &hash, || -> MockNet { panic!("network should not be used") }).unwrap();
+        assert_eq!(block.as_ref(), BLOCK_DATA);
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn net_sync_dry_run_writes_nothing() {
+        let storage = temporary_storage();
+        let (cfg, blocks) = chain();
+        let tip = blocks[3].decode().unwrap().get_header().compute_hash();
+
+        let report = net_sync_dry_run(&mut MockChain::new(blocks), &cfg, NETWORK, &storage).unwrap();
+        assert_eq!(report, SyncReport {
+            blocks: 4,
+            // the tip is the genesis block of the epoch 3, the epoch 2 is not stable
+            epochs: vec![EpochId::new(0), EpochId::new(1)],
+            blobs: 2,
+            head: Some(tip),
+        });
+
+        assert!(!tag::exist(&storage, &tag::get_network_head_tag(NETWORK)));
+        assert!(storage.config.list_indexes().is_empty());
+        assert!(storage.config.list_blob(None).is_empty());
+        assert!(!epoch_exists(&storage, EpochId::new(0)));
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn net_sync_dry_run_checks_the_chain() {
+        let storage = temporary_storage();
+        let (cfg, mut blocks) = chain();
+        // the block of the epoch 3 is received right after the epoch 1
+        blocks.remove(2);
+        let unexpected = blocks[2].decode().unwrap().get_header().compute_hash();
+
+        match net_sync_dry_run(&mut MockChain::new(blocks), &cfg, NETWORK, &storage) {
+            Err(Error::UnexpectedBlock(hash)) => assert_eq!(hash, unexpected),
+            r => panic!("expected an unexpected block error, got {:?}", r),
+        }
+        assert!(!tag::exist(&storage, &tag::get_network_head_tag(NETWORK)));
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn head_is_not_moved_backward() {
+        let storage = temporary_storage();
+        let prev = HeaderHash::new(b"genesis prev");
+        let store = |block: &RawBlock| {
+            let header = block.decode().unwrap().get_header();
+            let hash = header.compute_hash();
+            storage::blob::write(&storage, hash.bytes(), block.as_ref()).unwrap();
+            (hash, header)
+        };
+
+        let (head, head_header) = store(&genesis_block(2, 10, &prev));
+        assert!(advance_head(&storage, &mut StorageWriter::new(&storage), NETWORK, &head, &head_header));
+        assert_eq!(tag::read_hash(&storage, &tag::get_network_head_tag(NETWORK)), Some(head.clone()));
+
+        // a stale relay answering with a lower difficulty head
+        let (stale, stale_header) = store(&genesis_block(3, 5, &prev));
+        assert!(!advance_head(&storage, &mut StorageWriter::new(&storage), NETWORK, &stale, &stale_header));
+        assert_eq!(tag::read_hash(&storage, &tag::get_network_head_tag(NETWORK)), Some(head.clone()));
+
+        // same difficulty, earlier date
+        let (stale, stale_header) = store(&genesis_block(1, 10, &prev));
+        assert!(!advance_head(&storage, &mut StorageWriter::new(&storage), NETWORK, &stale, &stale_header));
+        assert_eq!(tag::read_hash(&storage, &tag::get_network_head_tag(NETWORK)), Some(head.clone()));
+
+        let (next, next_header) = store(&genesis_block(3, 11, &head));
+        assert!(advance_head(&storage, &mut StorageWriter::new(&storage), NETWORK, &next, &next_header));
+        assert_eq!(tag::read_hash(&storage, &tag::get_network_head_tag(NETWORK)), Some(next));
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn heads_of_two_networks() {
+        let storage = temporary_storage();
+        let prev = HeaderHash::new(b"genesis prev");
+        let store = |block: &RawBlock| {
+            let header = block.decode().unwrap().get_header();
+            let hash = header.compute_hash();
+            storage::blob::write(&storage, hash.bytes(), block.as_ref()).unwrap();
+            (hash, header)
+        };
+
+        let (mainnet, mainnet_header) = store(&genesis_block(2, 10, &prev));
+        assert!(advance_head(&storage, &mut StorageWriter::new(&storage), "mainnet", &mainnet, &mainnet_header));
+
+        // the head of the testnet is behind the one of the mainnet, but it
+        // is not compared to it
+        let (testnet, testnet_header) = store(&genesis_block(1, 5, &prev));
+        assert!(advance_head(&storage, &mut StorageWriter::new(&storage), "testnet", &testnet, &testnet_header));
+
+        assert_eq!(tag::read_hash(&storage, &tag::get_network_head_tag("mainnet")), Some(mainnet));
+        assert_eq!(tag::read_hash(&storage, &tag::get_network_head_tag("testnet")), Some(testnet));
+        assert!(!tag::exist(&storage, &tag::HEAD));
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn net_sync_epochs_downloads_only_the_range() {
+        let storage = temporary_storage();
+        // the tip is the genesis block of the epoch 5, the epoch 4 is not stable
+        let (cfg, blocks) = chain_of(6);
+        let hash = |i: usize| blocks[i].decode().unwrap().get_header().compute_hash();
+
+        let mut net = MockChain::new(blocks.clone());
+        net_sync_epochs(&mut net, &cfg, &storage, EpochId::new(0), EpochId::new(0)).unwrap();
+        // the genesis block of the epoch 1 tells the epoch 0 is complete
+        assert_eq!(net.1, vec![hash(0), hash(1)]);
+        assert!(epoch_exists(&storage, EpochId::new(0)));
+        assert!(!epoch_exists(&storage, EpochId::new(1)));
+
+        let mut net = MockChain::new(blocks.clone());
+        net_sync_epochs(&mut net, &cfg, &storage, EpochId::new(1), EpochId::new(2)).unwrap();
+        assert_eq!(net.1, vec![hash(1), hash(2), hash(3)]);
+        assert!(epoch_exists(&storage, EpochId::new(1)));
+        assert!(epoch_exists(&storage, EpochId::new(2)));
+        assert!(!epoch_exists(&storage, EpochId::new(3)));
+        assert!(!tag::exist(&storage, &tag::get_network_head_tag(NETWORK)));
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn download_epoch_resumes_from_the_partial_progress() {
+        let storage = temporary_storage();
+        let hash = |block: &RawBlock| block.decode().unwrap().get_header().compute_hash();
+        // the epoch 0 has a genesis block and 3 main blocks, the tip is
+        // the genesis block of the epoch 2
+        let mut blocks = vec![genesis_block(0, 0, &HeaderHash::new(b"genesis prev"))];
+        for slotid in 0..3 {
+            let block = main_block(0, slotid, &hash(blocks.last().unwrap()));
+            blocks.push(block);
+        }
+        for epoch in 1..3 {
+            let block = genesis_block(epoch, 0, &hash(blocks.last().unwrap()));
+            blocks.push(block);
+        }
+        let cfg = config(&blocks);
+
+        // the connection is lost after the second block of the epoch
+        let mut net = MockChain::failing_after(blocks.clone(), 2);
+        let progress = match download_epoch(&mut net, &cfg, &storage, EpochId::new(0), PartialProgress::default()) {
+            Err((progress, Error::ConnectionTimedOut)) => progress,
+            r => panic!("expected a connection error, got {:?}", r),
+        };
+        assert_eq!(progress, PartialProgress { last_block: Some(hash(&blocks[1])) });
+        assert!(storage::blob::exist(&storage, hash(&blocks[1]).bytes()));
+        assert!(!epoch_exists(&storage, EpochId::new(0)));
+
+        let mut net = MockChain::new(blocks.clone());
+        let last = download_epoch(&mut net, &cfg, &storage, EpochId::new(0), progress).unwrap();
+        assert_eq!(last, hash(&blocks[3]));
+        assert_eq!(net.1, vec![hash(&blocks[2]), hash(&blocks[3]), hash(&blocks[4])]);
+        assert!(epoch_exists(&storage, EpochId::new(0)));
+        assert_eq!(storage::epoch_blocks(&storage, EpochId::new(0)).unwrap().count(), 4);
+        assert!(storage.config.list_blob(None).is_empty());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn download_epoch_reports_storage_errors() {
+        let storage = temporary_storage();
+        let (cfg, blocks) = chain();
+        let hash = |block: &RawBlock| block.decode().unwrap().get_header().compute_hash();
+
+        // the blobs cannot be written
+        let blob_dir = storage.config.get_filetype_dir(storage::types::StorageFileType::Blob);
+        fs::remove_dir_all(&blob_dir).unwrap();
+        match download_epoch(&mut MockChain::new(blocks.clone()), &cfg, &storage, EpochId::new(0), PartialProgress::default()) {
+            Err((progress, Error::StorageError(_))) => assert_eq!(progress, PartialProgress::default()),
+            r => panic!("expected a storage error, got {:?}", r),
+        }
+        assert!(!epoch_exists(&storage, EpochId::new(0)));
+
+        fs::create_dir_all(&blob_dir).unwrap();
+        let last = download_epoch(&mut MockChain::new(blocks.clone()), &cfg, &storage, EpochId::new(0), PartialProgress::default()).unwrap();
+        assert_eq!(last, hash(&blocks[0]));
+        assert!(epoch_exists(&storage, EpochId::new(0)));
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn iter_back_across_downloaded_epochs() {
+        let storage = temporary_storage();
+        let hash = |block: &RawBlock| block.decode().unwrap().get_header().compute_hash();
+        // the epochs 0 and 1 have a genesis block and 2 main blocks, the
+        // tip is the genesis block of the epoch 3
+        let mut blocks = vec![genesis_block(0, 0, &HeaderHash::new(b"genesis prev"))];
+        for epoch in 0..2 {
+            if epoch > 0 {
+                let block = genesis_block(epoch, 0, &hash(blocks.last().unwrap()));
+                blocks.push(block);
+            }
+            for slotid in 0..2 {
+                let block = main_block(epoch as u32, slotid, &hash(blocks.last().unwrap()));
+                blocks.push(block);
+            }
+        }
+        for epoch in 2..4 {
+            let block = genesis_block(epoch, 0, &hash(blocks.last().unwrap()));
+            blocks.push(block);
+        }
+        let cfg = config(&blocks);
+
+        let mut net = MockChain::new(blocks.clone());
+        download_epoch(&mut net, &cfg, &storage, EpochId::new(0), PartialProgress::default()).unwrap();
+        let last = download_epoch(&mut net, &cfg, &storage, EpochId::new(1), PartialProgress::default()).unwrap();
+        assert_eq!(last, hash(&blocks[5]));
+        // the packed epoch 1 starts with its epoch boundary block
+        let first = storage::epoch_blocks(&storage, EpochId::new(1)).unwrap().next().unwrap();
+        assert_eq!(first.get_header().compute_hash(), hash(&blocks[3]));
+
+        // the lookups of the packs are loaded when the storage is opened
+        let storage = storage::Storage::init(&storage.config).unwrap();
+
+        // from the last block of the epoch 1 back to the genesis block of
+        // the epoch 0, through the epoch boundary block of the epoch 1
+        let walked : Vec<_> = storage::iter_back_from(&storage, &last, &cfg.genesis_prev).unwrap()
+            .map(|block| block.unwrap().get_header().compute_hash())
+            .collect();
+        let expected : Vec<_> = blocks[..6].iter().rev().map(hash).collect();
+        assert_eq!(walked, expected);
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn net_sync_epochs_checks_the_range() {
+        let storage = temporary_storage();
+        let (cfg, blocks) = chain_of(6);
+        let mut net = MockChain::new(blocks);
+
+        match net_sync_epochs(&mut net, &cfg, &storage, EpochId::new(2), EpochId::new(1)) {
+            Err(Error::InvalidEpochRange(from, to)) => assert_eq!((from, to), (EpochId::new(2), EpochId::new(1))),
+            r => panic!("expected an invalid range error, got {:?}", r),
+        }
+        // the epoch 1 is not packed, the hash the epoch 2 starts from is unknown
+        match net_sync_epochs(&mut net, &cfg, &storage, EpochId::new(2), EpochId::new(3)) {
+            Err(Error::UnknownEpochStart(epoch)) => assert_eq!(epoch, EpochId::new(2)),
+            r => panic!("expected an unknown epoch start error, got {:?}", r),
+        }
+        match net_sync_epochs(&mut net, &cfg, &storage, EpochId::new(0), EpochId::new(4)) {
+            Err(Error::UnstableEpoch(epoch)) => assert_eq!(epoch, EpochId::new(4)),
+            r => panic!("expected an unstable epoch error, got {:?}", r),
+        }
+        assert!(net.1.is_empty());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+}
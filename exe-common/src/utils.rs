@@ -5,11 +5,15 @@ use storage::types::PackHash;
 // Return the chain of block headers starting at from's next block
 // and terminating at to, unless this range represent a number
 // of blocks greater than the limit imposed by the node we're talking to.
+//
+// the epochs are searched from `start_epochid` down to `minimum_epochid`,
+// there is nothing to search if the bounds are inverted.
 pub fn find_earliest_epoch(
     storage: &storage::Storage,
     minimum_epochid: block::EpochId,
     start_epochid: block::EpochId,
 ) -> Option<(block::EpochId, PackHash)> {
+    if start_epochid < minimum_epochid { return None; }
     let mut epoch_id = start_epochid;
     loop {
         match storage::tag::read_hash(storage, &storage::tag::get_epoch_tag(epoch_id)) {
@@ -52,3 +56,21 @@ pub fn get_last_blockid(
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn find_earliest_epoch_bounds() {
+        let storage = storage::testing::temporary_storage();
+        let hash = block::HeaderHash::new(b"last block of epoch 2");
+        storage::tag::write_hash(&storage, &storage::tag::get_epoch_tag(block::EpochId::new(2)), &hash);
+
+        let found = find_earliest_epoch(&storage, block::EpochId::new(0), block::EpochId::new(4));
+        assert_eq!(found, Some((block::EpochId::new(2), hash.into_bytes())));
+        assert_eq!(find_earliest_epoch(&storage, block::EpochId::new(3), block::EpochId::new(4)), None);
+        // inverted bounds: the epoch 2 is below the minimum
+        assert_eq!(find_earliest_epoch(&storage, block::EpochId::new(3), block::EpochId::new(2)), None);
+    }
+}
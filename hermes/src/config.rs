@@ -67,11 +67,14 @@ impl Config {
 
         for name in &self.network_names {
             let netcfg_dir = self.get_networks_dir().join(name);
+            // the storage is used to synchronize the network and serve its blocks
+            let mut storage = self.get_storage(name)?;
+            storage.enable_block_cache(storage::DEFAULT_BLOCK_CACHE_CAPACITY);
 
             let network = Network {
                 path: netcfg_dir,
                 config: self.get_network_config(name)?,
-                storage: Arc::new(self.get_storage(name)?)
+                storage: Arc::new(storage)
             };
 
             networks.insert(name.to_owned(), network);
@@ -83,11 +86,11 @@ impl Config {
     pub fn get_network_config<P: AsRef<Path>>(&self, name: P) -> Result<net::Config> {
         let path = self.get_networks_dir().join(name).join("config.yml");
         match net::Config::from_file(&path) {
-            None => {
-                error!("error while parsing config file: {:?}", path);
+            Err(err) => {
+                error!("error while parsing config file {:?}: {}", path, err);
                 Err(Error::BlockchainConfigError("error while parsing network config file"))
             },
-            Some(cfg) => Ok(cfg)
+            Ok(cfg) => Ok(cfg)
         }
     }
 
@@ -29,6 +29,7 @@ impl iron::Handler for Handler {
     // XXX
     //
     // The current implementation of the TIP handler is to look for the HEAD tag
+    // of the network
     fn handle(&self, req: &mut Request) -> IronResult<Response> {
         let ref network_name = req.extensions.get::<router::Router>().unwrap().find("network").unwrap();
 
@@ -41,7 +42,7 @@ impl iron::Handler for Handler {
             Some(net) => net
         };
 
-        match net.storage.get_block_from_tag(&tag::HEAD) {
+        match net.storage.get_block_from_tag(&tag::get_network_head_tag(network_name)) {
             Err(Error::NoSuchTag) =>
                 Ok(Response::with((status::NotFound, "No Tip To Serve"))),
             Err(err) => {
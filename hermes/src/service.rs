@@ -55,6 +55,8 @@ fn refresh_network(label: &str, net: &Network) {
     let netcfg_file = net.storage.config.get_config_file();
     let net_cfg = net::Config::from_file(&netcfg_file).expect("no network config present");
 
-    sync::net_sync(&mut sync::get_peer(&label, &net_cfg, true), &net_cfg, &net.storage, false)
+    let mut peer = sync::get_peer(&label, &net_cfg, true);
+    peer.set_header_cache(&net.storage.config);
+    sync::net_sync(&mut peer, &net_cfg, label, &net.storage, false)
         .unwrap_or_else(|err| { warn!("Sync failed: {:?}", err) });
 }
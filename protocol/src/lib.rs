@@ -1,9 +1,15 @@
+#![cfg_attr(feature = "with-bench", feature(test))]
+
 extern crate cardano;
 #[macro_use]
 extern crate log;
 #[macro_use]
 extern crate cbor_event;
 
+#[cfg(test)]
+#[cfg(feature = "with-bench")]
+extern crate test;
+
 pub mod ntt;
 pub mod packet;
 
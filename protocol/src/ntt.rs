@@ -232,8 +232,8 @@ pub mod protocol {
 
     impl NodeId {
         pub fn from_slice(slice: &[u8]) -> Option<Self> {
-            if slice[0] != NODEID_SYN && slice[0] != NODEID_ACK { return None }
             if slice.len() != 9 { return None }
+            if slice[0] != NODEID_SYN && slice[0] != NODEID_ACK { return None }
             let mut buf = [0u8;9];
             buf.clone_from_slice(slice);
             Some(NodeId(buf))
@@ -3,6 +3,7 @@ use std::{fmt};
 use cardano::config::{ProtocolMagic};
 use cardano::block;
 use cardano::block::{HeaderHash};
+use cardano::tx;
 
 use cbor_event::{self, se, de::{self, RawCbor}};
 
@@ -30,10 +31,7 @@ impl se::Serialize for HandlerSpec {
 }
 impl de::Deserialize for HandlerSpec {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid HandlerSpec: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "HandlerSpec")?;
         let t = raw.unsigned_integer()?;
         if t != 0 {
             return Err(cbor_event::Error::CustomError(format!("Invalid value, expected 0, received {}", t)));
@@ -160,10 +158,7 @@ impl se::Serialize for Handshake {
 }
 impl cbor_event::de::Deserialize for Handshake {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(4) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid Handshake: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(4, "Handshake")?;
         let pm   = raw.deserialize()?;
         let v    = raw.deserialize()?;
         let ins  = raw.deserialize()?;
@@ -175,6 +170,27 @@ impl cbor_event::de::Deserialize for Handshake {
 
 pub fn send_handshake(hs: &Handshake) -> Vec<u8> { cbor!(hs).unwrap() }
 
+/// what the peer advertised in its reply to our handshake
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HandshakeAck {
+    pub protocol_magic: ProtocolMagic,
+    pub version: block::Version,
+}
+
+/// decode the peer's reply to our handshake `hs`, checking the peer is
+/// on the same network (same protocol magic) with a compatible version
+/// (same major version).
+pub fn parse_handshake_response(bytes: &[u8], hs: &Handshake) -> ::Result<HandshakeAck> {
+    let peer : Handshake = RawCbor::from(bytes).deserialize()?;
+    if peer.protocol_magic != hs.protocol_magic {
+        return Err(::Error::ProtocolMagicMismatch(hs.protocol_magic, peer.protocol_magic));
+    }
+    if peer.version.major() != hs.version.major() {
+        return Err(::Error::VersionMismatch(hs.version, peer.version));
+    }
+    Ok(HandshakeAck { protocol_magic: peer.protocol_magic, version: peer.version })
+}
+
 // Message Header follow by the data
 type Message = (u8, Vec<u8>);
 
@@ -183,6 +199,8 @@ pub enum MsgType {
     MsgHeaders = 0x5,
     MsgGetBlocks = 0x6,
     MsgSubscribe = 0xd,
+    /// transaction relay (`InvOrData` of the transaction messages)
+    MsgAnnounceTx = 0x25,
 }
 
 pub fn send_msg_subscribe(keep_alive: bool) -> Message {
@@ -213,11 +231,44 @@ pub fn send_msg_getblocks(from: &HeaderHash, to: &HeaderHash) -> Message {
     (MsgType::MsgGetBlocks as u8, dat)
 }
 
+/// send a transaction to the node's transaction relay
+///
+/// the relay messages are either an announcement of the transaction id
+/// (`0`) or the data itself (`1`), we send the data directly.
+pub fn send_msg_announce_tx(tx: &tx::TxAux) -> Message {
+    let dat = se::Serializer::new_vec().write_array(cbor_event::Len::Len(2)).unwrap()
+        .write_unsigned_integer(1).unwrap()
+        .serialize(tx).unwrap()
+        .finalize();
+    (MsgType::MsgAnnounceTx as u8, dat)
+}
+
+/// answer to a `GetHeaders` request.
+///
+/// The headers are kept in the order they were received: the node sends
+/// them newest first. Use `into_oldest_first` to get them in chain order
+/// regardless of the order they were received in.
 #[derive(Debug)]
 pub enum BlockHeaderResponse {
     Ok(Vec<block::BlockHeader>),
     Err(String)
 }
+impl BlockHeaderResponse {
+    /// the received headers, oldest first (ordered by chain difficulty
+    /// then by date), or the error message sent by the node.
+    ///
+    /// A genesis block has the same chain difficulty as the last block
+    /// of the previous epoch, the date puts it after that block.
+    pub fn into_oldest_first(self) -> ::std::result::Result<Vec<block::BlockHeader>, String> {
+        match self {
+            BlockHeaderResponse::Ok(mut headers) => {
+                headers.sort_by_key(|header| (header.get_chain_difficulty(), header.get_blockdate()));
+                Ok(headers)
+            },
+            BlockHeaderResponse::Err(err) => Err(err),
+        }
+    }
+}
 impl fmt::Display for BlockHeaderResponse {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -235,10 +286,7 @@ impl fmt::Display for BlockHeaderResponse {
 }
 impl de::Deserialize for BlockHeaderResponse {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid BlockHeaderResponse: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "BlockHeaderResponse")?;
         let sum_type = raw.unsigned_integer()?;
         match sum_type {
             0 => {
@@ -260,10 +308,7 @@ pub enum BlockResponse {
 }
 impl de::Deserialize for BlockResponse {
     fn deserialize<'a>(raw: &mut RawCbor<'a>) -> cbor_event::Result<Self> {
-        let len = raw.array()?;
-        if len != cbor_event::Len::Len(2) {
-            return Err(cbor_event::Error::CustomError(format!("Invalid BlockHeaderResponse: recieved array of {:?} elements", len)));
-        }
+        raw.tuple(2, "BlockResponse")?;
         let sum_type = raw.unsigned_integer()?;
         match sum_type {
             0 => {
@@ -281,7 +326,7 @@ mod tests {
     use super::*;
     use cbor_event::{de::{RawCbor}};
 
-    const GET_BLOCK_HEADER_BYTES : &'static [u8] = &[
+    pub const GET_BLOCK_HEADER_BYTES : &'static [u8] = &[
           0x82, 0x00, 0x9f, 0x82, 0x01, 0x85, 0x1a, 0x2d
         , 0x96, 0x4a, 0x09, 0x58, 0x20, 0x9d, 0x63, 0xd4, 0x66, 0x7d, 0x43, 0x26, 0x09, 0x8b, 0x1a, 0xb9
         , 0xa9, 0x61, 0xef, 0x30, 0x35, 0xbc, 0xe2, 0x49, 0x99, 0x07, 0xa0, 0x31, 0x24, 0x95, 0x5f, 0xbd
@@ -324,6 +369,118 @@ mod tests {
         , 0x4f, 0x0c, 0x4e, 0x8e, 0xdf, 0xff
     ];
 
+    // `MsgBlock` response of a mainnet main block with 3 transactions
+    pub const BLOCK_RESPONSE_BYTES : &'static [u8] = &[
+          0x82, 0x00, 0x82, 0x01, 0x83, 0x85, 0x1a, 0x2d, 0x96, 0x4a, 0x09, 0x58, 0x20, 0x3e, 0x70, 0x5e
+        , 0x9a, 0xa2, 0x7f, 0xe5, 0x4e, 0x2c, 0x66, 0x2a, 0x0a, 0x5a, 0xa8, 0x0c, 0x36, 0x0b, 0xd4, 0x7c
+        , 0xe2, 0x4b, 0xb9, 0x42, 0x9d, 0xfa, 0x4f, 0xdf, 0x17, 0x0c, 0x2d, 0xed, 0x81, 0x84, 0x83, 0x03
+        , 0x58, 0x20, 0x0a, 0x56, 0x16, 0x8c, 0x95, 0xc6, 0x78, 0x1f, 0xe3, 0x7e, 0x68, 0x53, 0x9b, 0x6c
+        , 0xef, 0x88, 0xce, 0xe1, 0xb4, 0x72, 0xe1, 0xd2, 0x9a, 0x7b, 0xe3, 0xed, 0x49, 0x79, 0x29, 0xc2
+        , 0x9c, 0x3d, 0x58, 0x20, 0x4f, 0xa3, 0xff, 0xe4, 0x9f, 0xc2, 0x35, 0x9e, 0xae, 0xb5, 0xe2, 0x4e
+        , 0x70, 0xc0, 0x7a, 0xe9, 0x52, 0x00, 0x0c, 0x39, 0xc9, 0x0f, 0xa6, 0x71, 0x95, 0x28, 0xb6, 0xab
+        , 0x27, 0xd0, 0x39, 0x3f, 0x82, 0x03, 0x58, 0x20, 0xd3, 0x6a, 0x26, 0x19, 0xa6, 0x72, 0x49, 0x46
+        , 0x04, 0xe1, 0x1b, 0xb4, 0x47, 0xcb, 0xcf, 0x52, 0x31, 0xe9, 0xf2, 0xba, 0x25, 0xc2, 0x16, 0x91
+        , 0x77, 0xed, 0xc9, 0x41, 0xbd, 0x50, 0xad, 0x6c, 0x58, 0x20, 0xaf, 0xc0, 0xda, 0x64, 0x18, 0x3b
+        , 0xf2, 0x66, 0x4f, 0x3d, 0x4e, 0xec, 0x72, 0x38, 0xd5, 0x24, 0xba, 0x60, 0x7f, 0xae, 0xea, 0xb2
+        , 0x4f, 0xc1, 0x00, 0xeb, 0x86, 0x1d, 0xba, 0x69, 0x97, 0x1b, 0x58, 0x20, 0x4e, 0x66, 0x28, 0x0c
+        , 0xd9, 0x4d, 0x59, 0x10, 0x72, 0x34, 0x9b, 0xec, 0x0a, 0x30, 0x90, 0xa5, 0x3a, 0xa9, 0x45, 0x56
+        , 0x2e, 0xfb, 0x6d, 0x08, 0xd5, 0x6e, 0x53, 0x65, 0x4b, 0x0e, 0x40, 0x98, 0x84, 0x82, 0x01, 0x19
+        , 0x37, 0xb2, 0x58, 0x40, 0x1b, 0xc9, 0x7a, 0x2f, 0xe0, 0x2c, 0x29, 0x78, 0x80, 0xce, 0x8e, 0xcf
+        , 0xd9, 0x97, 0xfe, 0x4c, 0x1e, 0xc0, 0x9e, 0xe1, 0x0f, 0xee, 0xee, 0x9f, 0x68, 0x67, 0x60, 0x16
+        , 0x6b, 0x05, 0x28, 0x1d, 0x62, 0x83, 0x46, 0x8f, 0xfd, 0x93, 0xbe, 0xcb, 0x0c, 0x95, 0x6c, 0xcd
+        , 0xdd, 0x64, 0x2d, 0xf9, 0xb1, 0x24, 0x4c, 0x91, 0x59, 0x11, 0x18, 0x5f, 0xa4, 0x93, 0x55, 0xf6
+        , 0xf2, 0x2b, 0xfa, 0xb9, 0x81, 0x19, 0x8b, 0xfe, 0x82, 0x02, 0x82, 0x84, 0x00, 0x58, 0x40, 0x1b
+        , 0xc9, 0x7a, 0x2f, 0xe0, 0x2c, 0x29, 0x78, 0x80, 0xce, 0x8e, 0xcf, 0xd9, 0x97, 0xfe, 0x4c, 0x1e
+        , 0xc0, 0x9e, 0xe1, 0x0f, 0xee, 0xee, 0x9f, 0x68, 0x67, 0x60, 0x16, 0x6b, 0x05, 0x28, 0x1d, 0x62
+        , 0x83, 0x46, 0x8f, 0xfd, 0x93, 0xbe, 0xcb, 0x0c, 0x95, 0x6c, 0xcd, 0xdd, 0x64, 0x2d, 0xf9, 0xb1
+        , 0x24, 0x4c, 0x91, 0x59, 0x11, 0x18, 0x5f, 0xa4, 0x93, 0x55, 0xf6, 0xf2, 0x2b, 0xfa, 0xb9, 0x58
+        , 0x40, 0x61, 0x26, 0x1a, 0x95, 0xb7, 0x61, 0x3e, 0xe6, 0xbf, 0x20, 0x67, 0xda, 0xd7, 0x7b, 0x70
+        , 0x34, 0x97, 0x29, 0xb0, 0xc5, 0x0d, 0x57, 0xbc, 0x1c, 0xf3, 0x0d, 0xe0, 0xdb, 0x4a, 0x1e, 0x73
+        , 0xa8, 0x85, 0xd0, 0x05, 0x4a, 0xf7, 0xc2, 0x3f, 0xc6, 0xc3, 0x79, 0x19, 0xdb, 0xa4, 0x1c, 0x60
+        , 0x2a, 0x57, 0xe2, 0xd0, 0xf9, 0x32, 0x9a, 0x79, 0x54, 0xb8, 0x67, 0x33, 0x8d, 0x6f, 0xb2, 0xc9
+        , 0x45, 0x58, 0x40, 0xe0, 0x3e, 0x62, 0xf0, 0x83, 0xdf, 0x55, 0x76, 0x36, 0x0e, 0x60, 0xa3, 0x2e
+        , 0x22, 0xbb, 0xb0, 0x7b, 0x3c, 0x8d, 0xf4, 0xfc, 0xab, 0x80, 0x79, 0xf1, 0xd6, 0xf6, 0x1a, 0xf3
+        , 0x95, 0x4d, 0x24, 0x2b, 0xa8, 0xa0, 0x65, 0x16, 0xc3, 0x95, 0x93, 0x9f, 0x24, 0x09, 0x6f, 0x3d
+        , 0xf1, 0x4e, 0x10, 0x3a, 0x7d, 0x9c, 0x2b, 0x80, 0xa6, 0x8a, 0x93, 0x63, 0xcf, 0x1f, 0x27, 0xc7
+        , 0xa4, 0xe3, 0x07, 0x58, 0x40, 0x2a, 0x64, 0xf2, 0x99, 0xc7, 0xfe, 0x54, 0x43, 0x33, 0x89, 0xca
+        , 0x74, 0xc7, 0xcf, 0x8e, 0x2c, 0x35, 0xff, 0x46, 0x3a, 0x36, 0x12, 0xf0, 0x8c, 0xb5, 0x6a, 0xce
+        , 0xb5, 0x9e, 0xfc, 0x75, 0xdb, 0x47, 0x48, 0xad, 0x7c, 0x12, 0xf7, 0x41, 0x89, 0xfd, 0xe5, 0x73
+        , 0x69, 0x91, 0x48, 0xe0, 0xfc, 0xf9, 0x78, 0xf2, 0x91, 0xd0, 0xc1, 0xde, 0xa6, 0xf7, 0xf5, 0xd9
+        , 0x8a, 0x0c, 0xb1, 0x1b, 0x05, 0x84, 0x83, 0x00, 0x00, 0x00, 0x82, 0x6a, 0x63, 0x61, 0x72, 0x64
+        , 0x61, 0x6e, 0x6f, 0x2d, 0x73, 0x6c, 0x01, 0xa0, 0x58, 0x20, 0x4b, 0xa9, 0x2a, 0xa3, 0x20, 0xc6
+        , 0x0a, 0xcc, 0x9a, 0xd7, 0xb9, 0xa6, 0x4f, 0x2e, 0xda, 0x55, 0xc4, 0xd2, 0xec, 0x28, 0xe6, 0x04
+        , 0xfa, 0xf1, 0x86, 0x70, 0x8b, 0x4f, 0x0c, 0x4e, 0x8e, 0xdf, 0x84, 0x9f, 0x82, 0x83, 0x9f, 0x82
+        , 0x00, 0xd8, 0x18, 0x58, 0x24, 0x82, 0x58, 0x20, 0xc4, 0xc9, 0x8f, 0x60, 0xc8, 0x4b, 0x4d, 0xdc
+        , 0xc8, 0xc5, 0xee, 0xb7, 0x4d, 0xf6, 0xd0, 0xe6, 0x3a, 0xaa, 0x83, 0x61, 0x7f, 0x8d, 0x96, 0x48
+        , 0x1b, 0x42, 0x26, 0x4c, 0x73, 0x9f, 0x3e, 0x98, 0x01, 0xff, 0x9f, 0x82, 0x82, 0xd8, 0x18, 0x58
+        , 0x42, 0x83, 0x58, 0x1c, 0x6d, 0x29, 0x25, 0xff, 0x0e, 0x0c, 0xa4, 0x62, 0x21, 0xce, 0xe3, 0x9f
+        , 0xb4, 0xf5, 0x66, 0xda, 0xae, 0x8f, 0x91, 0xda, 0xe7, 0xf3, 0xa6, 0xc5, 0x1b, 0x3e, 0xb0, 0x69
+        , 0xa1, 0x01, 0x58, 0x1e, 0x58, 0x1c, 0x9c, 0xe9, 0x95, 0x51, 0x13, 0xdb, 0xdf, 0xb8, 0x1a, 0xca
+        , 0xca, 0x59, 0x07, 0x83, 0xad, 0x7d, 0x1c, 0xdd, 0x13, 0x96, 0xfe, 0x90, 0x5a, 0x32, 0x2b, 0x06
+        , 0x2e, 0x2a, 0x00, 0x1a, 0x84, 0x67, 0x11, 0xf9, 0x1b, 0x00, 0x00, 0x00, 0x02, 0x73, 0x9c, 0x7d
+        , 0x1f, 0x82, 0x82, 0xd8, 0x18, 0x58, 0x42, 0x83, 0x58, 0x1c, 0xc6, 0x41, 0xa9, 0xe5, 0x93, 0xbf
+        , 0xaf, 0x1d, 0x6c, 0x9b, 0x35, 0x31, 0x7e, 0x07, 0x37, 0x62, 0x67, 0xb8, 0xea, 0x10, 0xe3, 0x6e
+        , 0x96, 0x1a, 0x0e, 0x52, 0xee, 0x46, 0xa1, 0x01, 0x58, 0x1e, 0x58, 0x1c, 0xca, 0x3e, 0x55, 0x3c
+        , 0x9c, 0x63, 0xc5, 0x55, 0x3f, 0x4e, 0x0f, 0x43, 0xc0, 0xfb, 0x20, 0x11, 0xf9, 0xa7, 0x41, 0xfd
+        , 0xbe, 0x32, 0x4f, 0xdc, 0xdb, 0x6b, 0x6c, 0x76, 0x00, 0x1a, 0x55, 0x90, 0xb0, 0x71, 0x1b, 0x00
+        , 0x00, 0x00, 0x07, 0x73, 0x59, 0x40, 0x00, 0xff, 0xa0, 0x81, 0x82, 0x00, 0xd8, 0x18, 0x58, 0x85
+        , 0x82, 0x58, 0x40, 0x34, 0x21, 0x22, 0xd9, 0xc4, 0x24, 0x51, 0x8f, 0x35, 0x1a, 0x06, 0x68, 0x49
+        , 0xac, 0x8f, 0x7f, 0x52, 0x2f, 0x0e, 0x5c, 0xee, 0xeb, 0xb7, 0x9d, 0x5b, 0xdb, 0xd2, 0xe5, 0xc3
+        , 0xef, 0x6a, 0x81, 0xc2, 0x0a, 0x92, 0x30, 0x10, 0xf8, 0x59, 0x79, 0x13, 0x3c, 0x51, 0xa7, 0x38
+        , 0x27, 0xef, 0xa7, 0xcc, 0x36, 0xba, 0xe6, 0x30, 0x07, 0xc7, 0x31, 0xa6, 0x3d, 0xe5, 0x1c, 0xcd
+        , 0x99, 0x58, 0x97, 0x58, 0x40, 0xb9, 0x64, 0x1b, 0x8d, 0x5b, 0x6b, 0x02, 0xf9, 0x5a, 0x67, 0x7a
+        , 0x2d, 0x44, 0x0f, 0xf9, 0x42, 0xc2, 0xaf, 0xbe, 0x9c, 0x1e, 0xcf, 0x4a, 0x92, 0x11, 0x50, 0xd2
+        , 0x91, 0xf9, 0x90, 0x01, 0xc7, 0x70, 0x5d, 0x8e, 0xeb, 0x47, 0xf1, 0xb3, 0x58, 0x15, 0x9c, 0xa9
+        , 0x61, 0x37, 0x44, 0xe2, 0xae, 0xa2, 0xa6, 0xa4, 0xc3, 0x8f, 0x7b, 0xc1, 0xbd, 0xac, 0x20, 0x87
+        , 0x91, 0x66, 0xfb, 0x96, 0x0d, 0x82, 0x83, 0x9f, 0x82, 0x00, 0xd8, 0x18, 0x58, 0x24, 0x82, 0x58
+        , 0x20, 0xfe, 0xf9, 0x88, 0xb1, 0xe9, 0xcc, 0x31, 0xff, 0x29, 0xbb, 0x01, 0x67, 0x49, 0xa5, 0x43
+        , 0xf0, 0x76, 0x59, 0xad, 0x61, 0xe6, 0x77, 0x66, 0x3d, 0x9f, 0x1d, 0x75, 0xf1, 0x5e, 0xf9, 0x6c
+        , 0x9b, 0x00, 0xff, 0x9f, 0x82, 0x82, 0xd8, 0x18, 0x58, 0x42, 0x83, 0x58, 0x1c, 0xfd, 0xe8, 0xdc
+        , 0xf1, 0x23, 0xe6, 0x12, 0xcb, 0x41, 0xf5, 0x05, 0x62, 0x8c, 0x5e, 0xf2, 0x42, 0x77, 0x8d, 0x6c
+        , 0x66, 0x56, 0x35, 0xb7, 0xf6, 0x07, 0xa2, 0x6d, 0x36, 0xa1, 0x01, 0x58, 0x1e, 0x58, 0x1c, 0xca
+        , 0x3e, 0x55, 0x3c, 0x9c, 0x63, 0xc5, 0x36, 0xbd, 0x56, 0x32, 0x43, 0xdd, 0x46, 0x4b, 0x37, 0x2d
+        , 0xdf, 0xc5, 0x1e, 0x87, 0x30, 0xf5, 0x21, 0x34, 0x53, 0xd7, 0xd4, 0x00, 0x1a, 0x18, 0x27, 0xe7
+        , 0xce, 0x1b, 0x00, 0x00, 0x44, 0x2a, 0x3d, 0x31, 0x48, 0xb6, 0x82, 0x82, 0xd8, 0x18, 0x58, 0x42
+        , 0x83, 0x58, 0x1c, 0xa4, 0x61, 0x94, 0x57, 0xa8, 0x82, 0x5f, 0x2c, 0x60, 0x30, 0x3d, 0xcb, 0xe1
+        , 0x0e, 0x37, 0xed, 0x72, 0xa2, 0x14, 0xd7, 0x16, 0xd0, 0x50, 0xe4, 0xc4, 0x38, 0x94, 0x5c, 0xa1
+        , 0x01, 0x58, 0x1e, 0x58, 0x1c, 0xca, 0x3e, 0x55, 0x3c, 0x9c, 0x63, 0xc5, 0x7f, 0xc4, 0x22, 0x22
+        , 0x43, 0x74, 0x6b, 0x3a, 0x5f, 0x31, 0xc8, 0xf7, 0x4d, 0x55, 0x07, 0x38, 0x15, 0x42, 0xf6, 0x7f
+        , 0x7f, 0x00, 0x1a, 0xc4, 0x45, 0x9d, 0x50, 0x1a, 0x00, 0x95, 0x89, 0x40, 0xff, 0xa0, 0x81, 0x82
+        , 0x00, 0xd8, 0x18, 0x58, 0x85, 0x82, 0x58, 0x40, 0x9b, 0xb8, 0x4a, 0x56, 0xad, 0x61, 0xd0, 0xdf
+        , 0xd6, 0x04, 0x7e, 0xca, 0x46, 0x3b, 0x6e, 0x69, 0x1a, 0x8b, 0xe8, 0xdc, 0x06, 0x4d, 0x00, 0x4e
+        , 0x5c, 0x9b, 0x79, 0x75, 0x21, 0x55, 0xb6, 0x79, 0x0a, 0xa7, 0x9c, 0xca, 0xef, 0xb0, 0x4c, 0xab
+        , 0x5f, 0x63, 0x6c, 0xd4, 0x8f, 0x7f, 0x93, 0x95, 0x92, 0x6d, 0x56, 0x5f, 0xe7, 0x7f, 0xd7, 0x24
+        , 0xc5, 0xed, 0xe7, 0xdc, 0x3e, 0x23, 0x96, 0xdc, 0x58, 0x40, 0x23, 0x75, 0x25, 0x30, 0xbe, 0x6a
+        , 0x66, 0xef, 0xb9, 0xc4, 0x64, 0x76, 0xb9, 0x2b, 0x7f, 0xc9, 0x76, 0x9b, 0xb4, 0x2d, 0x33, 0xd2
+        , 0x16, 0x8a, 0xbf, 0xeb, 0x2a, 0xc2, 0x58, 0xf9, 0x32, 0x3f, 0xb3, 0x51, 0x3c, 0x98, 0x2a, 0x0d
+        , 0x4e, 0x83, 0x9c, 0xe2, 0x96, 0x12, 0xa5, 0x6e, 0xa8, 0xac, 0xa6, 0x37, 0xa9, 0x0d, 0x87, 0x63
+        , 0x5d, 0xd9, 0x25, 0xfe, 0x1d, 0x6e, 0x95, 0xe4, 0x6b, 0x02, 0x82, 0x83, 0x9f, 0x82, 0x00, 0xd8
+        , 0x18, 0x58, 0x24, 0x82, 0x58, 0x20, 0xc7, 0xe7, 0x01, 0x5c, 0xfa, 0x4b, 0x44, 0x12, 0xe0, 0xb9
+        , 0x34, 0xea, 0xcc, 0x9d, 0xa7, 0x01, 0xa0, 0xb5, 0x9a, 0xed, 0xf2, 0x82, 0x29, 0x2b, 0x4d, 0x2f
+        , 0xa4, 0x2d, 0x9e, 0x70, 0x7a, 0x61, 0x00, 0xff, 0x9f, 0x82, 0x82, 0xd8, 0x18, 0x58, 0x42, 0x83
+        , 0x58, 0x1c, 0xa3, 0xda, 0x05, 0x6f, 0xf5, 0xc2, 0x08, 0x0e, 0x65, 0x32, 0x22, 0x1f, 0x1d, 0x73
+        , 0x29, 0xda, 0x2d, 0x35, 0x68, 0xa1, 0x41, 0x6f, 0x5d, 0x9d, 0xdc, 0x58, 0x32, 0x77, 0xa1, 0x01
+        , 0x58, 0x1e, 0x58, 0x1c, 0xd4, 0xd6, 0x64, 0x57, 0xf7, 0xe6, 0x89, 0x12, 0xe9, 0x0e, 0x43, 0x53
+        , 0xf9, 0x48, 0xf3, 0x6e, 0xcb, 0xcc, 0x22, 0x67, 0x49, 0x96, 0xb9, 0xb2, 0x8f, 0x80, 0x6b, 0x4e
+        , 0x00, 0x1a, 0xb5, 0xb3, 0x0d, 0x63, 0x1b, 0x00, 0x00, 0x00, 0x4b, 0x31, 0x8e, 0xf6, 0x80, 0x82
+        , 0x82, 0xd8, 0x18, 0x58, 0x42, 0x83, 0x58, 0x1c, 0x06, 0xfb, 0x4f, 0xb5, 0xc0, 0x95, 0x50, 0xe5
+        , 0x36, 0x4c, 0xd6, 0x5e, 0x24, 0x6f, 0x6e, 0x15, 0x47, 0xc9, 0x4b, 0x0c, 0xb6, 0xf4, 0x54, 0xff
+        , 0xfd, 0xaa, 0x7c, 0x18, 0xa1, 0x01, 0x58, 0x1e, 0x58, 0x1c, 0xca, 0x3e, 0x55, 0x3c, 0x9c, 0x63
+        , 0xc5, 0x78, 0xa5, 0xd6, 0x52, 0x43, 0x49, 0xf7, 0x7b, 0x6a, 0xa4, 0xb7, 0x5e, 0x05, 0xbc, 0xc6
+        , 0x2d, 0x4f, 0x9c, 0x04, 0x43, 0x3e, 0x00, 0x1a, 0x7d, 0x33, 0xd6, 0xb8, 0x1b, 0x00, 0x00, 0x01
+        , 0x1b, 0x0f, 0x9f, 0x15, 0x92, 0xff, 0xa0, 0x81, 0x82, 0x00, 0xd8, 0x18, 0x58, 0x85, 0x82, 0x58
+        , 0x40, 0x26, 0x59, 0xb6, 0xc9, 0xa2, 0x67, 0x3b, 0x51, 0xea, 0x12, 0x61, 0x66, 0xf6, 0xe8, 0x2d
+        , 0x7f, 0xdd, 0x3f, 0xb6, 0x24, 0xc1, 0xb1, 0x73, 0x54, 0xc9, 0xac, 0xf5, 0x2b, 0x72, 0xa1, 0x50
+        , 0xc5, 0x66, 0x8b, 0x74, 0xbe, 0xf0, 0xa3, 0xeb, 0x10, 0x3d, 0xbe, 0x76, 0x0c, 0x2b, 0x81, 0x6d
+        , 0xee, 0x77, 0x03, 0x4e, 0x69, 0xc5, 0x14, 0x1e, 0xba, 0x70, 0x9e, 0x18, 0x01, 0x1b, 0xd0, 0xf0
+        , 0xc9, 0x58, 0x40, 0x32, 0x28, 0x26, 0xe7, 0x57, 0x59, 0x26, 0xce, 0x95, 0x54, 0x8a, 0x0c, 0xce
+        , 0xe9, 0x92, 0x9c, 0x3c, 0x27, 0x06, 0x6f, 0x14, 0xb1, 0xb9, 0x19, 0x91, 0x87, 0x41, 0x2e, 0x99
+        , 0xce, 0xb7, 0x8d, 0x48, 0xdf, 0xd3, 0x9a, 0x58, 0xbb, 0xf6, 0x54, 0xaa, 0x36, 0x7c, 0x54, 0x74
+        , 0x90, 0x82, 0x28, 0xed, 0xfe, 0x79, 0x6c, 0xd4, 0xf2, 0xb1, 0xd5, 0xa2, 0x96, 0x22, 0x01, 0x91
+        , 0xdc, 0xe5, 0x01, 0xff, 0x82, 0x03, 0xd9, 0x01, 0x02, 0x80, 0x9f, 0xff, 0x82, 0x80, 0x9f, 0xff
+        , 0x81, 0xa0
+    ];
+
     #[test]
     fn parse_get_block_headers_response() {
         let b = RawCbor::from(GET_BLOCK_HEADER_BYTES).deserialize().unwrap();
@@ -333,6 +490,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_block_response() {
+        let BlockResponse::Ok(blk) = RawCbor::from(BLOCK_RESPONSE_BYTES).deserialize().unwrap();
+        match blk {
+            block::Block::MainBlock(blk) => assert_eq!(blk.body.tx.iter().count(), 3),
+            blk => panic!("expected a main block, got {:?}", blk),
+        }
+    }
+
+    #[test]
+    fn announce_tx_contains_the_submitted_tx() {
+        let tx = tx::TxAux::new(tx::Tx::new(), vec![]);
+        let (msg_type, dat) = send_msg_announce_tx(&tx);
+        assert_eq!(msg_type, MsgType::MsgAnnounceTx as u8);
+
+        let mut raw = RawCbor::from(&dat);
+        assert_eq!(raw.array().unwrap(), cbor_event::Len::Len(2));
+        assert_eq!(raw.unsigned_integer().unwrap(), 1);
+        assert_eq!(&dat[2..], tx.to_submit_bytes().as_slice());
+        let decoded : tx::TxAux = raw.deserialize().unwrap();
+        assert_eq!(decoded, tx);
+    }
+
+    // the peer only accepts the messages declared in our handshake
+    #[test]
+    fn announce_tx_is_a_declared_out_message() {
+        let outs = HandlerSpecs::default_outs();
+        assert!(outs.0.contains_key(&(MsgType::MsgAnnounceTx as MessageCode)));
+    }
+
+    // the protocol does not have its own block types, the headers are
+    // decoded directly as `cardano::block::BlockHeader` and can be stored
+    // or re-encoded without any conversion
+    #[test]
+    fn block_headers_response_are_blockchain_headers() {
+        let b = RawCbor::from(GET_BLOCK_HEADER_BYTES).deserialize().unwrap();
+        let header : block::BlockHeader = match b {
+            BlockHeaderResponse::Ok(mut ll) => ll.pop().unwrap(),
+            BlockHeaderResponse::Err(error) => panic!("test failed: {}", error)
+        };
+        // the header is the one embedded in the response (without the
+        // response's sum type and the indefinite array wrapping it)
+        let raw = header.to_raw();
+        assert_eq!(raw.as_ref(), &GET_BLOCK_HEADER_BYTES[3..GET_BLOCK_HEADER_BYTES.len() - 1]);
+
+        let decoded = raw.decode().unwrap();
+        assert_eq!(decoded.compute_hash(), header.compute_hash());
+        assert_eq!(decoded.get_previous_header(), header.get_previous_header());
+        assert_eq!(decoded.get_blockdate(), header.get_blockdate());
+    }
+
+    // genesis header of the epoch 1, chain difficulty 0
+    const GENESIS_HEADER_BYTES : &'static [u8] = &[
+        0x85, 0x00, 0x58, 0x20, 0xc4, 0xe0, 0xfc, 0x3a, 0x4f, 0xfb, 0x31, 0x91, 0xf8, 0x8b, 0x26, 0xa9
+      , 0x83, 0x44, 0x53, 0xcb, 0xac, 0x0e, 0x6b, 0x9c, 0x8d, 0x8f, 0x7a, 0xe8, 0x10, 0x69, 0x6b, 0xee
+      , 0x57, 0x5d, 0x1d, 0x22, 0x58, 0x20, 0xc4, 0xe0, 0xfc, 0x3a, 0x4f, 0xfb, 0x31, 0x91, 0xf8, 0x8b
+      , 0x26, 0xa9, 0x83, 0x44, 0x53, 0xcb, 0xac, 0x0e, 0x6b, 0x9c, 0x8d, 0x8f, 0x7a, 0xe8, 0x10, 0x69
+      , 0x6b, 0xee, 0x57, 0x5d, 0x1d, 0x22, 0x82, 0x01, 0x81, 0x00, 0x81, 0xa0
+    ];
+
+    #[test]
+    fn block_headers_response_into_oldest_first() {
+        let difficulty = |d: u8| RawCbor::from(&[0x81, d][..]).deserialize().unwrap();
+        let main_header = match RawCbor::from(GET_BLOCK_HEADER_BYTES).deserialize().unwrap() {
+            BlockHeaderResponse::Ok(mut ll) => match ll.pop().unwrap() {
+                block::BlockHeader::MainBlockHeader(header) => header,
+                header => panic!("expected a main block header, got {:?}", header),
+            },
+            BlockHeaderResponse::Err(error) => panic!("test failed: {}", error)
+        };
+        let epoch = main_header.consensus.slot_id.epoch;
+
+        // main blocks of difficulty 1 to 3 in the slots 10 to 12, then the
+        // genesis block of the next epoch with the same difficulty as the
+        // last main block
+        let mut expected = vec![];
+        for i in 1..4 {
+            let mut header = main_header.clone();
+            header.consensus.chain_difficulty = difficulty(i);
+            header.consensus.slot_id.slotid = 9 + i as u32;
+            expected.push(block::BlockHeader::MainBlockHeader(header));
+        }
+        let mut genesis : block::genesis::BlockHeader = RawCbor::from(GENESIS_HEADER_BYTES).deserialize().unwrap();
+        genesis.consensus.epoch = epoch + 1;
+        genesis.consensus.chain_difficulty = difficulty(3);
+        expected.push(block::BlockHeader::GenesisBlockHeader(genesis));
+
+        let mut received = expected.clone();
+        received.reverse();
+        let headers = BlockHeaderResponse::Ok(received).into_oldest_first().unwrap();
+        assert_eq!(headers.iter().map(|h| h.get_blockdate()).collect::<Vec<_>>(),
+                   expected.iter().map(|h| h.get_blockdate()).collect::<Vec<_>>());
+
+        assert_eq!(BlockHeaderResponse::Err("oops".to_owned()).into_oldest_first().unwrap_err(), "oops");
+    }
+
     const HANDSHAKE_BYTES : &'static [u8] = &[
         0x84, 0x1a, 0x2d, 0x96, 0x4a, 0x09, 0x83, 0x00
       , 0x01, 0x00, 0xb3, 0x04, 0x82, 0x00, 0xd8, 0x18, 0x41, 0x05, 0x05, 0x82, 0x00, 0xd8, 0x18, 0x41
@@ -370,6 +623,43 @@ mod tests {
         assert_eq!(hs, hs_);
     }
 
+    #[test]
+    fn handshake_response_same_magic() {
+        let ack = parse_handshake_response(HANDSHAKE_BYTES, &Handshake::default()).unwrap();
+        assert_eq!(ack, HandshakeAck { protocol_magic: ProtocolMagic::default(), version: block::Version::default() });
+    }
+
+    #[test]
+    fn handshake_response_mismatched_magic() {
+        let mut hs = Handshake::default();
+        hs.protocol_magic = ProtocolMagic::new(633343913);
+        match parse_handshake_response(HANDSHAKE_BYTES, &hs) {
+            Err(::Error::ProtocolMagicMismatch(ours, theirs)) => {
+                assert_eq!(ours, hs.protocol_magic);
+                assert_eq!(theirs, ProtocolMagic::default());
+            },
+            r => panic!("expected a protocol magic mismatch, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn handshake_response_mismatched_version() {
+        let mut hs = Handshake::default();
+        hs.version = block::Version::new(1, 0, 0);
+        match parse_handshake_response(HANDSHAKE_BYTES, &hs) {
+            Err(::Error::VersionMismatch(_, theirs)) => assert_eq!(theirs, block::Version::default()),
+            r => panic!("expected a version mismatch, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn handshake_response_truncated() {
+        match parse_handshake_response(&HANDSHAKE_BYTES[..HANDSHAKE_BYTES.len() - 10], &Handshake::default()) {
+            Err(::Error::ByteEncodingError(_)) => {},
+            r => panic!("expected a decoding error, got {:?}", r),
+        }
+    }
+
     #[test]
     fn handshake_encoding() {
         let hs = Handshake::default();
@@ -378,3 +668,29 @@ mod tests {
         assert_eq!(HANDSHAKE_BYTES, vec.as_slice());
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "with-bench")]
+mod bench {
+    use super::*;
+    use super::tests::{GET_BLOCK_HEADER_BYTES, BLOCK_RESPONSE_BYTES};
+    use cbor_event::de::RawCbor;
+    use test;
+
+    // one block per iteration: the blocks/sec are `1_000_000_000 / ns/iter`
+    #[bench]
+    fn decode_block_headers_response(b: &mut test::Bencher) {
+        b.bytes = GET_BLOCK_HEADER_BYTES.len() as u64;
+        b.iter(|| {
+            let _ : BlockHeaderResponse = RawCbor::from(GET_BLOCK_HEADER_BYTES).deserialize().unwrap();
+        })
+    }
+
+    #[bench]
+    fn decode_block_response(b: &mut test::Bencher) {
+        b.bytes = BLOCK_RESPONSE_BYTES.len() as u64;
+        b.iter(|| {
+            let _ : BlockResponse = RawCbor::from(BLOCK_RESPONSE_BYTES).deserialize().unwrap();
+        })
+    }
+}
@@ -3,7 +3,6 @@ use std::io::{Read, Write};
 use std::{io, fmt, result};
 
 use packet;
-use packet::{Handshake};
 use ntt;
 
 use cardano;
@@ -19,6 +18,16 @@ pub enum Error {
     UnsupportedControl(ntt::protocol::ControlHeader),
     NodeIdNotFound(ntt::protocol::NodeId),
     ClientIdNotFoundFromNodeId(ntt::protocol::NodeId, LightId),
+    /// the remote answered a command with something we did not expect
+    UnexpectedResponse,
+    /// the remote answered a command with an error message
+    ServerError(String),
+    /// the remote did not return any block for the requested hash
+    BlockNotFound(cardano::block::HeaderHash),
+    /// the remote is on another network (ours, theirs)
+    ProtocolMagicMismatch(cardano::config::ProtocolMagic, cardano::config::ProtocolMagic),
+    /// the remote speaks an incompatible version (ours, theirs)
+    VersionMismatch(cardano::block::Version, cardano::block::Version),
 }
 impl From<cbor_event::Error> for Error {
     fn from(e: cbor_event::Error) -> Self { Error::ByteEncodingError(e) }
@@ -190,7 +199,7 @@ impl<T: Write+Read> Connection<T> {
          */
         let siv = match self.ntt.recv()? {
             Command::Control(ControlHeader::CreateNewConnection, cid) => { LightId::new(cid) },
-            _ => { unimplemented!() }
+            _ => { return Err(Error::UnexpectedResponse) }
         };
 
         fn data_recv_on<T: Read+Write>(con: &mut Connection<T>, expected_id: LightId) -> Result<Vec<u8>> {
@@ -200,20 +209,21 @@ impl<T: Write+Read> Connection<T> {
                         let bytes = con.ntt.recv_len(len)?;
                         Ok(bytes)
                     } else {
-                        unimplemented!()
+                        Err(Error::UnexpectedResponse)
                     }
                 }
-                _ => { unimplemented!() }
+                _ => { Err(Error::UnexpectedResponse) }
             }
         };
 
         info!("creating initial light connection {}", lcid);
         let server_bytes_hs = data_recv_on(self, siv)?;
-        let _server_handshake : Handshake = RawCbor::from(&server_bytes_hs).deserialize()?;
+        let server_handshake = packet::parse_handshake_response(&server_bytes_hs, hs)?;
+        debug!("server handshake: protocol magic {}, version {}", server_handshake.protocol_magic, server_handshake.version);
 
         let server_bytes_nodeid = data_recv_on(self, siv)?;
         let server_nodeid = match ntt::protocol::NodeId::from_slice(&server_bytes_nodeid[..]) {
-            None   => unimplemented!(),
+            None   => return Err(Error::UnexpectedResponse),
             Some(nodeid) => nodeid,
         };
 
@@ -264,20 +274,27 @@ impl<T: Write+Read> Connection<T> {
     // same as wait_msg, except returns a vector of result
     pub fn wait_msg_eos(&mut self, id: LightId) -> Result<Vec<Vec<u8>>> {
         let mut r = Vec::new();
-        loop {
-            while !self.has_bytes_to_read_or_finish(id) {
-                self.process_message()?;
-            }
+        while let Some(yy) = self.wait_msg_next(id)? {
+            r.push(yy)
+        }
+        Ok(r)
+    }
 
-            match self.client_cons.get_mut(&id) {
-                None => panic!("oops"),
-                Some(ref mut con) => {
-                    match con.pop_received() {
-                        None => { if con.eos { return Ok(r) } else { panic!("oops 2") } },
-                        Some(yy) => r.push(yy),
-                    }
-                },
-            }
+    // same as wait_msg, except returns None once the remote has closed
+    // the light connection (end of stream)
+    pub fn wait_msg_next(&mut self, id: LightId) -> Result<Option<Vec<u8>>> {
+        while !self.has_bytes_to_read_or_finish(id) {
+            self.process_message()?;
+        }
+
+        match self.client_cons.get_mut(&id) {
+            None => panic!("oops"),
+            Some(ref mut con) => {
+                match con.pop_received() {
+                    None => { if con.eos { Ok(None) } else { panic!("oops 2") } },
+                    Some(yy) => Ok(Some(yy)),
+                }
+            },
         }
     }
 
@@ -452,8 +469,8 @@ impl<T: Write+Read> Connection<T> {
 
         // FIXME: use keep-alive?
         let (subscribe_id, subscribe_dat) = packet::send_msg_subscribe(false);
-        self.send_bytes(id, &[subscribe_id]).unwrap();
-        self.send_bytes(id, &subscribe_dat[..]).unwrap();
+        self.send_bytes(id, &[subscribe_id])?;
+        self.send_bytes(id, &subscribe_dat[..])?;
 
         Ok(())
     }
@@ -487,25 +504,26 @@ impl<T: Write+Read> Connection<T> {
 }
 
 pub mod command {
-    use std::io::{Read, Write};
-    use super::{LightId, Connection};
+    use std::io::{self, Read, Write};
+    use std::cmp;
+    use super::{LightId, Connection, Error, Result};
     use cardano;
     use packet;
-    use cbor_event::de::RawCbor;
+    use cbor_event::{self, de::RawCbor};
 
     pub trait Command<W: Read+Write> {
         type Output;
-        fn command(&self, connection: &mut Connection<W>, id: LightId) -> Result<(), &'static str>;
-        fn result(&self, connection: &mut Connection<W>, id: LightId) -> Result<Self::Output, String>;
+        fn command(&self, connection: &mut Connection<W>, id: LightId) -> Result<()>;
+        fn result(&self, connection: &mut Connection<W>, id: LightId) -> Result<Self::Output>;
 
-        fn initial(&self, connection: &mut Connection<W>) -> Result<LightId, &'static str> {
+        fn initial(&self, connection: &mut Connection<W>) -> Result<LightId> {
             let id = connection.get_free_light_id();
             trace!("creating light connection: {}", id);
 
-            connection.new_light_connection(id).unwrap();
+            connection.new_light_connection(id)?;
             Ok(id)
         }
-        fn execute(&self, connection: &mut Connection<W>) -> Result<Self::Output, String> {
+        fn execute(&self, connection: &mut Connection<W>) -> Result<Self::Output> {
             let id = Command::initial(self, connection)?;
 
             Command::command(self, connection, id)?;
@@ -515,7 +533,7 @@ pub mod command {
 
             Ok(ret)
         }
-        fn terminate(&self, connection: &mut Connection<W>, id: LightId) -> Result<(), &'static str> {
+        fn terminate(&self, connection: &mut Connection<W>, id: LightId) -> Result<()> {
             connection.close_light_connection(id);
             Ok(())
         }
@@ -524,43 +542,88 @@ pub mod command {
     #[derive(Debug)]
     pub struct GetBlockHeader {
         from: Vec<cardano::block::HeaderHash>,
-        to: Option<cardano::block::HeaderHash>
+        to: Option<cardano::block::HeaderHash>,
+        max: Option<usize>,
     }
     impl GetBlockHeader {
-        pub fn tip() -> Self { GetBlockHeader { from: vec![], to: None } }
-        pub fn range(from: &[cardano::block::HeaderHash], to: cardano::block::HeaderHash) -> Self {
+        pub fn tip() -> Self { GetBlockHeader { from: vec![], to: None, max: None } }
+
+        /// request the headers from the first known hash of `from` to `to`,
+        /// keeping at most `max` of them (see `cap`).
+        pub fn range(from: &[cardano::block::HeaderHash], to: cardano::block::HeaderHash, max: usize) -> Self {
             let mut vec = Vec::new();
             for f in from.iter() {
                 vec.push(f.clone());
             }
-            GetBlockHeader { from: vec, to: Some(to) }
+            GetBlockHeader { from: vec, to: Some(to), max: Some(max) }
+        }
+
+        /// the node answers with up to a limit of its own, without telling
+        /// whether the answer is complete. Keep the `max` oldest of the
+        /// received headers (newest first, as received) so the windows
+        /// are of a known size.
+        pub fn cap(&self, mut headers: Vec<cardano::block::BlockHeader>) -> Vec<cardano::block::BlockHeader> {
+            if let Some(max) = self.max {
+                if headers.len() > max {
+                    let newest = headers.len() - max;
+                    headers.drain(..newest);
+                }
+            }
+            headers
         }
     }
 
+    /// get the headers of `(from, to]`, oldest first, requesting them in
+    /// windows of at most `max` headers.
+    ///
+    /// `request` performs one request, e.g. `|cmd| cmd.execute(&mut connection)`.
+    pub fn get_headers_range<F>( from: &cardano::block::HeaderHash
+                               , to: &cardano::block::HeaderHash
+                               , max: usize
+                               , mut request: F
+                               ) -> Result<Vec<cardano::block::BlockHeader>>
+        where F: FnMut(&GetBlockHeader) -> Result<cardano::block::RawBlockHeaderMultiple>
+    {
+        let mut headers = Vec::new();
+        let mut cursor = from.clone();
+        while cursor != *to {
+            let cmd = GetBlockHeader::range(&[cursor.clone()], to.clone(), max);
+            let mut window = cmd.cap(request(&cmd)?.decode()?);
+            window.reverse();
+            match window.last() {
+                // no progress, the node does not know `to`
+                None => return Err(Error::UnexpectedResponse),
+                Some(header) => cursor = header.compute_hash(),
+            }
+            headers.extend(window);
+        }
+        Ok(headers)
+    }
+
     impl<W> Command<W> for GetBlockHeader where W: Read+Write {
         type Output = cardano::block::RawBlockHeaderMultiple;
-        fn command(&self, connection: &mut Connection<W>, id: LightId) -> Result<(), &'static str> {
+        fn command(&self, connection: &mut Connection<W>, id: LightId) -> Result<()> {
             let (get_header_id, get_header_dat) = packet::send_msg_getheaders(&self.from[..], &self.to);
-            connection.send_bytes(id, &[get_header_id]).unwrap();
-            connection.send_bytes(id, &get_header_dat[..]).unwrap();
+            connection.send_bytes(id, &[get_header_id])?;
+            connection.send_bytes(id, &get_header_dat[..])?;
             Ok(())
         }
-        fn result(&self, connection: &mut Connection<W>, id: LightId) -> Result<Self::Output, String> {
+        fn result(&self, connection: &mut Connection<W>, id: LightId) -> Result<Self::Output> {
             // require the initial header
-            let dat = connection.wait_msg(id).unwrap();
+            let dat = connection.wait_msg(id)?;
             match decode_sum_type(&dat) {
-                None => Err(String::from("message block decoder failed with something unexpected")),
+                None => Err(Error::UnexpectedResponse),
                 Some((0, dat)) => {
                     let mut v = Vec::new();
                     v.extend_from_slice(dat);
                     Ok(cardano::block::RawBlockHeaderMultiple::from_dat(v))
                 },
                 Some((1, dat)) => {
-                    Err(format!("server returned an error for GetHeaders: {}",
-                                RawCbor::from(dat).text().unwrap()))
+                    Err(Error::ServerError(format!("server returned an error for GetHeaders: {}",
+                                                   RawCbor::from(dat).text()?)))
                 },
                 Some((_n, _dat)) => {
-                    Err(String::from("message block decoder failed with something unexpected"))
+                    Err(Error::UnexpectedResponse)
                 }
             }
         }
@@ -574,19 +637,105 @@ pub mod command {
     impl GetBlock {
         pub fn only(hh: &cardano::block::HeaderHash) -> Self { GetBlock::from(&hh.clone(), &hh.clone()) }
         pub fn from(from: &cardano::block::HeaderHash, to: &cardano::block::HeaderHash) -> Self { GetBlock { from: from.clone(), to: to.clone() } }
+
+        /// same as `execute` but the blocks are returned one by one as
+        /// they are received instead of being collected first.
+        pub fn stream<'a, W>(&self, connection: &'a mut Connection<W>) -> Result<BlockStream<'a, W>>
+            where W: Read+Write
+        {
+            let id = Command::initial(self, connection)?;
+            Command::command(self, connection, id)?;
+            let reader = LightReader { connection, id, msg: Vec::new(), pos: 0, error: None };
+            Ok(BlockStream { reader: cbor_event::Reader::new(reader), to: self.to.clone(), received: 0, finished: false })
+        }
+    }
+
+    /// `io::Read` over the data received on a light connection, until the
+    /// remote closes it.
+    struct LightReader<'a, W: 'a+Read+Write> {
+        connection: &'a mut Connection<W>,
+        id: LightId,
+        msg: Vec<u8>,
+        pos: usize,
+        // the error of the connection, an `io::Error` only has its description
+        error: Option<Error>,
+    }
+    impl<'a, W: 'a+Read+Write> Read for LightReader<'a, W> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            while self.pos == self.msg.len() {
+                match self.connection.wait_msg_next(self.id) {
+                    Err(err) => {
+                        let io_err = io::Error::new(io::ErrorKind::Other, format!("{:?}", err));
+                        self.error = Some(err);
+                        return Err(io_err);
+                    },
+                    Ok(None) => return Ok(0),
+                    Ok(Some(msg)) => { self.msg = msg; self.pos = 0; },
+                }
+            }
+            let len = cmp::min(buf.len(), self.msg.len() - self.pos);
+            buf[..len].copy_from_slice(&self.msg[self.pos..self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        }
     }
 
-    fn strip_msg_response(msg: &[u8]) -> Result<cardano::block::RawBlock, &'static str> {
+    /// iterator over the blocks answered to a `GetBlock` request, see
+    /// `GetBlock::stream`.
+    ///
+    /// The blocks are decoded from the connection's data as it comes in,
+    /// so a block does not need to be received in a single message. The
+    /// light connection is closed once all the blocks are received.
+    pub struct BlockStream<'a, W: 'a+Read+Write> {
+        reader: cbor_event::Reader<LightReader<'a, W>>,
+        to: cardano::block::HeaderHash,
+        received: usize,
+        finished: bool,
+    }
+    impl<'a, W: 'a+Read+Write> Iterator for BlockStream<'a, W> {
+        type Item = Result<cardano::block::RawBlock>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.finished { return None; }
+            match next_block(&mut self.reader) {
+                Err(err) => {
+                    self.finished = true;
+                    Some(Err(self.reader.get_mut().error.take().unwrap_or(err)))
+                },
+                Ok(None) => {
+                    self.finished = true;
+                    let light = self.reader.get_mut();
+                    light.connection.close_light_connection(light.id);
+                    // same as `blocks_from_responses`
+                    if self.received == 0 { Some(Err(Error::BlockNotFound(self.to.clone()))) } else { None }
+                },
+                Ok(Some(block)) => {
+                    self.received += 1;
+                    Some(Ok(block))
+                },
+            }
+        }
+    }
+
+    // read the next `GetBlock` response, `None` at the end of the stream
+    fn next_block<R: Read>(reader: &mut cbor_event::Reader<R>) -> Result<Option<cardano::block::RawBlock>> {
+        match reader.next_raw()? {
+            None => Ok(None),
+            Some(msg) => strip_msg_response(&msg[..]).map(Some),
+        }
+    }
+
+    fn strip_msg_response(msg: &[u8]) -> Result<cardano::block::RawBlock> {
         // here we unwrap the CBOR of Array(2, [uint(0), something]) to something
         match decode_sum_type(msg) {
-            None => Err("message block decoder failed with something unexpected"),
+            None => Err(Error::UnexpectedResponse),
             Some((sumval, dat)) => {
                 if sumval == 0 {
                     let mut v = Vec::new();
                     v.extend_from_slice(dat);
                     Ok(cardano::block::RawBlock::from_dat(v))
                 } else {
-                    Err("message block decoder failed with something unexpected")
+                    Err(Error::UnexpectedResponse)
                 }
             },
         }
@@ -594,25 +743,34 @@ pub mod command {
 
     impl<W> Command<W> for GetBlock where W: Read+Write {
         type Output = Vec<cardano::block::RawBlock>;
-        fn command(&self, connection: &mut Connection<W>, id: LightId) -> Result<(), &'static str> {
+        fn command(&self, connection: &mut Connection<W>, id: LightId) -> Result<()> {
             // require the initial header
             let (get_header_id, get_header_dat) = packet::send_msg_getblocks(&self.from, &self.to);
-            connection.send_bytes(id, &[get_header_id]).unwrap();
-            connection.send_bytes(id, &get_header_dat[..]).unwrap();
+            connection.send_bytes(id, &[get_header_id])?;
+            connection.send_bytes(id, &get_header_dat[..])?;
             Ok(())
         }
 
-        fn result(&self, connection: &mut Connection<W>, id: LightId) -> Result<Self::Output, String> {
-            let msg_response = connection.wait_msg_eos(id).unwrap();
-            let mut msgs = Vec::new();
-            for response in msg_response.iter() {
-                let msg = strip_msg_response(&response[..])?;
-                msgs.push(msg)
-            }
-            Ok(msgs)
+        fn result(&self, connection: &mut Connection<W>, id: LightId) -> Result<Self::Output> {
+            let msg_response = connection.wait_msg_eos(id)?;
+            blocks_from_responses(&self.to, &msg_response)
         }
     }
 
+    /// the node answers with no block at all if it does not know the
+    /// requested hash, return an error instead of an empty list.
+    fn blocks_from_responses(to: &cardano::block::HeaderHash, responses: &[Vec<u8>]) -> Result<Vec<cardano::block::RawBlock>> {
+        if responses.is_empty() {
+            return Err(Error::BlockNotFound(to.clone()));
+        }
+        let mut msgs = Vec::new();
+        for response in responses.iter() {
+            let msg = strip_msg_response(&response[..])?;
+            msgs.push(msg)
+        }
+        Ok(msgs)
+    }
+
     // FIXME: use cardano::decode_sum_type().
     fn decode_sum_type(input: &[u8]) -> Option<(u8, &[u8])> {
         if input.len() > 2 && input[0] == 0x82 && input[1] < 23 {
@@ -621,4 +779,98 @@ pub mod command {
             None
         }
     }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn get_block_not_found() {
+            let hash = cardano::block::HeaderHash::new(b"unknown block");
+            match blocks_from_responses(&hash, &[]) {
+                Err(Error::BlockNotFound(h)) => assert_eq!(h, hash),
+                result => panic!("expected block not found, got {:?}", result),
+            }
+        }
+
+        #[test]
+        fn get_block_strips_the_responses() {
+            let hash = cardano::block::HeaderHash::new(b"block");
+            let blocks = blocks_from_responses(&hash, &[vec![0x82, 0x00, 0x80]]).unwrap();
+            assert_eq!(blocks.len(), 1);
+            assert_eq!(blocks[0].as_ref(), &[0x80]);
+        }
+
+        // a chain of `n` genesis headers, child of the `genesis` hash
+        fn chain(genesis: &cardano::block::HeaderHash, n: u32) -> Vec<cardano::block::BlockHeader> {
+            let mut headers = Vec::new();
+            let mut prev = genesis.clone();
+            for epoch in 0..n {
+                let header = cardano::testing::genesis_block(epoch, 0, &prev).decode().unwrap().get_header();
+                prev = header.compute_hash();
+                headers.push(header);
+            }
+            headers
+        }
+
+        #[test]
+        fn get_headers_range_in_windows() {
+            let genesis = cardano::block::HeaderHash::new(b"genesis");
+            let headers = chain(&genesis, 10);
+            let to = headers[9].compute_hash();
+
+            // the node answers with up to 4 headers after `from`, newest first
+            let mut requests = 0;
+            let got = get_headers_range(&genesis, &to, 3, |cmd| {
+                requests += 1;
+                let start = match headers.iter().position(|h| h.compute_hash() == cmd.from[0]) {
+                    None => 0,
+                    Some(i) => i + 1,
+                };
+                let mut answer : Vec<_> = headers[start..::std::cmp::min(start + 4, headers.len())].to_vec();
+                answer.reverse();
+                let bytes = cbor_event::se::serialize_fixed_array(answer.iter(), cbor_event::se::Serializer::new_vec()).unwrap().finalize();
+                Ok(cardano::block::RawBlockHeaderMultiple::from_dat(bytes))
+            }).unwrap();
+
+            // windows of 3, 3, 3 and 1 headers
+            assert_eq!(requests, 4);
+            assert_eq!(got.iter().map(|h| h.compute_hash()).collect::<Vec<_>>(),
+                       headers.iter().map(|h| h.compute_hash()).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn get_headers_range_unknown_to() {
+            let genesis = cardano::block::HeaderHash::new(b"genesis");
+            let to = cardano::block::HeaderHash::new(b"unknown");
+            match get_headers_range(&genesis, &to, 3, |_| Ok(cardano::block::RawBlockHeaderMultiple::from_dat(vec![0x80]))) {
+                Err(Error::UnexpectedResponse) => {},
+                r => panic!("expected an unexpected response, got {:?}", r),
+            }
+        }
+
+        #[test]
+        fn get_block_stream_decodes_concatenated_responses() {
+            // responses `[0, block]` split at arbitrary boundaries
+            let responses = [0x82, 0x00, 0x82, 0x01, 0x80,
+                             0x82, 0x00, 0x83, 0x02, 0x41, 0xff, 0x9f, 0xff,
+                             0x82, 0x00, 0x80];
+            let mut reader = cbor_event::Reader::new(io::Cursor::new(&responses[..]));
+
+            assert_eq!(next_block(&mut reader).unwrap().unwrap().as_ref(), &[0x82, 0x01, 0x80]);
+            assert_eq!(next_block(&mut reader).unwrap().unwrap().as_ref(), &[0x83, 0x02, 0x41, 0xff, 0x9f, 0xff]);
+            assert_eq!(next_block(&mut reader).unwrap().unwrap().as_ref(), &[0x80]);
+            assert!(next_block(&mut reader).unwrap().is_none());
+        }
+
+        #[test]
+        fn get_block_stream_rejects_error_responses() {
+            let responses = [0x82, 0x01, 0x62, 0x6b, 0x6f];
+            let mut reader = cbor_event::Reader::new(io::Cursor::new(&responses[..]));
+            match next_block(&mut reader) {
+                Err(Error::UnexpectedResponse) => {},
+                r => panic!("expected an unexpected response, got {:?}", r),
+            }
+        }
+    }
 }
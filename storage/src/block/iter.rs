@@ -70,49 +70,101 @@ impl<'a> iter::Iterator for Iter<'a> {
     }
 }
 
+/// block iterator over a single epoch, yielding the blocks in the order
+/// they have been packed (i.e. the chain order).
+pub struct EpochIter {
+    current: PackReader<fs::File>,
+}
+
+impl EpochIter {
+    /// create a block iterator over the pack of the given epoch.
+    ///
+    /// returns an `IoError` (`NotFound`) if the epoch is not in the storage.
+    pub fn new(storage: &StorageConfig, epoch: EpochId) -> Result<Self> {
+        let current = {
+            let epochref = epoch_read_pack(storage, epoch)?;
+            PackReader::init(&storage, &epochref)
+        };
+        Ok(EpochIter { current })
+    }
+
+    /// get the next raw block of the epoch, don't attempt to decode the raw block
+    pub fn next_raw(&mut self) -> Option<RawBlock> {
+        self.current.get_next()
+    }
+
+    /// just like `next_raw` but perform the cbor decoding into block
+    pub fn next_block(&mut self) -> Result<Option<Block>> {
+        match self.next_raw() {
+            None => Ok(None),
+            Some(raw) => Ok(Some(raw.decode()?))
+        }
+    }
+}
+impl iter::Iterator for EpochIter {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().unwrap()
+    }
+}
+
 /// reverse iterator over the block chain
+///
+/// follows the previous block links until the first block of the chain:
+/// the iteration ends at the given `genesis_prev` hash (the previous hash
+/// of the first block, which is not a block of the storage). A block
+/// missing from the storage before it is an error, after which the
+/// iteration ends.
 pub struct ReverseIter<'a> {
     storage: &'a Storage,
-    current_block: Option<HeaderHash>
+    current_block: Option<HeaderHash>,
+    genesis_prev: Option<HeaderHash>,
 }
 impl<'a> ReverseIter<'a> {
-    pub fn from(storage: &'a Storage, bh: &[u8]) -> Result<Self> {
+    pub fn from(storage: &'a Storage, bh: &[u8], genesis_prev: Option<HeaderHash>) -> Result<Self> {
         let hh = HeaderHash::from_slice(&bh)?;
         if let None = block_location(storage, hh.bytes()) {
             return Err(Error::HashNotFound(hh.into_bytes()));
         }
         let ri = ReverseIter {
             storage: storage,
-            current_block: Some(hh)
+            current_block: Some(hh),
+            genesis_prev: genesis_prev,
         };
         Ok(ri)
     }
 
-    pub fn new(storage: &'a Storage) -> Result<Self> {
-        let hh_bytes = match tag::read(&storage, &tag::HEAD) {
+    /// iterate from the `HEAD` tag of the given network
+    pub fn new(storage: &'a Storage, network: &str, genesis_prev: HeaderHash) -> Result<Self> {
+        let hh_bytes = match tag::read(&storage, &tag::get_network_head_tag(network)) {
             None => return Err(Error::NoTagHead),
             Some(t) => t
         };
-        Self::from(storage, &hh_bytes)
+        Self::from(storage, &hh_bytes, Some(genesis_prev))
     }
 }
 impl<'a> iter::Iterator for ReverseIter<'a> {
-    type Item = Block;
+    type Item = Result<Block>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let hh = match &self.current_block {
-            &None => return None,
-            &Some(ref hh) => hh.clone(),
+        let hh = match self.current_block.take() {
+            None => return None,
+            Some(ref hh) if Some(hh) == self.genesis_prev.as_ref() => return None,
+            Some(hh) => hh,
         };
 
-        let loc = block_location(&self.storage, hh.bytes()).expect("block location");
-        match block_read_location(&self.storage, &loc, hh.bytes()) {
-            None        => panic!("error while reading block {}", hh),
-            Some(blk) => {
-                let block = blk.decode().unwrap();
-                let hdr = block.get_header();
-                self.current_block = Some(hdr.get_previous_header());
-                Some(block)
+        let blk = match block_location(&self.storage, hh.bytes())
+            .and_then(|loc| block_read_location(&self.storage, &loc, hh.bytes()))
+        {
+            None => return Some(Err(Error::HashNotFound(hh.into_bytes()))),
+            Some(blk) => blk,
+        };
+        match blk.decode() {
+            Err(err) => Some(Err(Error::from(err))),
+            Ok(block) => {
+                self.current_block = Some(block.get_header().get_previous_header());
+                Some(Ok(block))
             }
         }
     }
@@ -8,12 +8,18 @@ use super::iter::{ReverseIter};
 pub struct Range(refpack::RefPack);
 impl Range {
     pub fn new(storage: &Storage, from: BlockHash, to: BlockHash) -> Result<Self> {
-        let ri = ReverseIter::from(storage, &to[..])?;
+        let ri = ReverseIter::from(storage, &to[..], None)?;
         let mut rp = refpack::RefPack::new();
         let mut finished = false;
 
         for block in ri {
-            let hash = block.get_header().compute_hash().into_bytes();
+            let hash = match block {
+                Ok(block) => block.get_header().compute_hash().into_bytes(),
+                // the beginning of the chain is reached: `from` is not
+                // an ancestor of `to`
+                Err(Error::HashNotFound(_)) => break,
+                Err(err) => return Err(err),
+            };
             rp.push_front(hash);
             if hash == from { finished = true; break; }
         }
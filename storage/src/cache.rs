@@ -0,0 +1,119 @@
+//! cache of the most recently read blocks.
+//!
+//! Looking up a block in the storage means searching the indexes of
+//! every pack and reading the block from disk. When the same blocks are
+//! looked up again and again (interactive inspection, verification) the
+//! `BlockCache` keeps the last ones in memory.
+//!
+
+use std::collections::BTreeMap;
+use cardano::block::{HeaderHash, RawBlock};
+
+/// number of blocks kept in memory by the storages opened to synchronize
+/// a network, see `Storage::enable_block_cache`.
+pub const DEFAULT_BLOCK_CACHE_CAPACITY : usize = 2048;
+
+/// Least Recently Used cache of blocks, indexed by their `HeaderHash`
+pub struct BlockCache {
+    capacity: usize,
+    // incremented on every access, the lowest tick is the least recently used
+    tick: u64,
+    blocks: BTreeMap<HeaderHash, (u64, RawBlock)>,
+    usage: BTreeMap<u64, HeaderHash>,
+}
+impl BlockCache {
+    /// create a cache holding at most `capacity` blocks
+    pub fn new(capacity: usize) -> Self {
+        BlockCache { capacity, tick: 0, blocks: BTreeMap::new(), usage: BTreeMap::new() }
+    }
+
+    pub fn capacity(&self) -> usize { self.capacity }
+    pub fn len(&self) -> usize { self.blocks.len() }
+    pub fn is_empty(&self) -> bool { self.blocks.is_empty() }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// get the block of the given hash if it is in the cache, making it
+    /// the most recently used block.
+    pub fn get(&mut self, hash: &HeaderHash) -> Option<RawBlock> {
+        let tick = self.next_tick();
+        match self.blocks.get_mut(hash) {
+            None => None,
+            Some(entry) => {
+                self.usage.remove(&entry.0);
+                self.usage.insert(tick, hash.clone());
+                entry.0 = tick;
+                Some(entry.1.clone())
+            }
+        }
+    }
+
+    /// add the block to the cache, evicting the least recently used
+    /// block if the cache is full.
+    pub fn insert(&mut self, hash: HeaderHash, block: RawBlock) {
+        if self.capacity == 0 { return; }
+        let tick = self.next_tick();
+        if let Some((old_tick, _)) = self.blocks.insert(hash.clone(), (tick, block)) {
+            self.usage.remove(&old_tick);
+        }
+        self.usage.insert(tick, hash);
+
+        if self.blocks.len() > self.capacity {
+            let oldest = *self.usage.keys().next().expect("the cache is not empty");
+            if let Some(hash) = self.usage.remove(&oldest) {
+                self.blocks.remove(&hash);
+            }
+        }
+    }
+
+    /// get the block of the given hash from the cache, or use `read` to
+    /// get it and keep it in the cache.
+    pub fn lookup<F>(&mut self, hash: &HeaderHash, read: F) -> Option<RawBlock>
+        where F: FnOnce(&HeaderHash) -> Option<RawBlock>
+    {
+        if let Some(block) = self.get(hash) { return Some(block); }
+        let block = read(hash)?;
+        self.insert(hash.clone(), block.clone());
+        Some(block)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn block(i: u8) -> (HeaderHash, RawBlock) {
+        (HeaderHash::new(&[i]), RawBlock::from_dat(vec![i]))
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let mut cache = BlockCache::new(2);
+        let (h1, b1) = block(1);
+        let (h2, b2) = block(2);
+        let (h3, b3) = block(3);
+
+        cache.insert(h1.clone(), b1);
+        cache.insert(h2.clone(), b2);
+        // 1 is now more recent than 2
+        assert!(cache.get(&h1).is_some());
+        cache.insert(h3.clone(), b3);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&h2).is_none());
+        assert_eq!(cache.get(&h1).unwrap().as_ref(), &[1]);
+        assert_eq!(cache.get(&h3).unwrap().as_ref(), &[3]);
+    }
+
+    #[test]
+    fn no_capacity() {
+        let mut cache = BlockCache::new(0);
+        let (h1, b1) = block(1);
+        cache.insert(h1.clone(), b1);
+        assert!(cache.is_empty());
+        assert!(cache.get(&h1).is_none());
+    }
+}
@@ -27,6 +27,7 @@ impl StorageConfig {
             StorageFileType::Blob => p.push("blob/"),
             StorageFileType::Tag => p.push("tag/"),
             StorageFileType::Epoch => p.push("epoch/"),
+            StorageFileType::Header => p.push("header/"),
         }
         p
     }
@@ -50,6 +51,21 @@ impl StorageConfig {
         p.push(hex::encode(blockhash));
         p
     }
+    pub fn get_header_filepath(&self, blockhash: &BlockHash) -> PathBuf {
+        let mut p = self.get_filetype_dir(StorageFileType::Header);
+        p.push(hex::encode(blockhash));
+        p
+    }
+    pub fn get_cached_headers_dir(&self, epoch: EpochId) -> PathBuf {
+        let mut p = self.get_filetype_dir(StorageFileType::Header);
+        p.push(epoch.to_string());
+        p
+    }
+    pub fn get_cached_header_filepath(&self, epoch: EpochId, previous: &BlockHash) -> PathBuf {
+        let mut p = self.get_cached_headers_dir(epoch);
+        p.push(hex::encode(previous));
+        p
+    }
     pub fn get_tag_filepath<P: AsRef<str>>(&self, s: P) -> PathBuf {
         let mut p = self.get_filetype_dir(StorageFileType::Tag);
         p.push(s.as_ref());
@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate log;
 extern crate cryptoxide;
+#[macro_use]
 extern crate cbor_event;
 extern crate cardano;
 extern crate rand;
@@ -15,15 +16,19 @@ pub mod refpack;
 pub mod tmpfile;
 pub mod lock;
 pub mod append;
+pub mod cache;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod bitmap;
 mod bloom;
-use std::{fs, io, result};
+use std::{fs, io, io::Write, result, sync::Mutex};
 
 pub use config::StorageConfig;
+pub use cache::{BlockCache, DEFAULT_BLOCK_CACHE_CAPACITY};
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use refpack::{RefPack};
-use cardano::block::{HeaderHash, BlockDate, RawBlock, Block};
+use cardano::block::{HeaderHash, BlockDate, RawBlock, RawBlockHeader, Block, BlockHeader, EpochId};
 
 use types::*;
 use tmpfile::*;
@@ -38,9 +43,13 @@ pub enum Error {
     RefPackUnexpectedGenesis(u32),
     // ** Epoch pack assumption errors
     EpochExpectingGenesis,
-    EpochError(u32, u32),
-    EpochSlotRewind(u32, u32),
+    EpochError(EpochId, EpochId),
+    EpochSlotRewind(EpochId, u32),
     EpochChainInvalid(BlockDate, HeaderHash, HeaderHash),
+    // ** Pack merge errors
+    PackChainInvalid(PackHash, HeaderHash, HeaderHash),
+    // ** Blob errors
+    BlobVersionUnknown(u8),
     NoSuchTag
 }
 impl From<io::Error> for Error {
@@ -61,6 +70,7 @@ pub type Result<T> = result::Result<T, Error>;
 pub struct Storage {
     pub config: StorageConfig,
     lookups: BTreeMap<PackHash, pack::Lookup>,
+    block_cache: Option<Mutex<BlockCache>>,
 }
 
 impl Storage {
@@ -73,6 +83,7 @@ impl Storage {
         fs::create_dir_all(cfg.get_filetype_dir(StorageFileType::Tag))?;
         fs::create_dir_all(cfg.get_filetype_dir(StorageFileType::Epoch))?;
         fs::create_dir_all(cfg.get_filetype_dir(StorageFileType::RefPack))?;
+        fs::create_dir_all(cfg.get_filetype_dir(StorageFileType::Header))?;
 
         let packhashes = cfg.list_indexes();
         for p in packhashes.iter() {
@@ -84,16 +95,21 @@ impl Storage {
             }
         }
 
-        let storage = Storage { config: cfg.clone(), lookups: lookups };
+        let storage = Storage { config: cfg.clone(), lookups: lookups, block_cache: None };
         Ok(storage)
     }
 
+    /// keep the last `capacity` blocks read with `block_read` in memory
+    pub fn enable_block_cache(&mut self, capacity: usize) {
+        self.block_cache = Some(Mutex::new(BlockCache::new(capacity)));
+    }
+
     /// create a reverse iterator over the stored blocks
     ///
-    /// it will iterate from the tag `HEAD` until there is no more
-    /// value to parse
-    pub fn reverse_iter<'a>(&'a self) -> Result<block::ReverseIter<'a>> {
-        block::ReverseIter::new(self).map_err(|err| Error::BlockError(err))
+    /// it will iterate from the tag `HEAD` of the given network until
+    /// the first block of the chain, see `iter_back_from`
+    pub fn reverse_iter<'a>(&'a self, network: &str, genesis_prev: &HeaderHash) -> Result<block::ReverseIter<'a>> {
+        block::ReverseIter::new(self, network, genesis_prev.clone()).map_err(|err| Error::BlockError(err))
     }
 
     /// create a block iterator starting from the given EpochId
@@ -122,40 +138,144 @@ impl Storage {
     }
 }
 
+/// iterate over the decoded blocks, from the block of the given hash back
+/// to the first block of the chain, whose previous hash is `genesis_prev`
+/// (see `exe_common::config::net::Config`)
+///
+/// fails if the block is not in the storage. The iteration yields an
+/// error if a block of the chain is missing from the storage.
+pub fn iter_back_from<'a>(storage: &'a Storage, from: &HeaderHash, genesis_prev: &HeaderHash) -> Result<block::ReverseIter<'a>> {
+    block::ReverseIter::from(storage, from.as_ref(), Some(genesis_prev.clone())).map_err(|err| Error::BlockError(err))
+}
+
+/// iterate over the decoded blocks of the given epoch, in chain order
+///
+/// fails if the epoch has not been packed in the storage yet.
+pub fn epoch_blocks(storage: &Storage, epoch: EpochId) -> Result<block::EpochIter> {
+    Ok(block::EpochIter::new(&storage.config, epoch)?)
+}
+
+/// read the last block of the given epoch, looked up in the epoch's pack
+/// index without reading the other blocks of the pack
+///
+/// fails if the epoch has not been packed in the storage yet.
+pub fn epoch_last_block(storage: &Storage, epoch: EpochId) -> Result<RawBlock> {
+    let (packref, refpack) = epoch::epoch_read(&storage.config, epoch)?;
+    let hash = match refpack.iter().rev().find(|hash| **hash != [0;HASH_SIZE]) {
+        None => return Err(Error::EpochExpectingGenesis),
+        Some(hash) => hash,
+    };
+    let idx_file = fs::File::open(storage.config.get_index_filepath(&packref))?;
+    let lookup = pack::index_get_header(&idx_file)?;
+    let (start, nb) = lookup.fanout.get_indexer_by_hash(hash);
+    match pack::search_index(&idx_file, &lookup.params, hash, start, nb) {
+        None => Err(Error::BlockError(block::Error::HashNotFound(*hash))),
+        Some(iofs) => {
+            let pack_offset = pack::resolve_index_offset(&idx_file, &lookup, iofs);
+            let pack_file = fs::File::open(storage.config.get_pack_filepath(&packref))?;
+            Ok(pack::read_block_at(&pack_file, pack_offset)?)
+        }
+    }
+}
+
+/// a difference between the epochs of two storages, see `diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpochDiff {
+    /// the epoch is only in the first storage
+    OnlyInFirst(EpochId),
+    /// the epoch is only in the second storage
+    OnlyInSecond(EpochId),
+    /// the epoch has a different pack in each storage (first, second)
+    DifferentPack(EpochId, PackHash, PackHash),
+}
+
+/// compare the epochs of two storages, which are identical if the
+/// returned list is empty. The differences are ordered by epoch.
+///
+/// The pack of an epoch is the one of its `EPOCH_<n>` tag, or the one
+/// of its epoch directory if it has no tag.
+pub fn diff(a: &Storage, b: &Storage) -> Vec<EpochDiff> {
+    let packs_a = epoch_packs(a);
+    let packs_b = epoch_packs(b);
+
+    let epochs : BTreeSet<&EpochId> = packs_a.keys().chain(packs_b.keys()).collect();
+    epochs.into_iter().filter_map(|epoch| {
+        match (packs_a.get(epoch), packs_b.get(epoch)) {
+            (Some(pa), Some(pb)) if pa == pb => None,
+            (Some(pa), Some(pb)) => Some(EpochDiff::DifferentPack(*epoch, *pa, *pb)),
+            (Some(_), None) => Some(EpochDiff::OnlyInFirst(*epoch)),
+            (None, _) => Some(EpochDiff::OnlyInSecond(*epoch)),
+        }
+    }).collect()
+}
+
+// the pack of every epoch of the storage, from the epoch tags and the
+// epoch directories
+fn epoch_packs(storage: &Storage) -> BTreeMap<EpochId, PackHash> {
+    let mut packs = BTreeMap::new();
+
+    let dir = storage.config.get_filetype_dir(StorageFileType::Epoch);
+    for entry in fs::read_dir(dir).unwrap() {
+        let entry = entry.unwrap();
+        if let Some(epoch) = entry.file_name().to_str().and_then(|s| s.parse::<EpochId>().ok()) {
+            if let Ok(packhash) = epoch::epoch_read_pack(&storage.config, epoch) {
+                packs.insert(epoch, packhash);
+            }
+        }
+    }
+
+    for name in tag::list(storage) {
+        if ! name.starts_with("EPOCH_") { continue; }
+        if let Ok(epoch) = name["EPOCH_".len()..].parse::<EpochId>() {
+            if let Some(hash) = tag::read_hash(storage, &name) {
+                packs.insert(epoch, hash.into_bytes());
+            }
+        }
+    }
+    packs
+}
+
 fn tmpfile_create_type(storage: &Storage, filetype: StorageFileType) -> TmpFile {
     TmpFile::create(storage.config.get_filetype_dir(filetype)).unwrap()
 }
 
 pub mod blob {
     use std::fs;
-    use std::io::{Read,Write};
+    use std::io::{Write};
     use super::{Result, Error};
     use cardano::block::RawBlock;
 
+    /// version of the format of the blobs, written as their first byte.
+    ///
+    /// The blobs written before the blobs were versioned are the CBOR
+    /// encoding of a block, starting with `UNVERSIONED`, and are read as
+    /// they are.
+    pub const BLOB_VERSION : u8 = 1;
+    const UNVERSIONED : u8 = 0x82;
+
     pub fn write(storage: &super::Storage, hash: &super::BlockHash, block: &[u8]) -> Result<()> {
         let path = storage.config.get_blob_filepath(&hash);
-        let mut tmp_file = super::tmpfile_create_type(storage, super::StorageFileType::Blob);
+        let mut tmp_file = super::TmpFile::create(storage.config.get_filetype_dir(super::StorageFileType::Blob))?;
+        tmp_file.write_all(&[BLOB_VERSION])?;
         tmp_file.write_all(block)?;
         tmp_file.render_permanent(&path).map_err(|e| Error::IoError(e))
     }
 
+    /// read the content of the blob, without its version. Fails if the
+    /// blob is of a version newer than `BLOB_VERSION`.
     pub fn read_raw(storage: &super::Storage, hash: &super::BlockHash) -> Result<Vec<u8>> {
-        let mut content = Vec::new();
-        let path = storage.config.get_blob_filepath(&hash);
-
-        let mut file = fs::File::open(path)?;
-        file.read_to_end(&mut content)?;
+        let path = storage.config.get_blob_filepath(hash);
+        let mut content = fs::read(path)?;
+        match content.first().cloned() {
+            None | Some(UNVERSIONED) => {},
+            Some(BLOB_VERSION) => { content.remove(0); },
+            Some(version) => return Err(Error::BlobVersionUnknown(version)),
+        }
         Ok(content)
     }
 
     pub fn read(storage: &super::Storage, hash: &super::BlockHash) -> Result<RawBlock> {
-        let mut content = Vec::new();
-        let path = storage.config.get_blob_filepath(&hash);
-
-        let mut file = fs::File::open(path)?;
-        file.read_to_end(&mut content)?;
-
-        Ok(RawBlock::from_dat(content))
+        Ok(RawBlock::from_dat(read_raw(storage, hash)?))
     }
 
     pub fn exist(storage: &super::Storage, hash: &super::BlockHash) -> bool {
@@ -172,6 +292,84 @@ pub mod blob {
     }
 }
 
+/// store the block as a blob, under its own hash which is returned.
+///
+/// Prefer this to `blob::write` which stores any bytes under any key.
+pub fn store_block(storage: &Storage, block: &Block) -> Result<HeaderHash> {
+    let hash = block.get_header().compute_hash();
+    blob::write(storage, hash.bytes(), &cbor!(block)?)?;
+    Ok(hash)
+}
+
+/// store the header under its own hash which is returned. The headers are
+/// stored apart from the blocks, see `header_read`.
+pub fn store_header(storage: &Storage, header: &BlockHeader) -> Result<HeaderHash> {
+    let raw = header.to_raw();
+    let hash = raw.compute_hash();
+    let path = storage.config.get_header_filepath(hash.bytes());
+    let mut tmp_file = tmpfile_create_type(storage, StorageFileType::Header);
+    tmp_file.write_all(raw.as_ref())?;
+    tmp_file.render_permanent(&path)?;
+    Ok(hash)
+}
+
+/// read the header of the given hash stored with `store_header`
+pub fn header_read(storage: &Storage, hash: &BlockHash) -> Option<RawBlockHeader> {
+    fs::read(storage.config.get_header_filepath(hash)).ok().map(RawBlockHeader::from_dat)
+}
+
+/// keep the header in the header cache of its epoch, under the hash of
+/// its previous block so the chain can be followed forward, see
+/// `cached_headers_after`.
+///
+/// A header which may still be rolled back must not be cached: only one
+/// child of each block is kept.
+pub fn cache_header(config: &StorageConfig, header: &BlockHeader) -> Result<()> {
+    let epoch = header.epoch();
+    fs::create_dir_all(config.get_cached_headers_dir(epoch))?;
+    let path = config.get_cached_header_filepath(epoch, header.get_previous_header().bytes());
+    let mut tmp_file = TmpFile::create(config.get_filetype_dir(StorageFileType::Header))?;
+    tmp_file.write_all(header.to_raw().as_ref())?;
+    tmp_file.render_permanent(&path)?;
+    Ok(())
+}
+
+/// the cached headers following the block `from` of the given epoch, oldest
+/// first, up to `to` or at most `max` of them. Empty if the header
+/// following `from` is not cached.
+pub fn cached_headers_after(config: &StorageConfig, from: &HeaderHash, epoch: EpochId, to: &HeaderHash, max: usize) -> Vec<BlockHeader> {
+    let read = |epoch: EpochId, previous: &HeaderHash| {
+        fs::read(config.get_cached_header_filepath(epoch, previous.bytes())).ok()
+            .and_then(|raw| RawBlockHeader::from_dat(raw).decode().ok())
+    };
+
+    let mut headers = Vec::new();
+    let mut previous = from.clone();
+    let mut epoch = epoch;
+    while headers.len() < max && previous != *to {
+        // the child of the last block of an epoch is the genesis block of
+        // the next one
+        let header = match read(epoch, &previous).or_else(|| read(epoch + 1, &previous)) {
+            None => break,
+            Some(header) => header,
+        };
+        epoch = header.epoch();
+        previous = header.compute_hash();
+        headers.push(header);
+    }
+    headers
+}
+
+/// remove the cached headers of the given epoch, once it is packed
+pub fn remove_cached_headers(config: &StorageConfig, epoch: EpochId) {
+    let dir = config.get_cached_headers_dir(epoch);
+    if dir.exists() {
+        if let Err(err) = fs::remove_dir_all(&dir) {
+            warn!("unable to remove the cached headers of the epoch {}: {}", epoch, err);
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum BlockLocation {
     Packed(PackHash, pack::IndexOffset),
@@ -220,7 +418,19 @@ pub fn block_read_location(storage: &Storage, loc: &BlockLocation, hash: &BlockH
     }
 }
 
+/// read the block of the given hash, from the block cache if enabled
+/// (see `Storage::enable_block_cache`) or from the disk.
 pub fn block_read(storage: &Storage, hash: &BlockHash) -> Option<RawBlock> {
+    match storage.block_cache {
+        None => block_read_uncached(storage, hash),
+        Some(ref cache) => {
+            let mut cache = cache.lock().unwrap();
+            cache.lookup(&HeaderHash::from_bytes(*hash), |_| block_read_uncached(storage, hash))
+        }
+    }
+}
+
+fn block_read_uncached(storage: &Storage, hash: &BlockHash) -> Option<RawBlock> {
     match block_location(storage, hash) {
         None      => None,
         Some(loc) => block_read_location(storage, &loc, hash),
@@ -312,7 +522,7 @@ pub fn refpack_epoch_pack<S: AsRef<str>>(storage: &Storage, tag: &S) -> Result<(
                 if !hdr.is_genesis_block() {
                     return Err(Error::EpochExpectingGenesis)
                 }
-                current_state = Some((hdr.get_blockdate().get_epochid(), 0, hdr.compute_hash()));
+                current_state = Some((hdr.epoch(), 0, hdr.compute_hash()));
                 rp.push_back(hash.into_bytes());
             },
             Some((current_epoch, expected_slotid, current_prevhash)) => {
@@ -348,16 +558,117 @@ pub fn refpack_epoch_pack<S: AsRef<str>>(storage: &Storage, tag: &S) -> Result<(
     refpack::write_refpack(&storage.config, tag, &rp).map_err(From::from)
 }
 
+/// what `verify_epoch` found in a consistent epoch pack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochReport {
+    /// the number of blocks of the pack, its genesis block included
+    pub blocks: usize,
+    /// the number of slots without a block, up to the last block
+    pub empty_slots: usize,
+    /// the hash of the last block of the pack
+    pub last_block: HeaderHash,
+}
+
+/// the first inconsistency found by `verify_epoch`, the `usize` being the
+/// position of the block in the pack
+#[derive(Debug)]
+pub enum VerifyError {
+    /// the epoch is not packed, or its refpack cannot be read
+    IoError(io::Error),
+    /// the block cannot be decoded
+    InvalidBlock(usize, cbor_event::Error),
+    /// the pack does not start with the genesis block of the epoch
+    ExpectingGenesis,
+    /// the block is not of the epoch
+    WrongEpoch(usize, BlockDate),
+    /// the slot of the block is not after the slot of the previous block
+    SlotRewind(usize, BlockDate),
+    /// the block is not the child of the previous block (expected, got)
+    ChainInvalid(usize, HeaderHash, HeaderHash),
+    /// the hash of the block is not the one of its slot in the refpack
+    WrongHash(usize, HeaderHash),
+    /// the body of the main block does not match the proofs of its header
+    WrongProof(usize),
+    /// the content of the pack does not have the pack's hash
+    WrongPackHash(PackHash),
+}
+impl From<io::Error> for VerifyError {
+    fn from(e: io::Error) -> Self { VerifyError::IoError(e) }
+}
+
+/// check the consistency of the pack of the given epoch: it starts with
+/// the genesis block of the epoch, every block is the child of the
+/// previous one in a later slot of the epoch, has the hash of its slot in
+/// the epoch's refpack and a body matching the proofs of its header, and
+/// the content of the pack has the pack's hash.
+///
+/// The genesis block is not checked to follow the previous epoch.
+pub fn verify_epoch(storage: &Storage, epoch: EpochId) -> result::Result<EpochReport, VerifyError> {
+    let (packhash, refpack) = epoch::epoch_read(&storage.config, epoch)?;
+    let refpack : Vec<BlockHash> = refpack.iter().cloned().collect();
+    let mut reader = pack::PackReader::init(&storage.config, &packhash);
+
+    let mut report : Option<EpochReport> = None;
+    let mut previous_slot = 0;
+    while let Some(raw_block) = reader.get_next() {
+        let index = report.as_ref().map_or(0, |report| report.blocks);
+        let block = raw_block.decode().map_err(|err| VerifyError::InvalidBlock(index, err))?;
+        let hdr = block.get_header();
+        let hash = hdr.compute_hash();
+        let date = hdr.get_blockdate();
+
+        if hdr.epoch() != epoch {
+            return Err(VerifyError::WrongEpoch(index, date));
+        }
+        // the position of the block in the refpack: the genesis block
+        // then the slots of the epoch
+        let slot = match date {
+            BlockDate::Genesis(_) => 0,
+            BlockDate::Normal(ref slot_id) => slot_id.slotid as usize + 1,
+        };
+        match report {
+            None => {
+                if slot != 0 { return Err(VerifyError::ExpectingGenesis) }
+            },
+            Some(ref report) => {
+                if slot <= previous_slot {
+                    return Err(VerifyError::SlotRewind(index, date));
+                }
+                if hdr.get_previous_header() != report.last_block {
+                    return Err(VerifyError::ChainInvalid(index, report.last_block.clone(), hdr.get_previous_header()));
+                }
+            },
+        }
+        if refpack.get(slot) != Some(hash.bytes()) {
+            return Err(VerifyError::WrongHash(index, hash));
+        }
+        if let Block::MainBlock(ref blk) = block {
+            if blk.header.body_proof.tx.number as usize != blk.transaction_count() || !blk.verify_extra_data_proof() {
+                return Err(VerifyError::WrongProof(index));
+            }
+        }
+
+        let empty_slots = report.as_ref().map_or(0, |report| report.empty_slots + (slot - previous_slot - 1));
+        report = Some(EpochReport { blocks: index + 1, empty_slots, last_block: hash });
+        previous_slot = slot;
+    }
+
+    if reader.finalize() != packhash {
+        return Err(VerifyError::WrongPackHash(packhash));
+    }
+    report.ok_or(VerifyError::ExpectingGenesis)
+}
+
 pub fn integrity_check(storage: &Storage, genesis_hash: HeaderHash, count: u32) {
     let mut previous_header = genesis_hash;
     for epochid in 0..count {
         println!("check epoch {}'s integrity", epochid);
-        previous_header = epoch_integrity_check(storage, epochid, previous_header).unwrap();
+        previous_header = epoch_integrity_check(storage, EpochId::new(epochid), previous_header).unwrap();
     }
 }
 
-fn epoch_integrity_check(storage: &Storage, epochid: u32, last_known_hash: HeaderHash) -> Result<HeaderHash> {
-    let packhash_vec = tag::read(storage, &format!("EPOCH_{}", epochid)).expect("EPOCH not found");
+fn epoch_integrity_check(storage: &Storage, epochid: EpochId, last_known_hash: HeaderHash) -> Result<HeaderHash> {
+    let packhash_vec = tag::read(storage, &tag::get_epoch_tag(epochid)).expect("EPOCH not found");
     let mut packhash = [0;HASH_SIZE];
     packhash[..].clone_from_slice(packhash_vec.as_slice());
     let mut pack = pack::PackReader::init(&storage.config, &packhash);
@@ -380,7 +691,7 @@ fn epoch_integrity_check(storage: &Storage, epochid: u32, last_known_hash: Heade
                 if last_known_hash != prevhash {
                     return Err(Error::EpochChainInvalid(date, last_known_hash, prevhash))
                 }
-                current_state = Some((hdr.get_blockdate().get_epochid(), 0, hdr.compute_hash()));
+                current_state = Some((hdr.epoch(), 0, hdr.compute_hash()));
             },
             Some((current_epoch, expected_slotid, current_prevhash)) => {
                 match date.clone() {
@@ -392,7 +703,7 @@ fn epoch_integrity_check(storage: &Storage, epochid: u32, last_known_hash: Heade
                             return Err(Error::EpochError(current_epoch, slotid.epoch));
                         }
                         if slotid.slotid < expected_slotid {
-                            return Err(Error::EpochSlotRewind(current_epoch, slotid.epoch));
+                            return Err(Error::EpochSlotRewind(current_epoch, slotid.slotid));
                         }
                         if prevhash != current_prevhash {
                             return Err(Error::EpochChainInvalid(date.clone(), prevhash, current_prevhash))
@@ -416,3 +727,400 @@ fn epoch_integrity_check(storage: &Storage, epochid: u32, last_known_hash: Heade
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cardano::testing::{genesis_block, main_block};
+    use testing::temporary_storage;
+
+    #[test]
+    fn store_block_under_its_hash() {
+        let storage = temporary_storage();
+        let raw = genesis_block(1, 0, &HeaderHash::from_slice(&[0u8;32]).unwrap());
+        let block = raw.decode().unwrap();
+        let hash = block.get_header().compute_hash();
+
+        assert_eq!(store_block(&storage, &block).unwrap(), hash);
+        assert_eq!(storage.config.list_blob(None), vec![*hash.bytes()]);
+        assert_eq!(block_read(&storage, hash.bytes()).unwrap().as_ref(), raw.as_ref());
+
+        // the header does not replace the block stored under the same hash
+        assert_eq!(store_header(&storage, &block.get_header()).unwrap(), hash);
+        assert_eq!(header_read(&storage, hash.bytes()).unwrap().compute_hash(), hash);
+        assert_eq!(block_read(&storage, hash.bytes()).unwrap().as_ref(), raw.as_ref());
+        assert!(header_read(&storage, &[0u8;32]).is_none());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn cached_headers_follow_the_chain() {
+        let storage = temporary_storage();
+        // the epoch 0 has a genesis block and 2 main blocks, followed by
+        // the genesis block of the epoch 1
+        let genesis_prev = HeaderHash::from_slice(&[0u8;32]).unwrap();
+        let mut headers = vec![genesis_block(0, 0, &genesis_prev).decode().unwrap().get_header()];
+        for slot in 0..2 {
+            let previous = headers.last().unwrap().compute_hash();
+            headers.push(main_block(0, slot, &previous).decode().unwrap().get_header());
+        }
+        let previous = headers.last().unwrap().compute_hash();
+        headers.push(genesis_block(1, 2, &previous).decode().unwrap().get_header());
+        let hashes : Vec<_> = headers.iter().map(|header| header.compute_hash()).collect();
+        let cached = |from: &HeaderHash, epoch, to: &HeaderHash, max| -> Vec<HeaderHash> {
+            cached_headers_after(&storage.config, from, EpochId::new(epoch), to, max).iter()
+                .map(|header| header.compute_hash()).collect()
+        };
+
+        assert!(cached(&genesis_prev, 0, &hashes[3], 10).is_empty());
+        for header in headers.iter() { cache_header(&storage.config, header).unwrap(); }
+
+        // from any block, across the epochs
+        assert_eq!(cached(&genesis_prev, 0, &hashes[3], 10), hashes);
+        assert_eq!(cached(&hashes[1], 0, &hashes[3], 10), &hashes[2..]);
+        // up to `to`, at most `max`
+        assert_eq!(cached(&hashes[0], 0, &hashes[2], 10), &hashes[1..3]);
+        assert_eq!(cached(&hashes[0], 0, &hashes[3], 1), &hashes[1..2]);
+        // the end of the cache is not `to`
+        assert_eq!(cached(&hashes[1], 0, &HeaderHash::from_slice(&[1u8;32]).unwrap(), 10), &hashes[2..]);
+        assert!(cached(&hashes[3], 1, &HeaderHash::from_slice(&[1u8;32]).unwrap(), 10).is_empty());
+
+        // pruned once the epoch 0 is packed
+        remove_cached_headers(&storage.config, EpochId::new(0));
+        assert!(cached(&genesis_prev, 0, &hashes[3], 10).is_empty());
+        assert_eq!(cached(&hashes[2], 0, &hashes[3], 10), &hashes[3..]);
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn blob_version() {
+        let storage = temporary_storage();
+        let raw = genesis_block(0, 0, &HeaderHash::from_slice(&[0u8;32]).unwrap());
+        let hash = *raw.decode().unwrap().get_header().compute_hash().bytes();
+        let path = storage.config.get_blob_filepath(&hash);
+
+        blob::write(&storage, &hash, raw.as_ref()).unwrap();
+        assert_eq!(fs::read(&path).unwrap()[0], blob::BLOB_VERSION);
+        assert_eq!(blob::read(&storage, &hash).unwrap().as_ref(), raw.as_ref());
+
+        // the blobs written before the versioning
+        fs::write(&path, raw.as_ref()).unwrap();
+        assert_eq!(blob::read(&storage, &hash).unwrap().as_ref(), raw.as_ref());
+
+        let mut future = vec![blob::BLOB_VERSION + 1];
+        future.extend_from_slice(raw.as_ref());
+        fs::write(&path, &future).unwrap();
+        match blob::read(&storage, &hash) {
+            Err(Error::BlobVersionUnknown(version)) => assert_eq!(version, blob::BLOB_VERSION + 1),
+            r => panic!("expected an unknown version error, got {:?}", r),
+        }
+        assert!(block_read(&storage, &hash).is_none());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    // write a pack of the given blocks, without its index
+    fn write_pack(storage: &Storage, blocks: &[RawBlock]) -> PackHash {
+        let mut writer = pack::PackWriter::init(&storage.config);
+        for block in blocks {
+            writer.append(block.decode().unwrap().get_header().compute_hash().bytes(), block.as_ref());
+        }
+        writer.finalize().0
+    }
+
+    #[test]
+    fn pack_index_version() {
+        let storage = temporary_storage();
+        let mut writer = pack::PackWriter::init(&storage.config);
+        let block = genesis_block(0, 0, &HeaderHash::from_slice(&[0u8;32]).unwrap());
+        writer.append(block.decode().unwrap().get_header().compute_hash().bytes(), block.as_ref());
+        let (packhash, index) = writer.finalize();
+        let (_, tmpfile) = pack::create_index(&storage, &index);
+        let path = storage.config.get_index_filepath(&packhash);
+        tmpfile.render_permanent(&path).unwrap();
+
+        let lookup = pack::read_index_fanout(&storage.config, &packhash).unwrap();
+        assert!(lookup.bloom.search(block.decode().unwrap().get_header().compute_hash().bytes()));
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[12..16].copy_from_slice(&[0, 0, 0, 2]);
+        fs::write(&path, &bytes).unwrap();
+        match pack::read_index_fanout(&storage.config, &packhash) {
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidData),
+            Ok(_) => panic!("the index of an unknown version should not be read"),
+        }
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn merge_packs() {
+        let mut storage = temporary_storage();
+        let mut blocks = vec![genesis_block(0, 0, &HeaderHash::from_slice(&[0u8;32]).unwrap())];
+        for epoch in 1..5 {
+            let prev = blocks.last().unwrap().decode().unwrap().get_header().compute_hash();
+            blocks.push(genesis_block(epoch, 0, &prev));
+        }
+        let first = write_pack(&storage, &blocks[..2]);
+        let second = write_pack(&storage, &blocks[2..]);
+
+        match pack::merge(&mut storage, &[second, first]) {
+            Err(Error::PackChainInvalid(pack, _, _)) => assert_eq!(pack, first),
+            r => panic!("expected an invalid chain error, got {:?}", r),
+        }
+        assert!(storage.lookups.is_empty());
+
+        let merged = pack::merge(&mut storage, &[first, second]).unwrap();
+        assert_eq!(merged, write_pack(&storage, &blocks));
+        for block in blocks.iter() {
+            let hash = block.decode().unwrap().get_header().compute_hash();
+            match block_location(&storage, hash.bytes()) {
+                Some(BlockLocation::Packed(pack, _)) => assert_eq!(pack, merged),
+                _ => panic!("block {} not found in the merged pack", hash),
+            }
+            assert_eq!(block_read(&storage, hash.bytes()).unwrap().as_ref(), block.as_ref());
+        }
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn diff_epochs() {
+        let a = temporary_storage();
+        let b = temporary_storage();
+
+        // the epoch 0 is packed in both, tagged in one of them
+        epoch::epoch_create_with_refpack(&a.config, &[1;32], &RefPack::new(), EpochId::new(0));
+        tag::write(&b, &tag::get_epoch_tag(EpochId::new(0)), &[1;32]);
+        // the epoch 1 differs
+        epoch::epoch_create_with_refpack(&a.config, &[2;32], &RefPack::new(), EpochId::new(1));
+        epoch::epoch_create_with_refpack(&b.config, &[3;32], &RefPack::new(), EpochId::new(1));
+        // the epochs 2 and 3 are each in one storage only
+        epoch::epoch_create_with_refpack(&a.config, &[4;32], &RefPack::new(), EpochId::new(3));
+        tag::write(&b, &tag::get_epoch_tag(EpochId::new(2)), &[5;32]);
+
+        assert_eq!(diff(&a, &a), vec![]);
+        assert_eq!(diff(&a, &b), vec![
+            EpochDiff::DifferentPack(EpochId::new(1), [2;32], [3;32]),
+            EpochDiff::OnlyInSecond(EpochId::new(2)),
+            EpochDiff::OnlyInFirst(EpochId::new(3)),
+        ]);
+
+        fs::remove_dir_all(a.config.get_path()).unwrap();
+        fs::remove_dir_all(b.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn epoch_blocks_in_order() {
+        let storage = temporary_storage();
+        let epoch = EpochId::new(3);
+
+        let mut writer = pack::PackWriter::init(&storage.config);
+        let mut previous = HeaderHash::from_slice(&[0u8;32]).unwrap();
+        let mut hashes = Vec::new();
+        for difficulty in 0..4 {
+            let raw = genesis_block(epoch.as_u32(), difficulty, &previous);
+            let hash = raw.decode().unwrap().get_header().compute_hash();
+            writer.append(hash.bytes(), raw.as_ref());
+            hashes.push(hash.clone());
+            previous = hash;
+        }
+        let (packhash, _) = writer.finalize();
+        epoch::epoch_create_with_refpack(&storage.config, &packhash, &RefPack::new(), epoch);
+
+        let got : Vec<HeaderHash> = epoch_blocks(&storage, epoch).unwrap()
+            .map(|blk| blk.get_header().compute_hash())
+            .collect();
+        assert_eq!(got, hashes);
+
+        assert!(epoch_blocks(&storage, epoch + 1).is_err());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    // pack the given blocks as the given epoch
+    fn write_epoch(storage: &Storage, epoch: EpochId, blocks: &[RawBlock]) -> PackHash {
+        let packhash = write_pack(storage, blocks);
+        epoch::epoch_create(&storage.config, &packhash, epoch);
+        packhash
+    }
+
+    // the genesis block of the epoch then main blocks of the given slots
+    fn epoch_of(epoch: u32, slots: &[u32]) -> Vec<RawBlock> {
+        let hash = |block: &RawBlock| block.decode().unwrap().get_header().compute_hash();
+        let mut blocks = vec![genesis_block(epoch, 0, &HeaderHash::from_slice(&[0u8;32]).unwrap())];
+        for slotid in slots {
+            let block = main_block(epoch, *slotid, &hash(blocks.last().unwrap()));
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    #[test]
+    fn epoch_last_block_of_a_new_pack() {
+        // the pack is written after the storage loaded its lookups
+        let storage = temporary_storage();
+        let blocks = epoch_of(1, &[0, 1, 4]);
+        let mut writer = pack::PackWriter::init(&storage.config);
+        for block in blocks.iter() {
+            writer.append(block.decode().unwrap().get_header().compute_hash().bytes(), block.as_ref());
+        }
+        let (packhash, index) = writer.finalize();
+        let (_, tmpfile) = pack::create_index(&storage, &index);
+        tmpfile.render_permanent(&storage.config.get_index_filepath(&packhash)).unwrap();
+        epoch::epoch_create(&storage.config, &packhash, EpochId::new(1));
+
+        assert_eq!(epoch_last_block(&storage, EpochId::new(1)).unwrap().as_ref(), blocks[3].as_ref());
+        assert!(epoch_last_block(&storage, EpochId::new(2)).is_err());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn verify_valid_epoch() {
+        let storage = temporary_storage();
+        let epoch = EpochId::new(1);
+        let blocks = epoch_of(1, &[0, 1, 4]);
+        write_epoch(&storage, epoch, &blocks);
+
+        let report = verify_epoch(&storage, epoch).unwrap();
+        assert_eq!(report, EpochReport {
+            blocks: 4,
+            empty_slots: 2,
+            last_block: blocks[3].decode().unwrap().get_header().compute_hash(),
+        });
+        match verify_epoch(&storage, epoch + 1) {
+            Err(VerifyError::IoError(_)) => {},
+            r => panic!("expected an io error, got {:?}", r),
+        }
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn verify_corrupted_epochs() {
+        let storage = temporary_storage();
+        let blocks = epoch_of(0, &[0, 1, 2]);
+
+        // a block of the chain is missing
+        write_epoch(&storage, EpochId::new(0), &[&blocks[..2], &blocks[3..]].concat());
+        match verify_epoch(&storage, EpochId::new(0)) {
+            Err(VerifyError::ChainInvalid(2, _, _)) => {},
+            r => panic!("expected an invalid chain error, got {:?}", r),
+        }
+
+        // the slots go backward, with the refpack written by hand as
+        // `epoch_create` expects the slots in order
+        let rewind = epoch_of(1, &[3, 2]);
+        let hash = |block: &RawBlock| *block.decode().unwrap().get_header().compute_hash().bytes();
+        let mut refpack = RefPack::new();
+        refpack.push_back(hash(&rewind[0]));
+        refpack.push_back_missing();
+        refpack.push_back_missing();
+        refpack.push_back(hash(&rewind[2]));
+        refpack.push_back(hash(&rewind[1]));
+        let packhash = write_pack(&storage, &rewind);
+        epoch::epoch_create_with_refpack(&storage.config, &packhash, &refpack, EpochId::new(1));
+        match verify_epoch(&storage, EpochId::new(1)) {
+            Err(VerifyError::SlotRewind(2, _)) => {},
+            r => panic!("expected a slot rewind error, got {:?}", r),
+        }
+
+        // no genesis block, or blocks of another epoch
+        write_epoch(&storage, EpochId::new(2), &epoch_of(2, &[0])[1..]);
+        match verify_epoch(&storage, EpochId::new(2)) {
+            Err(VerifyError::ExpectingGenesis) => {},
+            r => panic!("expected a missing genesis error, got {:?}", r),
+        }
+        let packhash = write_pack(&storage, &blocks);
+        epoch::epoch_create_with_refpack(&storage.config, &packhash, &RefPack::new(), EpochId::new(3));
+        match verify_epoch(&storage, EpochId::new(3)) {
+            Err(VerifyError::WrongEpoch(0, _)) => {},
+            r => panic!("expected a wrong epoch error, got {:?}", r),
+        }
+
+        // the refpack does not have the hashes of the blocks
+        epoch::epoch_create_with_refpack(&storage.config, &packhash, &RefPack::new(), EpochId::new(0));
+        match verify_epoch(&storage, EpochId::new(0)) {
+            Err(VerifyError::WrongHash(0, _)) => {},
+            r => panic!("expected a wrong hash error, got {:?}", r),
+        }
+
+        // the content of the pack was replaced by another one
+        let packhash = write_epoch(&storage, EpochId::new(0), &blocks);
+        assert_eq!(verify_epoch(&storage, EpochId::new(0)).unwrap().blocks, 4);
+        let other = write_pack(&storage, &blocks[..3]);
+        fs::copy(storage.config.get_pack_filepath(&other), storage.config.get_pack_filepath(&packhash)).unwrap();
+        match verify_epoch(&storage, EpochId::new(0)) {
+            Err(VerifyError::WrongPackHash(hash)) => assert_eq!(hash, packhash),
+            r => panic!("expected a wrong pack hash error, got {:?}", r),
+        }
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+
+    #[test]
+    fn block_read_goes_through_the_block_cache() {
+        let storage = temporary_storage();
+        let raw = genesis_block(0, 0, &HeaderHash::from_slice(&[0u8;32]).unwrap());
+        let hash = raw.decode().unwrap().get_header().compute_hash();
+        let mut writer = pack::PackWriter::init(&storage.config);
+        writer.append(hash.bytes(), raw.as_ref());
+        let (packhash, index) = writer.finalize();
+        let (_, tmpfile) = pack::create_index(&storage, &index);
+        tmpfile.render_permanent(&storage.config.get_index_filepath(&packhash)).unwrap();
+        // reload the storage to see the new pack
+        let mut cached = Storage::init(&storage.config).unwrap();
+        cached.enable_block_cache(16);
+
+        assert_eq!(block_read(&cached, hash.bytes()).unwrap().as_ref(), raw.as_ref());
+
+        // once read, the block is still found in the cache without the
+        // pack it was read from, or its index
+        fs::remove_file(storage.config.get_pack_filepath(&packhash)).unwrap();
+        fs::remove_file(storage.config.get_index_filepath(&packhash)).unwrap();
+        assert_eq!(block_read(&cached, hash.bytes()).unwrap().as_ref(), raw.as_ref());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+    #[test]
+    fn iter_back_from_the_tip() {
+        let storage = temporary_storage();
+        let mut previous = HeaderHash::from_slice(&[0u8;32]).unwrap();
+        let mut hashes = Vec::new();
+        for difficulty in 0..4 {
+            let raw = genesis_block(0, difficulty, &previous);
+            let hash = raw.decode().unwrap().get_header().compute_hash();
+            blob::write(&storage, hash.bytes(), raw.as_ref()).unwrap();
+            hashes.push(hash.clone());
+            previous = hash;
+        }
+
+        let genesis_prev = HeaderHash::from_slice(&[0u8;32]).unwrap();
+        let got : Vec<HeaderHash> = iter_back_from(&storage, &hashes[3], &genesis_prev).unwrap()
+            .take(3)
+            .map(|blk| blk.unwrap().get_header().compute_hash())
+            .collect();
+        assert_eq!(got, vec![hashes[3].clone(), hashes[2].clone(), hashes[1].clone()]);
+
+        // stops after the first block of the chain
+        assert_eq!(iter_back_from(&storage, &hashes[1], &genesis_prev).unwrap().count(), 2);
+
+        assert!(iter_back_from(&storage, &HeaderHash::from_slice(&[1u8;32]).unwrap(), &genesis_prev).is_err());
+
+        // a missing block is not the beginning of the chain
+        blob::remove(&storage, hashes[1].bytes());
+        let mut iter = iter_back_from(&storage, &hashes[3], &genesis_prev).unwrap();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_ok());
+        match iter.next() {
+            Some(Err(block::Error::HashNotFound(hash))) => assert_eq!(&hash, hashes[1].bytes()),
+            r => panic!("expected the block {} not to be found, got {:?}", hashes[1], r),
+        }
+        assert!(iter.next().is_none());
+
+        fs::remove_dir_all(storage.config.get_path()).unwrap();
+    }
+}
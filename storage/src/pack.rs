@@ -8,7 +8,7 @@
 //
 // MAGIC (8 Bytes)
 // BLOOM SIZE (4 bytes BE)
-// 0-PADDING (4 bytes)
+// VERSION (4 bytes BE)
 // FANOUT (256*4 bytes)
 // BLOOM FILTER (BLOOM_SIZE bytes)
 // BLOCK HASHES present in this pack ordered lexigraphically (#ENTRIES * 32 bytes)
@@ -41,6 +41,10 @@ use cardano;
 
 const MAGIC : &[u8] = b"ADAPACK1";
 const MAGIC_SIZE : usize = 8;
+
+// version of the index format, the indexes written before the version
+// have 0-padding instead and the same layout as the version 1
+const INDEX_VERSION : u32 = 1;
 const OFF_SIZE : usize = 8;
 const SIZE_SIZE : usize = 4;
 
@@ -187,7 +191,7 @@ pub fn create_index(storage: &super::Storage, index: &Index) -> (Lookup, super::
 
     hdr_buf[0..8].clone_from_slice(&MAGIC[..]);
     write_size(&mut hdr_buf[8..12], bloom_size as u32);
-    write_size(&mut hdr_buf[12..16], 0);
+    write_size(&mut hdr_buf[12..16], INDEX_VERSION);
 
     // write fanout to hdr_buf
     let fanout = {
@@ -204,7 +208,7 @@ pub fn create_index(storage: &super::Storage, index: &Index) -> (Lookup, super::
         }
 
         for i in 0..FANOUT_ELEMENTS {
-            let ofs = FANOUT_OFFSET + i * SIZE_SIZE; /* start at 16, because 0..8 is the magic, followed by size, and padding */
+            let ofs = FANOUT_OFFSET + i * SIZE_SIZE; /* start at 16, because 0..8 is the magic, followed by size, and version */
             write_size(&mut hdr_buf[ofs..ofs+SIZE_SIZE], fanout_incr[i]);
         }
         Fanout(fanout_incr)
@@ -263,6 +267,10 @@ pub fn index_get_header(mut file: &fs::File) -> io::Result<Lookup> {
         return Err(io::Error::last_os_error());
     }
     let bloom_size = read_size(&hdr_buf[8..12]);
+    let version = read_size(&hdr_buf[12..16]);
+    if version > INDEX_VERSION {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("unsupported pack index version {}", version)));
+    }
 
     let mut fanout = [0u32;FANOUT_ELEMENTS];
     for i in 0..FANOUT_ELEMENTS {
@@ -492,6 +500,49 @@ impl RawBufPackWriter {
     }
 }
 
+/// concatenate the blocks of the given packs, in order, into a new pack
+/// and create its index. The merged packs are left in place, it is up
+/// to the caller to retag and remove them.
+///
+/// The first block of every pack must be the child of the last block of
+/// the previous pack, otherwise `Error::PackChainInvalid` is returned
+/// (with the pack, the previous hash of its first block and the expected
+/// one) and nothing is written.
+pub fn merge(storage: &mut super::Storage, packs: &[super::PackHash]) -> super::Result<super::PackHash> {
+    let mut hashes = Vec::with_capacity(packs.len());
+    let mut last : Option<cardano::block::HeaderHash> = None;
+    for packhash in packs {
+        let mut reader = PackReader::init(&storage.config, packhash);
+        let mut pack_hashes = Vec::new();
+        while let Some(block_raw) = reader.get_next() {
+            let header = block_raw.decode()?.get_header();
+            if let (true, Some(last)) = (pack_hashes.is_empty(), last.as_ref()) {
+                if header.get_previous_header() != *last {
+                    return Err(super::Error::PackChainInvalid(*packhash, header.get_previous_header(), last.clone()));
+                }
+            }
+            last = Some(header.compute_hash());
+            pack_hashes.push(header.compute_hash());
+        }
+        hashes.push(pack_hashes);
+    }
+
+    let mut writer = PackWriter::init(&storage.config);
+    for (packhash, pack_hashes) in packs.iter().zip(hashes) {
+        let mut reader = PackReader::init(&storage.config, packhash);
+        for hash in pack_hashes {
+            let block_raw = reader.get_next().unwrap();
+            writer.append(hash.bytes(), block_raw.as_ref());
+        }
+    }
+    let (packhash, index) = writer.finalize();
+
+    let (lookup, tmpfile) = create_index(storage, &index);
+    tmpfile.render_permanent(&storage.config.get_index_filepath(&packhash))?;
+    storage.lookups.insert(packhash, lookup);
+    Ok(packhash)
+}
+
 // A Reader
 pub struct PackReader<R> {
     reader: R,
@@ -1,19 +1,40 @@
-use std::{fs, path::{PathBuf}};
-use std::io::{Write,Read};
+use std::{fs, path::{Path, PathBuf}};
+use std::io::{self, Write, Read};
+use tmpfile::TmpFile;
 use cardano::util::{hex};
 
-use cardano::block;
+use cardano::block::{self, EpochId};
 
 pub const OLDEST_BLOCK : &str = "OLDEST_BLOCK";
 pub const HEAD : &str = "HEAD";
 
-pub fn get_epoch_tag(epoch: u32) -> String {
+pub fn get_epoch_tag(epoch: EpochId) -> String {
     format!("EPOCH_{}", epoch)
 }
 
+/// the `HEAD` tag of the given network, for a storage shared between
+/// several networks (e.g. `mainnet/HEAD`).
+pub fn get_network_head_tag(network: &str) -> String {
+    format!("{}/{}", network, HEAD)
+}
+
+/// write the tag atomically: the content is written in a temporary file
+/// which then replaces the tag. If the process dies before the end of the
+/// write, the previous value of the tag is left untouched.
 pub fn write<S: AsRef<str>>(storage: &super::Storage, name: &S, content: &[u8]) {
-    let mut tmp_file = super::tmpfile_create_type(storage, super::StorageFileType::Tag);
-    tmp_file.write_all(hex::encode(content).as_bytes()).unwrap();
+    write_with(storage, name, |tmp_file| tmp_file.write_all(hex::encode(content).as_bytes())).unwrap()
+}
+
+/// `write`, with the content written into the temporary file by `fill`
+fn write_with<S, F>(storage: &super::Storage, name: &S, fill: F) -> io::Result<()>
+    where S: AsRef<str>
+        , F: FnOnce(&mut TmpFile) -> io::Result<()>
+{
+    let mut tmp_file = TmpFile::create(storage.config.get_filetype_dir(super::StorageFileType::Tag))?;
+    fill(&mut tmp_file)?;
+    // the content must be on disk before the rename, or a crash could
+    // leave an empty tag behind
+    tmp_file.sync()?;
 
     let path = storage.config.get_tag_filepath(name);
     let dir = PathBuf::from(path);
@@ -22,12 +43,12 @@ pub fn write<S: AsRef<str>>(storage: &super::Storage, name: &S, content: &[u8])
         None => {},
         Some(parent) => {
             if parent != storage.config.get_filetype_dir(super::StorageFileType::Tag) {
-                fs::create_dir_all(parent).unwrap()
+                fs::create_dir_all(parent)?
             }
         }
     };
 
-    tmp_file.render_permanent(&storage.config.get_tag_filepath(name)).unwrap();
+    tmp_file.render_permanent(&storage.config.get_tag_filepath(name))
 }
 
 pub fn write_hash<S: AsRef<str>>(storage: &super::Storage, name: &S, content: &block::HeaderHash) {
@@ -58,3 +79,103 @@ pub fn remove_tag<S: AsRef<str>>(storage: &super::Storage, name: &S) {
     let p = storage.config.get_tag_filepath(name);
     fs::remove_file(p).unwrap()
 }
+
+/// list the names of all the tags of the storage, in alphabetical order.
+///
+/// Tags written with a path (e.g. `wallet/HEAD`) are listed with their
+/// full name. Temporary files of unfinished writes are ignored.
+pub fn list(storage: &super::Storage) -> Vec<String> {
+    fn list_dir(dir: &Path, prefix: &str, tags: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let entry = entry.unwrap();
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if name.starts_with(".") { continue; }
+            let name = format!("{}{}", prefix, name);
+            let file_type = entry.file_type().unwrap();
+            if file_type.is_dir() {
+                list_dir(&entry.path(), &format!("{}/", name), tags);
+            } else if file_type.is_file() {
+                tags.push(name);
+            }
+        }
+    }
+
+    let mut tags = Vec::new();
+    list_dir(&storage.config.get_filetype_dir(super::StorageFileType::Tag), "", &mut tags);
+    tags.sort();
+    tags
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+    use config::StorageConfig;
+    use testing::temporary_storage;
+
+    #[test]
+    fn epoch_tag_format() {
+        assert_eq!(get_epoch_tag(EpochId::new(0)), "EPOCH_0");
+        assert_eq!(get_epoch_tag(EpochId::new(42)), "EPOCH_42");
+        assert_eq!(get_epoch_tag(EpochId::new(41) + 1), format!("EPOCH_{}", 42u32));
+    }
+
+    #[test]
+    fn epoch_dir_format() {
+        let config = StorageConfig::new(&PathBuf::from("storage"));
+        let mut expected = config.get_filetype_dir(::types::StorageFileType::Epoch);
+        expected.push("42");
+        assert_eq!(config.get_epoch_dir(EpochId::new(42)), expected);
+    }
+
+    #[test]
+    fn list_tags() {
+        let storage = temporary_storage();
+        assert!(list(&storage).is_empty());
+
+        write(&storage, &HEAD, &[1,2,3]);
+        write(&storage, &get_epoch_tag(EpochId::new(1)), &[4]);
+        write(&storage, &get_epoch_tag(EpochId::new(0)), &[5]);
+        write(&storage, &"wallet/HEAD", &[6]);
+
+        assert_eq!(list(&storage), vec!["EPOCH_0", "EPOCH_1", "HEAD", "wallet/HEAD"]);
+
+        remove_tag(&storage, &HEAD);
+        assert_eq!(list(&storage), vec!["EPOCH_0", "EPOCH_1", "wallet/HEAD"]);
+    }
+    #[test]
+    fn network_head_tags() {
+        let storage = temporary_storage();
+        assert_eq!(get_network_head_tag("mainnet"), "mainnet/HEAD");
+
+        write(&storage, &get_network_head_tag("mainnet"), &[1,2,3]);
+        write(&storage, &get_network_head_tag("testnet"), &[4,5,6]);
+
+        assert_eq!(read(&storage, &get_network_head_tag("mainnet")), Some(vec![1,2,3]));
+        assert_eq!(read(&storage, &get_network_head_tag("testnet")), Some(vec![4,5,6]));
+        assert!(!exist(&storage, &HEAD));
+        assert_eq!(list(&storage), vec!["mainnet/HEAD", "testnet/HEAD"]);
+    }
+    #[test]
+    fn interrupted_write_keeps_the_previous_value() {
+        let storage = temporary_storage();
+        write(&storage, &HEAD, &[1,2,3]);
+
+        // a write failing half way through the content: the temporary
+        // file is never made permanent
+        let interrupted = write_with(&storage, &HEAD, |tmp_file| {
+            tmp_file.write_all(&hex::encode(&[4,5,6]).as_bytes()[..3])?;
+            Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted write"))
+        });
+        assert_eq!(interrupted.unwrap_err().kind(), io::ErrorKind::Interrupted);
+
+        assert_eq!(read(&storage, &HEAD), Some(vec![1,2,3]));
+        assert_eq!(list(&storage), vec!["HEAD"]);
+
+        write(&storage, &HEAD, &[4,5,6]);
+        assert_eq!(read(&storage, &HEAD), Some(vec![4,5,6]));
+    }
+}
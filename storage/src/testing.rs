@@ -0,0 +1,16 @@
+//! Storage fixtures for the tests
+//!
+//! Available to the tests of this crate, and to the crates depending on it
+//! with the `testing` feature.
+
+use std::env;
+use rand;
+use config::StorageConfig;
+use Storage;
+
+/// a new storage in a random directory of the system's temporary directory.
+/// The caller removes it once done.
+pub fn temporary_storage() -> Storage {
+    let path = env::temp_dir().join(format!("storage-test-{}", rand::random::<u64>()));
+    Storage::init(&StorageConfig::new(&path)).unwrap()
+}
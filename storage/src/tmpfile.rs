@@ -29,6 +29,11 @@ impl TmpFile {
             .map(|file| TmpFile { file: file, path: path })
     }
 
+    /// make sure the content written so far has reached the disk
+    pub fn sync(&self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+
     pub fn render_permanent(&self, path: &PathBuf) -> io::Result<()> {
         // NOTE: we need to consider what is being written, in a case of a tag we want rename
         // to error out correctly in every cases rename fail, however in a case of a hash, since the hash is suppose
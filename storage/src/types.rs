@@ -19,4 +19,5 @@ pub enum StorageFileType {
     Tag,
     RefPack,
     Epoch,
+    Header,
 }
@@ -2,7 +2,7 @@ use cardano::{address::{ExtendedAddr}};
 use cardano::util::base58;
 use command::{HasCommand};
 use clap::{ArgMatches, Arg, App};
-use cardano::block::{Block};
+use cardano::block::{Block, EpochId};
 use cbor_event::de::RawCbor;
 
 use super::util;
@@ -29,7 +29,7 @@ impl HasCommand for FindAddress {
         for address in addresses_bytes {
             addresses.push(RawCbor::from(&address).deserialize().unwrap());
         }
-        let mut iter = storage.iterate_from_epoch(0).unwrap();
+        let mut iter = storage.iterate_from_epoch(EpochId::new(0)).unwrap();
         while let Some(blk) = iter.next_block().unwrap() {
             let hdr = blk.get_header();
             let blk_hash = hdr.compute_hash();
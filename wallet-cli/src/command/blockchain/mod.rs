@@ -2,16 +2,17 @@ use cardano::{util::{hex}};
 use command::{HasCommand};
 use clap::{ArgMatches, Arg, SubCommand, App};
 use storage;
-use storage::{blob, tag, Storage};
+use storage::{tag, Storage};
 use storage::types::{PackHash};
 use storage::{pack_blobs, block_location, block_read_location, pack, PackParameters};
 //use storage::tag::{HEAD};
 use cardano::block;
+use cardano::config::{ProtocolMagic};
 use config::{Config};
 use std::io::{Write, stdout};
 use cbor_event::de::RawCbor;
 
-use exe_common::{config::{net}, network::{api::{*}}, sync};
+use exe_common::{config::{net}, network::{self, api::{*}}, sync};
 
 use command::pretty::Pretty;
 
@@ -35,7 +36,21 @@ impl HasCommand for Blockchain {
                 .about("create a new blockchain, blockchain that can be shared between wallets and work independently from the wallet.")
                 .arg(Arg::with_name("template")
                         .long("template").help("the template for the new blockchain").required(false)
-                        .possible_values(&["mainnet", "testnet"]).default_value("mainnet"))
+                        .possible_values(&["mainnet", "testnet", "custom"]).default_value("mainnet"))
+                .arg(Arg::with_name("genesis")
+                        .long("genesis").value_name("HASH").takes_value(true).required_if("template", "custom")
+                        .validator(validate_header_hash)
+                        .help("hexadecimal encoded hash of the genesis block of the custom blockchain"))
+                .arg(Arg::with_name("genesis-prev")
+                        .long("genesis-prev").value_name("HASH").takes_value(true).required_if("template", "custom")
+                        .validator(validate_header_hash)
+                        .help("hexadecimal encoded hash of the genesis data of the custom blockchain (the genesis block's previous hash)"))
+                .arg(Arg::with_name("magic")
+                        .long("magic").value_name("N").takes_value(true).required_if("template", "custom")
+                        .help("protocol magic of the custom blockchain"))
+                .arg(Arg::with_name("epoch-start")
+                        .long("epoch-start").value_name("N").takes_value(true).default_value("0")
+                        .help("first epoch of the custom blockchain"))
                 .arg(blockchain_name_arg(1))
             )
             .subcommand(SubCommand::with_name("get-tip")
@@ -53,6 +68,11 @@ impl HasCommand for Blockchain {
                 .about("get the next block repeatedly (deprecated will be replaced soon).")
                 .arg(blockchain_name_arg(1))
                 .arg(Arg::with_name("native").long("native").help("use native protocol rather than HTTP"))
+                .arg(Arg::with_name("dry-run").long("dry-run").help("download and check the blocks but do not write anything, report what would be written instead"))
+                .arg(Arg::with_name("from-epoch").long("from-epoch").value_name("A").takes_value(true).requires("to-epoch").conflicts_with("dry-run")
+                        .help("only download and pack the epochs A to B, A must be the first epoch or follow an already packed epoch"))
+                .arg(Arg::with_name("to-epoch").long("to-epoch").value_name("B").takes_value(true).requires("from-epoch")
+                        .help("last epoch to download with --from-epoch"))
             )
             .subcommand(SubCommand::with_name("cat")
                 .about("show content of a block")
@@ -126,10 +146,21 @@ impl HasCommand for Blockchain {
                 let net_cfg = match value_t!(opts.value_of("template"), String).unwrap().as_str() {
                     "mainnet" => { net::Config::mainnet() },
                     "testnet" => { net::Config::testnet() },
+                    "custom"  => {
+                        // the hashes are checked by clap (validator)
+                        let genesis = block::HeaderHash::from_hex(&opts.value_of("genesis").unwrap()).unwrap();
+                        let genesis_prev = block::HeaderHash::from_hex(&opts.value_of("genesis-prev").unwrap()).unwrap();
+                        let magic = value_t!(opts.value_of("magic"), u32).unwrap_or_else(|e| e.exit());
+                        let epoch_start = value_t!(opts.value_of("epoch-start"), block::EpochId).unwrap_or_else(|e| e.exit());
+                        net::Config::custom(
+                            genesis,
+                            genesis_prev,
+                            ProtocolMagic::new(magic),
+                            epoch_start
+                        )
+                    },
                     _         => {
-                        // we do not support custom template yet.
-                        // in the mean while the error is handled by clap
-                        // (possible_values)
+                        // the error is handled by clap (possible_values)
                         panic!("invalid template option")
                     }
                 };
@@ -154,23 +185,47 @@ impl HasCommand for Blockchain {
                 let hh = block::HeaderHash::from_slice(&hh_bytes).expect("blockid invalid");
                 let netcfg_file = config.get_storage_config().get_config_file();
                 let net_cfg = net::Config::from_file(&netcfg_file).expect("no network config present");
-                let b = sync::get_peer(&config.network, &net_cfg, opts.is_present("native"))
-                    .get_block(&hh).unwrap().decode().unwrap();
-                println!("got block: {} {}", b.get_header().get_blockdate(), b);
                 let storage = config.get_storage().unwrap();
-                blob::write(&storage, hh.bytes(), &cbor!(&b).unwrap()).unwrap();
+                let b = match sync::get_block(&storage, &hh, || {
+                    sync::get_peer(&config.network, &net_cfg, opts.is_present("native"))
+                }) {
+                    Ok(b) => b.decode().unwrap(),
+                    Err(network::Error::BlockNotFound(hash)) => {
+                        eprintln!("block not found: {}", hash);
+                        ::std::process::exit(1)
+                    },
+                    Err(err) => panic!("cannot get the block {}: {:?}", hh, err),
+                };
+                println!("got block: {} {}", b.get_header().get_blockdate(), b);
             },
             ("sync", Some(opts)) => {
                 let config = resolv_network_by_name(&opts);
                 let netcfg_file = config.get_storage_config().get_config_file();
                 let net_cfg = net::Config::from_file(&netcfg_file).expect("no network config present");
-                sync::net_sync(
-                    &mut sync::get_peer(
-                        &config.network,
-                        &net_cfg,
-                        opts.is_present("native")),
-                    &net_cfg, &config.get_storage().unwrap(), true)
-                    .expect("While synchronizing")
+                let mut peer = sync::get_peer(&config.network, &net_cfg, opts.is_present("native"));
+                let mut storage = config.get_storage().unwrap();
+                storage.enable_block_cache(storage::DEFAULT_BLOCK_CACHE_CAPACITY);
+                if opts.is_present("from-epoch") {
+                    let from = value_t!(opts.value_of("from-epoch"), block::EpochId).unwrap_or_else(|e| e.exit());
+                    let to = value_t!(opts.value_of("to-epoch"), block::EpochId).unwrap_or_else(|e| e.exit());
+                    sync::net_sync_epochs(&mut peer, &net_cfg, &storage, from, to)
+                        .expect("While synchronizing")
+                } else if opts.is_present("dry-run") {
+                    let report = sync::net_sync_dry_run(&mut peer, &net_cfg, &config.network, &storage)
+                        .expect("While synchronizing");
+                    println!("blocks downloaded: {}", report.blocks);
+                    for epoch in report.epochs.iter() {
+                        println!("would pack epoch:  {}", epoch);
+                    }
+                    println!("would store blobs: {}", report.blobs);
+                    match report.head {
+                        None       => println!("HEAD unchanged"),
+                        Some(head) => println!("would set HEAD:    {}", head),
+                    }
+                } else {
+                    sync::net_sync(&mut peer, &net_cfg, &config.network, &storage, true)
+                        .expect("While synchronizing")
+                }
             },
             ("debug-index", Some(opts)) => {
                 let config = resolv_network_by_name(&opts);
@@ -281,7 +336,7 @@ impl HasCommand for Blockchain {
             ("epoch-from-pack", Some(opts)) => {
                 let config = resolv_network_by_name(&opts);
                 let storage = config.get_storage_config();
-                let epoch = value_t!(opts.value_of("epoch"), u32).unwrap();
+                let epoch = value_t!(opts.value_of("epoch"), block::EpochId).unwrap();
                 let packrefhex = opts.value_of("packhash").and_then(|s| Some(s.to_string())).unwrap();
                 storage::epoch::epoch_create(&storage, &packref_fromhex(&packrefhex), epoch);
                 println!("epoch {} successfuly created", epoch);
@@ -348,9 +403,10 @@ impl HasCommand for Blockchain {
                 let net_cfg = net::Config::from_file(&netcfg_file).expect("no network config present");
                 let mut prev = None;
 
-                let mut iter = storage.reverse_iter().unwrap();
+                let mut iter = storage.reverse_iter(&config.network, &net_cfg.genesis_prev).unwrap();
 
                 while let Some(blk) = iter.next() {
+                    let blk = blk.unwrap();
                     let hdr = blk.get_header();
                     let date = hdr.get_blockdate();
                     let hash = hdr.compute_hash();
@@ -457,6 +513,10 @@ fn pack_is_epoch(config: &Config,
     }
 }
 
+fn validate_header_hash(hash: String) -> Result<(), String> {
+    block::HeaderHash::from_hex(&hash).map(|_| ()).map_err(|err| format!("invalid hash {}: {}", hash, err))
+}
+
 fn packref_fromhex(s: &String) -> PackHash {
     let mut packref = [0u8;32];
     packref.clone_from_slice(&hex::decode(&s).unwrap()[..]);
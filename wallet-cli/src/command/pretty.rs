@@ -301,7 +301,6 @@ impl Pretty for types::SlotId {
     }
 }
 
-// XXX: EpochId is only a type alias; make sure this instance isn't used for any u32
 impl Pretty for types::EpochId {
     fn to_pretty(&self) -> Val {
         Val::BlockDate(BlockDate::Genesis(*self))
@@ -73,16 +73,23 @@ pub struct Config {
     /// epoch when the wallet was created. this affect recovery
     /// and the safe default is 0, where we don't skip any epoch
     pub epoch_start: u32,
+
+    /// number of unused addresses to look ahead of the last used one
+    /// when looking for the wallet's addresses in the blockchain
+    #[serde(default = "default_gap_limit")]
+    pub gap_limit: u32,
 }
+fn default_gap_limit() -> u32 { cardano::config::DEFAULT_GAP_LIMIT }
 impl Config {
     /// construct a wallet configuration from the given wallet and blockchain name
     ///
-    pub fn from_wallet<P: Into<PathBuf>>(wallet: bip44::Wallet, blockchain: P, selection_policy: SelectionPolicy, epoch_start: Option<u32>) -> Self {
+    pub fn from_wallet<P: Into<PathBuf>>(wallet: bip44::Wallet, blockchain: P, selection_policy: SelectionPolicy, epoch_start: Option<u32>, gap_limit: Option<u32>) -> Self {
         Config {
             blockchain: blockchain.into(),
             selection_fee_policy: selection_policy,
             cached_root_key: (**wallet).clone(),
             epoch_start: epoch_start.unwrap_or(0),
+            gap_limit: gap_limit.unwrap_or_else(default_gap_limit),
         }
     }
 
@@ -90,11 +97,11 @@ impl Config {
     pub fn blockchain_config(&self) -> Result<net::Config> {
         let path = ariadne_path()?.join("networks").join(&self.blockchain).join("config.yml");
         match net::Config::from_file(path.clone()) {
-            None => {
-                error!("error with blockchain config file {:?}", path);
+            Err(err) => {
+                error!("error with blockchain config file {:?}: {}", path, err);
                 Err(Error::BlockchainConfigError("unable to parse wallet config file"))
             },
-            Some(cfg) => Ok(cfg)
+            Ok(cfg) => Ok(cfg)
         }
     }
 
@@ -108,10 +115,16 @@ impl Config {
         Ok(storage::Storage::init(&self.blockchain_storage_config()?)?)
     }
 
+    /// the cryptographic configuration of the wallet
+    pub fn wallet_config(&self) -> Result<cardano::config::Config> {
+        let blockchain_config = self.blockchain_config()?;
+        let mut config = cardano::config::Config::new(blockchain_config.protocol_magic);
+        config.gap_limit = self.gap_limit;
+        Ok(config)
+    }
+
     /// construct the wallet object from the wallet configuration
     pub fn wallet(&self) -> Result<bip44::Wallet> {
-        let blockchain_config = self.blockchain_config()?;
-        let wallet_cfg = cardano::config::Config::new(blockchain_config.protocol_magic);
         let cached_key = RootLevel::from(self.cached_root_key.clone());
 
         // TODO: derivation scheme to be set in the config file
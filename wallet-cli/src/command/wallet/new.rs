@@ -53,6 +53,13 @@ impl HasCommand for CommandNewWallet {
                 .help("set the epoch where this wallet was created. if the option is not set then, the network associated with the blockchain is queries and the current stable epoch is set as the start")
                 .required(false)
             )
+            .arg(Arg::with_name("GAP LIMIT")
+                .long("--gap-limit")
+                .takes_value(true)
+                .value_name("GAP_LIMIT")
+                .help("set the number of unused addresses to look ahead of the last used one when looking for the wallet's addresses. if this is not set, the default of 20 is used")
+                .required(false)
+            )
             .arg(Arg::with_name("WALLET NAME").help("the name of the new wallet").index(1).required(true))
             .arg(Arg::with_name("BLOCKCHAIN").help("the name of the associated blockchain (see command `blockchain')").index(2).required(true))
     }
@@ -63,6 +70,7 @@ impl HasCommand for CommandNewWallet {
         let mnemonic_sz = value_t!(args.value_of("MNEMONIC SIZE"), bip39::Type).unwrap();
         let password    = value_t!(args.value_of("PASSWORD"), String).ok();
         let epoch_start = value_t!(args.value_of("EPOCH START"), u32).ok();
+        let gap_limit   = value_t!(args.value_of("GAP LIMIT"), u32).ok();
         let without_paper_wallet = args.is_present("NO PAPER WALLET");
         let seed = generate_entropy(language, password, mnemonic_sz, without_paper_wallet);
 
@@ -71,7 +79,7 @@ impl HasCommand for CommandNewWallet {
 
         // TODO, shall we have a default for the fee selection policy?
         let selection_policy = Default::default();
-        let config = config::Config::from_wallet(wallet, blockchain, selection_policy, epoch_start);
+        let config = config::Config::from_wallet(wallet, blockchain, selection_policy, epoch_start, gap_limit);
 
         config.to_file(&name).unwrap();
     }
@@ -44,6 +44,13 @@ impl HasCommand for Recover {
                 .help("set the epoch where this wallet was created. if this is not set, the default epoch of 0 is assumed")
                 .required(false)
             )
+            .arg(Arg::with_name("GAP LIMIT")
+                .long("--gap-limit")
+                .takes_value(true)
+                .value_name("GAP_LIMIT")
+                .help("set the number of unused addresses to look ahead of the last used one when looking for the wallet's addresses. if this is not set, the default of 20 is used")
+                .required(false)
+            )
             .arg(Arg::with_name("WALLET NAME").help("the name of the new wallet").index(1).required(true))
             .arg(Arg::with_name("BLOCKCHAIN").help("the name of the associated blockchain (see command `blockchain')").index(2).required(true))
     }
@@ -53,6 +60,7 @@ impl HasCommand for Recover {
         let language    = value_t!(args.value_of("LANGUAGE"), String).unwrap(); // we have a default value
         let password    = value_t!(args.value_of("PASSWORD"), String).ok();
         let epoch_start = value_t!(args.value_of("EPOCH START"), u32).ok();
+        let gap_limit   = value_t!(args.value_of("GAP LIMIT"), u32).ok();
         let from_paper_wallet = args.is_present("FROM PAPER WALLET");
         let seed = if from_paper_wallet {
             recover_paperwallet(language, password)
@@ -63,7 +71,7 @@ impl HasCommand for Recover {
         let wallet = bip44::Wallet::from_bip39_seed(&seed, Default::default());
 
         // TODO, shall we have a default for the fee selection policy?
-        let config = config::Config::from_wallet(wallet, blockchain, Default::default(), epoch_start);
+        let config = config::Config::from_wallet(wallet, blockchain, Default::default(), epoch_start, gap_limit);
 
         config.to_file(&name).unwrap();
     }
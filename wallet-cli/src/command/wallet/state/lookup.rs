@@ -1,6 +1,6 @@
 use std::{result, fmt, path::{Path, PathBuf}};
 use std::collections::BTreeMap;
-use cardano::block::{Block, BlockDate, HeaderHash};
+use cardano::block::{Block, BlockDate, EpochId, HeaderHash};
 use cardano::hdwallet;
 use cardano::hdpayload;
 use cardano::bip::bip44;
@@ -100,7 +100,7 @@ impl StatePtr {
         if let Some(ref date) = self.latest_addr {
             date.clone()
         } else {
-            BlockDate::Genesis(0)
+            BlockDate::Genesis(EpochId::new(0))
         }
     }
 }
@@ -5,7 +5,7 @@ pub mod sequentialindex;
 pub mod accum;
 pub mod log;
 
-use cardano::block::{BlockDate};
+use cardano::block::{BlockDate, EpochId};
 use command::{HasCommand};
 use clap::{ArgMatches, Arg, App};
 
@@ -53,7 +53,8 @@ impl HasCommand for Update {
         //
         // i.e. we need to know if it is a bip44 or a random address method
         //      for now we assume a bip44 sequential indexing
-        let mut lookup_structure = sequentialindex::SequentialBip44Lookup::new(wallet_cfg.wallet().unwrap());
+        let gap_limit = wallet_cfg.wallet_config().unwrap().gap_limit;
+        let mut lookup_structure = sequentialindex::SequentialBip44Lookup::new(wallet_cfg.wallet().unwrap(), gap_limit);
         for _ in accounts.iter() {
            lookup_structure.prepare_next_account().unwrap();
         }
@@ -77,7 +78,7 @@ impl HasCommand for Update {
         info!("  from block- {}", latest_block_date);
         info!("  known utxos {:?}", state.utxos);
         debug!("epoch_start: {:?}, slot_start: {:?}", epoch_start, slot_start);
-        if slot_start.is_some() || epoch_start > 0 {
+        if slot_start.is_some() || epoch_start > EpochId::new(0) {
             while let Some(blk) = iter.next_block().unwrap() {
                 let hdr = blk.get_header();
                 debug!("skipping: {}", hdr.get_blockdate());
@@ -20,21 +20,22 @@ pub struct SequentialBip44Lookup {
 }
 
 fn wallet_get_address(wallet: &bip44::Wallet, addr: &bip44::Addressing) -> ExtendedAddr {
-    let xprv = wallet.account(wallet.derivation_scheme(), addr.account.get_scheme_value())
-                    .change(wallet.derivation_scheme(), addr.address_type())
-                    .index(wallet.derivation_scheme(), addr.index.get_scheme_value());
+    let xprv = wallet.account(wallet.derivation_scheme(), addr.account.get_account_number())
+                    .and_then(|account| account.change(wallet.derivation_scheme(), addr.address_type())
+                                               .index(wallet.derivation_scheme(), addr.index.get_scheme_value()))
+                    .expect("cannot fail, the addressing is soft");
     let xpub = xprv.public();
     let a = ExtendedAddr::new_simple(*xpub);
     a
 }
 
 impl SequentialBip44Lookup {
-    pub fn new(wallet: bip44::Wallet) -> Self {
+    pub fn new(wallet: bip44::Wallet, gap_limit: u32) -> Self {
         SequentialBip44Lookup {
             wallet: wallet,
             expected: BTreeMap::new(),
             accounts: Vec::new(),
-            gap_limit: 20,
+            gap_limit: gap_limit,
         }
     }
 